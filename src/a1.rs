@@ -0,0 +1,278 @@
+use crate::limits::MAX_COLUMNS;
+use std::fmt;
+
+// A1 notation helpers
+
+// 0 -> A, 25 -> Z, 26 -> AA
+pub fn column_letter(mut index: usize) -> String {
+    let mut letters = Vec::new();
+    loop {
+        letters.push((b'A' + (index % 26) as u8) as char);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    letters.iter().rev().collect()
+}
+
+// A -> 0, AB -> 27; None for anything that isn't letters or is past ZZZ
+pub fn column_index(letters: &str) -> Option<usize> {
+    if letters.is_empty() || !letters.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let n = letters
+        .to_ascii_uppercase()
+        .bytes()
+        .try_fold(0usize, |acc, b| acc.checked_mul(26)?.checked_add((b - b'A' + 1) as usize))?;
+    (n <= MAX_COLUMNS).then(|| n - 1)
+}
+
+// Zero-based column and one-based row of the top-left cell of "Tab!C5:F9";
+// (0, 1) for a bare tab name
+pub fn range_start(range: &str) -> (usize, usize) {
+    let start = A1Range::parse(range).ok().and_then(|range| range.start);
+    start.map_or((0, 1), |corner| (corner.column.unwrap_or(0), corner.row.unwrap_or(1)))
+}
+
+// Number of columns "Tab!A2:J" spans (10); None when the range doesn't bound
+// its columns, e.g. a bare tab name or "Tab!2:5"
+pub fn range_width(range: &str) -> Option<usize> {
+    A1Range::parse(range).ok()?.width()
+}
+
+// Tab name of "'My Tab'!A1:B2" (unquoted, '' unescaped); None when the range has no tab
+pub fn range_sheet(range: &str) -> Option<String> {
+    let (sheet, _) = range.rsplit_once('!')?;
+    Some(unquote(sheet))
+}
+
+fn unquote(sheet: &str) -> String {
+    match sheet.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')) {
+        Some(quoted) => quoted.replace("''", "'"),
+        None => sheet.to_string(),
+    }
+}
+
+// Percent-encode a range for a /values/{range} URL path. Letters, digits and
+// the punctuation of A1 notation (! : ' $) stay readable; spaces, '#', '?',
+// '/', '%' and non-ASCII bytes are escaped.
+pub fn encode_range(range: &str) -> String {
+    let mut out = String::with_capacity(range.len());
+    for byte in range.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'!' | b':' | b'\'' | b'$' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+// One corner of a range; either part may be open ("A" is a whole column,
+// "5" a whole row). Columns are 0-based, rows 1-based as in the sheet.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Corner {
+    column: Option<usize>,
+    row: Option<usize>,
+}
+
+impl Corner {
+    fn parse(cell: &str) -> Option<Self> {
+        let cell = cell.trim().replace('$', "");
+        let letters: String = cell.chars().take_while(char::is_ascii_alphabetic).collect();
+        let digits = &cell[letters.len()..];
+        // "Sheet" or "Log1" is a word, not a column
+        if letters.chars().any(|c| c.is_ascii_lowercase()) && letters.chars().any(|c| c.is_ascii_uppercase()) {
+            return None;
+        }
+        let corner = Corner {
+            column: if letters.is_empty() { None } else { Some(column_index(&letters)?) },
+            row: if digits.is_empty() { None } else { Some(digits.parse().ok().filter(|&row| row > 0)?) },
+        };
+        (corner != Corner::default()).then_some(corner)
+    }
+}
+
+impl fmt::Display for Corner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(column) = self.column {
+            f.write_str(&column_letter(column))?;
+        }
+        if let Some(row) = self.row {
+            write!(f, "{}", row)?;
+        }
+        Ok(())
+    }
+}
+
+// A range built from parts instead of format!, so tab names are always
+// quoted and URLs always encoded:
+//
+//   A1Range::sheet("RETURNS MAIN")                   -> 'RETURNS MAIN'
+//   A1Range::sheet("Log").cell(0, 2).to(27, 10)      -> 'Log'!A2:AB10
+//   A1Range::sheet("Log").cell(0, 2).to_column(9)    -> 'Log'!A2:J (open-ended rows)
+//   A1Range::sheet("Log").rows(5, 5)                 -> 'Log'!5:5
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct A1Range {
+    sheet: Option<String>,
+    start: Option<Corner>,
+    end: Option<Corner>,
+}
+
+impl A1Range {
+    // The whole tab
+    pub fn sheet(name: &str) -> Self {
+        A1Range { sheet: Some(name.to_string()), ..Default::default() }
+    }
+
+    // Top-left cell: 0-based column, 1-based row
+    pub fn cell(mut self, column: usize, row: usize) -> Self {
+        self.start = Some(Corner { column: Some(column), row: Some(row.max(1)) });
+        self
+    }
+
+    // Bottom-right cell
+    pub fn to(mut self, column: usize, row: usize) -> Self {
+        self.end = Some(Corner { column: Some(column), row: Some(row.max(1)) });
+        self
+    }
+
+    // Bottom-right column with no last row: everything from the start down
+    pub fn to_column(mut self, column: usize) -> Self {
+        self.end = Some(Corner { column: Some(column), row: None });
+        self
+    }
+
+    // Whole columns, e.g. columns(0, 9) -> A:J
+    pub fn columns(mut self, first: usize, last: usize) -> Self {
+        self.start = Some(Corner { column: Some(first), row: None });
+        self.end = Some(Corner { column: Some(last), row: None });
+        self
+    }
+
+    // Whole rows, e.g. rows(2, 5) -> 2:5
+    pub fn rows(mut self, first: usize, last: usize) -> Self {
+        self.start = Some(Corner { column: None, row: Some(first.max(1)) });
+        self.end = Some(Corner { column: None, row: Some(last.max(1)) });
+        self
+    }
+
+    // "Tab!A1:B2", "'My Tab'!A:C", "A2:J" or a bare tab name. Without a '!',
+    // text is a range only if it's a single cell or two corners ("B5", "A:C",
+    // "2:5"); anything else ("Sheet1", "Returns", "Log") is a tab name, kept
+    // as typed.
+    pub fn parse(range: &str) -> Result<Self, String> {
+        let corners = |cells: &str| match cells.split_once(':') {
+            Some((first, last)) => Some((Corner::parse(first)?, Some(Corner::parse(last)?))),
+            None => Some((Corner::parse(cells)?, None)),
+        };
+        match range.rsplit_once('!') {
+            Some((sheet, cells)) => {
+                let (start, end) = corners(cells).ok_or_else(|| format!("'{}' is not an A1 range", range))?;
+                Ok(A1Range { sheet: Some(unquote(sheet)), start: Some(start), end })
+            }
+            None => match corners(range).filter(|(start, end)| end.is_some() || (start.column.is_some() && start.row.is_some())) {
+                Some((start, end)) => Ok(A1Range { sheet: None, start: Some(start), end }),
+                None if range.trim().is_empty() => Err("empty range".to_string()),
+                None => Ok(A1Range::sheet(&unquote(range.trim()))),
+            },
+        }
+    }
+
+    pub fn sheet_name(&self) -> Option<&str> {
+        self.sheet.as_deref()
+    }
+
+    // Columns spanned, when both ends bound them
+    pub fn width(&self) -> Option<usize> {
+        let first = self.start?.column?;
+        let last = self.end.map_or(Some(first), |end| end.column)?;
+        Some(last.max(first) - last.min(first) + 1)
+    }
+
+    // Ready for a /values/{range} URL path
+    pub fn encoded(&self) -> String {
+        encode_range(&self.to_string())
+    }
+}
+
+impl fmt::Display for A1Range {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(sheet) = &self.sheet {
+            write!(f, "'{}'", sheet.replace('\'', "''"))?;
+            if self.start.is_some() {
+                f.write_str("!")?;
+            }
+        }
+        if let Some(start) = self.start {
+            write!(f, "{}", start)?;
+            if let Some(end) = self.end {
+                write!(f, ":{}", end)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn column_letters_round_trip() {
+        for (index, letters) in [(0, "A"), (25, "Z"), (26, "AA"), (27, "AB"), (701, "ZZ"), (702, "AAA"), (18277, "ZZZ")] {
+            assert_eq!(column_letter(index), letters);
+            assert_eq!(column_index(letters), Some(index));
+        }
+        assert_eq!(column_index("ab"), Some(27));
+    }
+
+    fn round_trip(text: &str) -> String {
+        let range = A1Range::parse(text).unwrap();
+        assert_eq!(A1Range::parse(&range.to_string()).unwrap(), range);
+        range.to_string()
+    }
+
+    #[test]
+    fn parse_round_trips() {
+        assert_eq!(round_trip("'My Tab'!A1:B2"), "'My Tab'!A1:B2");
+        assert_eq!(round_trip("Log!$b$2:c"), "'Log'!B2:C");
+        assert_eq!(round_trip("A:C"), "A:C");
+        assert_eq!(round_trip("2:5"), "2:5");
+        assert_eq!(round_trip("B5"), "B5");
+        assert_eq!(round_trip("'It''s'!A1"), "'It''s'!A1");
+    }
+
+    #[test]
+    fn bare_words_are_tab_names() {
+        for name in ["Sheet1", "Returns", "RETURNS MAIN", "Log", "Reconciliations", "ABCD1"] {
+            let range = A1Range::parse(name).unwrap();
+            assert_eq!(range.sheet_name(), Some(name));
+            assert_eq!(round_trip(name), format!("'{}'", name));
+            assert_eq!(range_start(name), (0, 1));
+            assert_eq!(range_width(name), None);
+        }
+        assert!(A1Range::parse("Log!Sheet1").is_err());
+    }
+
+    #[test]
+    fn start_and_width() {
+        assert_eq!(range_start("Tab!C5:F9"), (2, 5));
+        assert_eq!(range_start("'My Tab'!A:C"), (0, 1));
+        assert_eq!(range_start("Tab!3:4"), (0, 3));
+        assert_eq!(range_width("Tab!A2:J"), Some(10));
+        assert_eq!(range_width("Tab!2:5"), None);
+        assert_eq!(range_sheet("'My Tab'!A1"), Some("My Tab".to_string()));
+    }
+
+    #[test]
+    fn column_index_stops_at_zzz() {
+        assert_eq!(column_index("AAAA"), None);
+        assert_eq!(column_index("Reconciliations"), None);
+        assert_eq!(column_index(&"Z".repeat(40)), None);
+        assert_eq!(column_index(""), None);
+        assert_eq!(column_index("A1"), None);
+    }
+}
@@ -0,0 +1,120 @@
+use crate::numbers::{parse_amount, Amount, NumberLocale};
+use crate::schema::Schema;
+use serde_json::Value;
+use std::cmp::Ordering;
+
+// Totals for one numeric column. With the `decimal` feature, currency sums
+// are exact (no 0.1 + 0.2 drift).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnSummary {
+    pub column: String,
+    pub count: usize,   // Cells that parsed as numbers
+    pub skipped: usize, // Non-empty cells that didn't
+    pub sum: Amount,
+    pub min: Option<Amount>,
+    pub max: Option<Amount>,
+}
+
+impl ColumnSummary {
+    pub fn mean(&self) -> Option<Amount> {
+        if self.count == 0 {
+            return None;
+        }
+        Some(self.sum / Amount::from(self.count as u32))
+    }
+}
+
+// Parse a cell using the column's schema locale
+pub fn cell_amount(schema: &Schema, column: &str, cell: &Value) -> Option<Amount> {
+    amount_in(cell, schema.locale(column))
+}
+
+// Plain numbers plus locale/money formatting: "£1,234.50", "(45.00)", "1.234,56"
+pub fn amount_in(cell: &Value, locale: NumberLocale) -> Option<Amount> {
+    match cell {
+        Value::Number(n) => parse_amount(&n.to_string(), NumberLocale::Dot),
+        Value::String(s) => parse_amount(s, locale),
+        _ => None,
+    }
+}
+
+// Compare a cell to a threshold numerically; None if the cell isn't a number
+pub fn compare_cell(cell: &Value, locale: NumberLocale, threshold: Amount) -> Option<Ordering> {
+    amount_in(cell, locale)?.partial_cmp(&threshold)
+}
+
+// `table` has the header as its first row
+pub fn summarize_column(table: &[Vec<Value>], column: &str, schema: &Schema) -> Result<ColumnSummary, String> {
+    let header = table.first().ok_or("table is empty")?;
+    let index = header
+        .iter()
+        .position(|h| h.as_str().is_some_and(|h| h.trim().eq_ignore_ascii_case(column.trim())))
+        .ok_or_else(|| format!("no column named '{}'", column))?;
+
+    let mut summary = ColumnSummary {
+        column: column.to_string(),
+        count: 0,
+        skipped: 0,
+        sum: Amount::default(),
+        min: None,
+        max: None,
+    };
+    for cell in table[1..].iter().filter_map(|row| row.get(index)) {
+        match cell_amount(schema, column, cell) {
+            Some(n) => {
+                summary.count += 1;
+                summary.sum += n;
+                summary.min = Some(summary.min.map_or(n, |m| if n < m { n } else { m }));
+                summary.max = Some(summary.max.map_or(n, |m| if n > m { n } else { m }));
+            }
+            None if cell.as_str().is_some_and(|s| s.trim().is_empty()) || cell.is_null() => {}
+            None => summary.skipped += 1,
+        }
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numbers::amount_to_f64;
+    use serde_json::json;
+
+    #[test]
+    fn amounts_follow_the_column_locale() {
+        let schema = Schema::parse("PRICE:currency(eu)").unwrap();
+        let price = cell_amount(&schema, "price", &json!("1.234,50 €")).unwrap();
+        assert_eq!(amount_to_f64(price), 1234.5);
+        assert_eq!(cell_amount(&schema, "OTHER", &json!("£1,234.50")).map(amount_to_f64), Some(1234.5));
+        assert_eq!(cell_amount(&schema, "OTHER", &json!(12)).map(amount_to_f64), Some(12.0));
+        assert_eq!(cell_amount(&schema, "OTHER", &json!("n/a")), None);
+    }
+
+    #[test]
+    fn compares_money_text_as_numbers() {
+        let threshold = parse_amount("999", NumberLocale::Auto).unwrap();
+        assert_eq!(compare_cell(&json!("£1,234.50"), NumberLocale::Auto, threshold), Some(Ordering::Greater));
+        assert_eq!(compare_cell(&json!("(5.00)"), NumberLocale::Auto, threshold), Some(Ordering::Less));
+        assert_eq!(compare_cell(&json!("soon"), NumberLocale::Auto, threshold), None);
+    }
+
+    #[test]
+    fn summarizes_a_column() {
+        let table = vec![
+            vec![json!("ITEM"), json!("AMOUNT")],
+            vec![json!("a"), json!("£0.10")],
+            vec![json!("b"), json!("0.20")],
+            vec![json!("c"), json!("")],
+            vec![json!("d"), json!("refund")],
+        ];
+        let summary = summarize_column(&table, "amount", &Schema::default()).unwrap();
+        assert_eq!((summary.count, summary.skipped), (2, 1));
+        assert!((amount_to_f64(summary.sum) - 0.3).abs() < 1e-9);
+        // Exact with Decimal: no 0.30000000000000004
+        #[cfg(feature = "decimal")]
+        assert_eq!(summary.sum.to_string(), "0.30");
+        assert_eq!(summary.min.map(amount_to_f64), Some(0.1));
+        assert_eq!(summary.max.map(amount_to_f64), Some(0.2));
+        assert!(summarize_column(&table, "missing", &Schema::default()).is_err());
+    }
+}
@@ -0,0 +1,3 @@
+// Versioned wrappers over the Google REST APIs. Code outside this module
+// should go through these so retry behaviour stays in one place.
+pub mod v4;
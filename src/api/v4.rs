@@ -0,0 +1,376 @@
+use crate::a1::{encode_range, A1Range};
+use crate::har;
+use crate::policy::{self, Operation};
+use crate::render::RenderOptions;
+use crate::{coalesce, config, confirm, credentials, grid, journal, rate_limit, read_only, summary, trace, SecretString, SheetsError};
+use reqwest::{Client, Method, RequestBuilder, Response, StatusCode};
+use serde_json::{json, Value};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::time::sleep;
+use tracing::Instrument;
+
+pub const BASE_URL: &str = "https://sheets.googleapis.com/v4/";
+
+// Attempts for rate-limited (429) and 5xx responses, with backoff doubling from 1s
+const MAX_ATTEMPTS: u32 = 4;
+
+// POST writes that overwrite rather than add, so sending them twice is harmless
+const REPEATABLE_POSTS: &[&str] = &["/values:batchUpdate", "/values:batchClear", ":clear"];
+
+// POST endpoints that only read
+const READ_POSTS: &[&str] = &[":batchGetByDataFilter", ":getByDataFilter", "developerMetadata:search"];
+
+fn is_write(method: &Method, url: &str) -> bool {
+    let path = url.split('?').next().unwrap_or(url);
+    *method != Method::GET && !(*method == Method::POST && READ_POSTS.iter().any(|suffix| path.ends_with(suffix)))
+}
+
+// A 5xx can arrive after Google applied the write (a timeout behind the
+// frontend), so only requests that are safe to repeat retry on one: reads,
+// PUT, PATCH, DELETE and overwriting values writes. Appends, batchUpdate
+// (inserts, deletes, appendCells) and Drive creates and copies retry on 429
+// only, which Google sends before doing anything; otherwise a retry could
+// add the rows twice.
+fn repeatable(method: &Method, url: &str) -> bool {
+    let path = url.split('?').next().unwrap_or(url);
+    match *method {
+        Method::POST => !is_write(method, url) || REPEATABLE_POSTS.iter().any(|suffix| path.ends_with(suffix)),
+        _ => true,
+    }
+}
+
+fn retryable(status: StatusCode, repeatable: bool) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || (repeatable && status.is_server_error())
+}
+
+// Send a request and decode the JSON reply, retrying throttled responses
+// and, where repeating is safe, 5xx ones. API error payloads become the
+// matching SheetsError.
+pub async fn send(
+    access_token: &SecretString,
+    method: Method,
+    url: &str,
+    body: Option<&Value>,
+) -> Result<Value, SheetsError> {
+    send_with(shared_client(), access_token, method, url, body).await
+}
+
+// One connection pool for the free functions; SheetsClient brings its own
+pub(crate) fn shared_client() -> &'static Client {
+    static CLIENT: OnceLock<Client> = OnceLock::new();
+    CLIENT.get_or_init(Client::new)
+}
+
+pub async fn send_with(
+    client: &Client,
+    access_token: &SecretString,
+    method: Method,
+    url: &str,
+    body: Option<&Value>,
+) -> Result<Value, SheetsError> {
+    if !is_write(&method, url) {
+        return send_once(client, access_token, method, url, body).await;
+    }
+    read_only::guard(&format!("{} {}", method, url.split('?').next().unwrap_or(url)))?;
+    if journal::pending() {
+        // Journaled writes go first; while Google is still out of reach this one joins them
+        let outcome = journal::replay(client, access_token).await?;
+        if outcome.remaining > 0 {
+            return journaled(&method, url, body, None);
+        }
+    }
+    match send_direct(client, access_token, method.clone(), url, body).await {
+        Err(e) if e.is_unreachable() && journal::enabled() => journaled(&method, url, body, Some(e)),
+        result => result,
+    }
+}
+
+// A write without the journal: read-only guard, coalescing, retries
+pub(crate) async fn send_direct(
+    client: &Client,
+    access_token: &SecretString,
+    method: Method,
+    url: &str,
+    body: Option<&Value>,
+) -> Result<Value, SheetsError> {
+    read_only::guard(&format!("{} {}", method, url.split('?').next().unwrap_or(url)))?;
+    // Identical writes within DEDUPE_WINDOW_MS share one call
+    coalesce::coalesce(access_token, &method, url, body, || send_once(client, access_token, method.clone(), url, body)).await
+}
+
+fn journaled(method: &Method, url: &str, body: Option<&Value>, cause: Option<SheetsError>) -> Result<Value, SheetsError> {
+    match journal::queue(method, url, body) {
+        Ok(id) => Err(SheetsError::Journaled { id }),
+        Err(e) => {
+            tracing::warn!("couldn't journal {} {}: {}", method, url.split('?').next().unwrap_or(url), e);
+            Err(cause.unwrap_or_else(|| SheetsError::Parse(format!("journal unwritable: {}", e))))
+        }
+    }
+}
+
+// How every Google API request goes out: tagged with the quota project, after
+// a slot from the shared rate limiter, recorded when HAR output is on. No
+// retries or error decoding; send_with adds those. For replies that aren't
+// JSON (exports) or whose status the caller inspects itself.
+pub(crate) async fn dispatch(request: RequestBuilder) -> reqwest::Result<Response> {
+    let (client, request) = request.build_split();
+    let mut request = request?;
+    credentials::add_quota_project(&mut request);
+    rate_limit::acquire(&request).await;
+    har::execute(&client, request).await
+}
+
+// One logical request: retries included, no coalescing
+async fn send_once(
+    client: &Client,
+    access_token: &SecretString,
+    method: Method,
+    url: &str,
+    body: Option<&Value>,
+) -> Result<Value, SheetsError> {
+    let mut delay = Duration::from_secs(1);
+    let repeatable = repeatable(&method, url);
+    for attempt in 1..=MAX_ATTEMPTS {
+        let (span, traceparent) = trace::request_span(method.as_str(), url);
+        let request = client.request(method.clone(), url).bearer_auth(access_token.expose_secret());
+        let mut request = trace::inject(request, traceparent.as_deref());
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        summary::api_call();
+        let response = dispatch(request).instrument(span.clone()).await?;
+        let status = response.status();
+        trace::record_response(&span, status.as_u16(), response.headers());
+        if retryable(status, repeatable) && attempt < MAX_ATTEMPTS {
+            summary::retry();
+            sleep(delay).await;
+            delay *= 2;
+            continue;
+        }
+        let headers = response.headers().clone();
+        let text = response.text().await?;
+        let value: Value = match serde_json::from_str(&text) {
+            Ok(value) => value,
+            Err(_) if text.trim().is_empty() => json!({}),
+            Err(_) if !status.is_success() => json!({}), // HTML error pages from proxies
+            Err(e) => return Err(e.into()),
+        };
+        let context = format!("{} {}", method, url.split('?').next().unwrap_or(url));
+        if let Some(error) = SheetsError::from_response(status, &headers, &value, &context) {
+            return Err(error);
+        }
+        return Ok(value);
+    }
+    unreachable!("the last attempt always returns")
+}
+
+// Escape hatch for endpoints without a typed wrapper. `path` is relative to
+// BASE_URL ("spreadsheets/{spreadsheetId}/developerMetadata:search"), with
+// {spreadsheetId} filled in from SHEET_ID; a full https:// URL is used as is.
+// The policy can't see which tabs these touch, so writes need the structure
+// operation and no tab restriction.
+pub async fn raw_request(
+    access_token: &SecretString,
+    method: Method,
+    path: &str,
+    body: Option<Value>,
+) -> Result<Value, SheetsError> {
+    let policy = policy::global()?;
+    if is_write(&method, path) {
+        policy.check(Operation::Structure, None)?;
+    } else {
+        policy.check_operation(Operation::Read)?;
+    }
+    let url = if path.starts_with("https://") {
+        path.to_string()
+    } else {
+        let path = path.trim_start_matches('/');
+        let path = if path.contains("{spreadsheetId}") {
+            path.replace("{spreadsheetId}", &config::sheet_id()?)
+        } else {
+            path.to_string()
+        };
+        format!("{}{}", BASE_URL, path)
+    };
+    send(access_token, method, &url, body.as_ref()).await
+}
+
+fn spreadsheet_url(suffix: &str) -> Result<String, SheetsError> {
+    Ok(format!("{}spreadsheets/{}{}", BASE_URL, config::sheet_id()?, suffix))
+}
+
+// spreadsheets.get
+pub async fn get_spreadsheet(access_token: &SecretString, fields: &str) -> Result<Value, SheetsError> {
+    let url = spreadsheet_url(&format!("?fields={}", fields))?;
+    send(access_token, Method::GET, &url, None).await
+}
+
+// spreadsheets.get with includeGridData for one A1 range; `fields` picks the
+// parts of each cell, e.g. "formattedValue,effectiveFormat.backgroundColor"
+pub async fn get_grid_data(access_token: &SecretString, range: &str, fields: &str) -> Result<Value, SheetsError> {
+    policy::global()?.check_range(Operation::Read, range)?;
+    let fields = format!("sheets.data(startRow,startColumn,rowData.values({}))", fields);
+    get_with_grid_data(access_token, &[range], &fields).await
+}
+
+// spreadsheets.get with includeGridData over `ranges` (every tab when empty);
+// `fields` is the whole response mask, so keep it narrow
+pub async fn get_with_grid_data(access_token: &SecretString, ranges: &[&str], fields: &str) -> Result<Value, SheetsError> {
+    let mut params: Vec<(&str, &str)> = ranges.iter().map(|r| ("ranges", *r)).collect();
+    params.extend([("includeGridData", "true"), ("fields", fields)]);
+    let url = reqwest::Url::parse_with_params(&spreadsheet_url("")?, &params).map_err(|e| SheetsError::Parse(e.to_string()))?;
+    send(access_token, Method::GET, url.as_str(), None).await
+}
+
+// spreadsheets.batchUpdate; returns the replies array
+pub async fn batch_update(access_token: &SecretString, requests: Vec<Value>) -> Result<Vec<Value>, SheetsError> {
+    let policy = policy::global()?;
+    let titles = if policy.restricts_sheets() {
+        policy::titles_from(&get_spreadsheet(access_token, "sheets.properties(sheetId,title)").await?)
+    } else {
+        Default::default()
+    };
+    policy.check_requests(&requests, &titles)?;
+    let deleted = policy::deleted_rows_in(&requests);
+    confirm::check("batchUpdate", deleted + duplicates_in(access_token, &requests).await?, false)?;
+    let url = spreadsheet_url(":batchUpdate")?;
+    let response = send(access_token, Method::POST, &url, Some(&json!({ "requests": requests }))).await?;
+    summary::rows_deleted(deleted);
+    Ok(response["replies"].as_array().cloned().unwrap_or_default())
+}
+
+// Rows the deleteDuplicates among `requests` would remove, counted from a
+// read of each tab (Sheets only reports the count afterwards)
+async fn duplicates_in(access_token: &SecretString, requests: &[Value]) -> Result<usize, SheetsError> {
+    let bodies: Vec<&Value> = requests.iter().filter_map(|request| request.get("deleteDuplicates")).collect();
+    if bodies.is_empty() || confirm::is_forced() {
+        return Ok(0);
+    }
+    let titles = policy::titles_from(&get_spreadsheet(access_token, "sheets.properties(sheetId,title)").await?);
+    let mut total = 0;
+    for body in bodies {
+        let gid = body["range"]["sheetId"].as_u64().unwrap_or(0);
+        let title = titles.get(&gid).ok_or_else(|| SheetsError::NotFound(format!("no tab with gid {}", gid)))?;
+        let rows = get_values_with(access_token, &A1Range::sheet(title).to_string(), &RenderOptions::unformatted()).await?;
+        total += grid::duplicate_rows(body, &rows);
+    }
+    Ok(total)
+}
+
+// spreadsheets.values.get
+pub async fn get_values(access_token: &SecretString, range: &str) -> Result<Vec<Vec<Value>>, SheetsError> {
+    get_values_with(access_token, range, &RenderOptions::default()).await
+}
+
+// Like get_values, but formula cells come back as their formula ("=B2*2")
+pub async fn get_formulas(access_token: &SecretString, range: &str) -> Result<Vec<Vec<Value>>, SheetsError> {
+    get_values_with(access_token, range, &RenderOptions::formulas()).await
+}
+
+pub async fn get_values_with(
+    access_token: &SecretString,
+    range: &str,
+    render: &RenderOptions,
+) -> Result<Vec<Vec<Value>>, SheetsError> {
+    policy::global()?.check_range(Operation::Read, range)?;
+    let url = spreadsheet_url(&format!("/values/{}{}", encode_range(range), render))?;
+    let response = send(access_token, Method::GET, &url, None).await?;
+    let rows: Vec<Vec<Value>> = serde_json::from_value(response["values"].clone()).unwrap_or_default();
+    summary::rows_read(rows.len());
+    Ok(rows)
+}
+
+// spreadsheets.values.clear: blanks the values in `range` but keeps the rows,
+// formatting and validation. Returns the range cleared.
+pub async fn clear_values(access_token: &SecretString, range: &str) -> Result<String, SheetsError> {
+    policy::global()?.check_range(Operation::Update, range)?;
+    let url = spreadsheet_url(&format!("/values/{}:clear", encode_range(range)))?;
+    let response = send(access_token, Method::POST, &url, Some(&json!({}))).await?;
+    Ok(response["clearedRange"].as_str().unwrap_or(range).to_string())
+}
+
+// spreadsheets.values.batchClear; the ranges cleared, in order
+pub async fn batch_clear(access_token: &SecretString, ranges: &[String]) -> Result<Vec<String>, SheetsError> {
+    let policy = policy::global()?;
+    for range in ranges {
+        policy.check_range(Operation::Update, range)?;
+    }
+    let url = spreadsheet_url("/values:batchClear")?;
+    let response = send(access_token, Method::POST, &url, Some(&json!({ "ranges": ranges }))).await?;
+    Ok(cleared_ranges(&response))
+}
+
+pub(crate) fn cleared_ranges(response: &Value) -> Vec<String> {
+    response["clearedRanges"].as_array().into_iter().flatten().filter_map(|r| r.as_str().map(str::to_string)).collect()
+}
+
+// spreadsheets.values.batchUpdate with {"range", "values"} entries
+pub async fn values_batch_update(
+    access_token: &SecretString,
+    value_input_option: &str,
+    data: Vec<Value>,
+) -> Result<Value, SheetsError> {
+    let policy = policy::global()?;
+    for entry in &data {
+        policy.check_range(Operation::Update, entry["range"].as_str().unwrap_or_default())?;
+    }
+    let url = spreadsheet_url("/values:batchUpdate")?;
+    let body = json!({ "valueInputOption": value_input_option, "data": data });
+    let response = send(access_token, Method::POST, &url, Some(&body)).await?;
+    summary::rows_written(response["totalUpdatedRows"].as_u64().unwrap_or(0) as usize);
+    Ok(response)
+}
+
+// spreadsheets.values.batchGet; one Vec of rows per requested range, in order
+pub async fn batch_get(access_token: &SecretString, ranges: &[String]) -> Result<Vec<Vec<Vec<Value>>>, SheetsError> {
+    batch_get_with(access_token, ranges, &RenderOptions::default()).await
+}
+
+pub async fn batch_get_with(
+    access_token: &SecretString,
+    ranges: &[String],
+    render: &RenderOptions,
+) -> Result<Vec<Vec<Vec<Value>>>, SheetsError> {
+    let policy = policy::global()?;
+    for range in ranges {
+        policy.check_range(Operation::Read, range)?;
+    }
+    let mut params: Vec<(&str, &str)> = ranges.iter().map(|r| ("ranges", r.as_str())).collect();
+    params.push(("majorDimension", "ROWS"));
+    params.extend(render.params());
+    let url = reqwest::Url::parse_with_params(&spreadsheet_url("/values:batchGet")?, &params)
+        .map_err(|e| SheetsError::Parse(e.to_string()))?;
+    let response = send(access_token, Method::GET, url.as_str(), None).await?;
+    let value_ranges = response["valueRanges"].as_array().cloned().unwrap_or_default();
+    let ranges: Vec<Vec<Vec<Value>>> = value_ranges
+        .into_iter()
+        .map(|range| serde_json::from_value(range["values"].clone()).unwrap_or_default())
+        .collect();
+    summary::rows_read(ranges.iter().map(Vec::len).sum());
+    Ok(ranges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHEET: &str = "https://sheets.googleapis.com/v4/spreadsheets/abc";
+
+    #[test]
+    fn only_repeatable_requests_retry_server_errors() {
+        let post = |suffix: &str| repeatable(&Method::POST, &format!("{}{}", SHEET, suffix));
+        assert!(post("/values:batchUpdate"));
+        assert!(post("/values/Log!A1:B2:clear"));
+        assert!(post("/values:batchGetByDataFilter"));
+        assert!(!post("/values/Log:append?valueInputOption=RAW"));
+        assert!(!post(":batchUpdate"));
+        assert!(repeatable(&Method::PUT, &format!("{}/values/Log!A1", SHEET)));
+        assert!(repeatable(&Method::GET, SHEET));
+
+        assert!(retryable(StatusCode::TOO_MANY_REQUESTS, false));
+        assert!(!retryable(StatusCode::BAD_GATEWAY, false));
+        assert!(retryable(StatusCode::BAD_GATEWAY, true));
+        assert!(!retryable(StatusCode::BAD_REQUEST, true));
+    }
+}
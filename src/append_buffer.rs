@@ -0,0 +1,110 @@
+use crate::cell_value::{CellValue, WriteOptions};
+use crate::limits::{row_payload_bytes, MAX_REQUEST_BYTES};
+use crate::{append_rows_to_google_sheet_with, SecretString, SheetsError};
+
+pub const DEFAULT_BATCH_ROWS: usize = 1_000;
+
+// Collects rows and appends them to `range` a batch at a time, once
+// `max_rows` rows or `max_bytes` of payload are waiting, so a loop pushing
+// thousands of rows makes a handful of values:append calls. Call finish (or
+// flush) at the end: rows still buffered when it's dropped are lost.
+pub struct AppendBuffer<'a> {
+    access_token: &'a SecretString,
+    range: String,
+    options: WriteOptions,
+    max_rows: usize,
+    max_bytes: usize,
+    rows: Vec<Vec<CellValue>>,
+    bytes: usize,
+    appended: usize,
+}
+
+impl<'a> AppendBuffer<'a> {
+    pub fn new(access_token: &'a SecretString, range: &str) -> Self {
+        AppendBuffer {
+            access_token,
+            range: range.to_string(),
+            options: WriteOptions::default(),
+            max_rows: DEFAULT_BATCH_ROWS,
+            max_bytes: MAX_REQUEST_BYTES,
+            rows: Vec::new(),
+            bytes: 0,
+            appended: 0,
+        }
+    }
+
+    pub fn max_rows(mut self, rows: usize) -> Self {
+        self.max_rows = rows.max(1);
+        self
+    }
+
+    // Capped at MAX_REQUEST_BYTES, which a single append can't exceed anyway
+    pub fn max_bytes(mut self, bytes: usize) -> Self {
+        self.max_bytes = bytes.clamp(1, MAX_REQUEST_BYTES);
+        self
+    }
+
+    pub fn write_options(mut self, options: impl Into<WriteOptions>) -> Self {
+        self.options = options.into();
+        self
+    }
+
+    // Rows waiting for the next flush
+    pub fn pending(&self) -> usize {
+        self.rows.len()
+    }
+
+    // Rows written so far
+    pub fn appended(&self) -> usize {
+        self.appended
+    }
+
+    pub async fn push(&mut self, row: Vec<impl Into<CellValue>>) -> Result<(), SheetsError> {
+        let row: Vec<CellValue> = row.into_iter().map(Into::into).collect();
+        let bytes = row_payload_bytes(&row);
+        if !self.rows.is_empty() && self.bytes + bytes > self.max_bytes {
+            self.flush().await?;
+        }
+        self.rows.push(row);
+        self.bytes += bytes;
+        if self.rows.len() >= self.max_rows {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    pub async fn extend(&mut self, rows: impl IntoIterator<Item = Vec<impl Into<CellValue>>>) -> Result<(), SheetsError> {
+        for row in rows {
+            self.push(row).await?;
+        }
+        Ok(())
+    }
+
+    // Append whatever is buffered. A failed batch stays buffered, so the
+    // caller can flush again once the cause is fixed. Returns the rows written.
+    pub async fn flush(&mut self) -> Result<usize, SheetsError> {
+        if self.rows.is_empty() {
+            return Ok(0);
+        }
+        let written = append_rows_to_google_sheet_with(self.access_token, &self.range, self.rows.clone(), self.options).await?;
+        tracing::debug!("appended {} buffered rows to '{}'", written, self.range);
+        self.rows.clear();
+        self.bytes = 0;
+        self.appended += written;
+        Ok(written)
+    }
+
+    // Flush the rest; returns every row this buffer appended
+    pub async fn finish(mut self) -> Result<usize, SheetsError> {
+        self.flush().await?;
+        Ok(self.appended)
+    }
+}
+
+impl Drop for AppendBuffer<'_> {
+    fn drop(&mut self) {
+        if !self.rows.is_empty() {
+            tracing::warn!("{} rows for '{}' dropped without being appended; call finish()", self.rows.len(), self.range);
+        }
+    }
+}
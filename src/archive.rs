@@ -0,0 +1,146 @@
+use crate::a1::{range_start, A1Range};
+use crate::cell_value::{CellValue, ValueInputOption};
+use crate::confirm;
+use crate::dimensions::delete_row_requests;
+use crate::render::RenderOptions;
+use crate::{Filter, SheetsClient, SheetsError};
+use serde_json::Value;
+use std::error::Error;
+use std::fmt;
+
+pub const DEFAULT_BATCH_ROWS: usize = 500;
+
+// Moves the rows of a working tab that match a filter to the bottom of an
+// archive tab, so the working tab stays small. Each batch is appended to the
+// archive and then deleted from the source; when the delete fails, the
+// batch's archive rows are deleted again, so a row is never lost or left in
+// both tabs. Batches before a failure stay moved. Values are copied
+// unformatted as RAW: numbers, text and dates (as serial numbers) carry over,
+// formulas become their results and formatting doesn't.
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ArchiveOutcome {
+    pub moved: usize,
+    pub batches: usize,
+}
+
+// Archiving stopped part way
+#[derive(Debug)]
+pub struct ArchiveError {
+    pub moved: usize, // By the batches that completed
+    pub cause: SheetsError,
+    // Removing the failed batch from the archive failed too: it's in both tabs
+    pub rollback: Option<SheetsError>,
+}
+
+impl fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "archiving stopped after {} rows: {}", self.moved, self.cause)?;
+        if let Some(rollback) = &self.rollback {
+            write!(f, " (the last batch is in both tabs: removing it from the archive failed: {})", rollback)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ArchiveError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.cause)
+    }
+}
+
+// Failed before the first batch moved
+impl From<SheetsError> for ArchiveError {
+    fn from(cause: SheetsError) -> Self {
+        ArchiveError { moved: 0, cause, rollback: None }
+    }
+}
+
+pub async fn archive_where(client: &SheetsClient, source: &str, filter: &Filter, dest: &str) -> Result<ArchiveOutcome, ArchiveError> {
+    archive_where_with(client, source, filter, dest, DEFAULT_BATCH_ROWS).await
+}
+
+// archive_where, moving at most `batch_rows` rows per append/delete pair
+pub async fn archive_where_with(
+    client: &SheetsClient,
+    source: &str,
+    filter: &Filter,
+    dest: &str,
+    batch_rows: usize,
+) -> Result<ArchiveOutcome, ArchiveError> {
+    let rows = client.read_with(&A1Range::sheet(source).to_string(), &RenderOptions::unformatted()).await?;
+    let Some((header, data)) = rows.split_first() else { return Ok(ArchiveOutcome::default()) };
+    let compiled = filter.compile(header).map_err(SheetsError::Invalid)?;
+    // With their 1-based row numbers
+    let matched: Vec<(usize, &Vec<Value>)> = data.iter().enumerate().filter(|(_, row)| compiled.matches(row)).map(|(i, row)| (i + 2, row)).collect();
+    move_rows(client, "archive_where", source, &matched, Some(dest), batch_rows).await
+}
+
+// Delete `rows` (1-based row numbers, in order, with the values they were
+// read with) from `source`, appending each batch to `dest` first when given.
+// Every batch is checked against the sheet before it's deleted, and its
+// archive rows removed again when that or the delete fails.
+pub(crate) async fn move_rows(
+    client: &SheetsClient,
+    operation: &str,
+    source: &str,
+    rows: &[(usize, &Vec<Value>)],
+    dest: Option<&str>,
+    batch_rows: usize,
+) -> Result<ArchiveOutcome, ArchiveError> {
+    if rows.is_empty() {
+        return Ok(ArchiveOutcome::default());
+    }
+    // Confirmed once for the whole move rather than batch by batch, which
+    // would let a large archive through in small deletes
+    confirm::check(operation, rows.len(), client.is_forced()).map_err(SheetsError::from)?;
+    let forced = client.clone().force();
+    let gid = client.sheet_gid(source).await?;
+
+    let mut outcome = ArchiveOutcome::default();
+    for batch in rows.chunks(batch_rows.max(1)) {
+        // Earlier batches were all above this one, so it moved up by as many rows
+        let numbers: Vec<usize> = batch.iter().map(|(number, _)| number - outcome.moved).collect();
+        let appended = match dest {
+            Some(dest) => {
+                let values: Vec<Vec<CellValue>> = batch.iter().map(|(_, row)| row.iter().map(CellValue::from_json).collect()).collect();
+                match client.append_with(&A1Range::sheet(dest).to_string(), values, ValueInputOption::Raw).await {
+                    Ok(range) => Some((dest, range)),
+                    Err(cause) => return Err(ArchiveError { moved: outcome.moved, cause, rollback: None }),
+                }
+            }
+            None => None,
+        };
+        let deleted = match unchanged(client, source, &numbers, batch).await {
+            Ok(()) => forced.batch_update(delete_row_requests(gid, &numbers)).await,
+            Err(e) => Err(e),
+        };
+        if let Err(cause) = deleted {
+            let rollback = match appended {
+                Some((dest, range)) => forced.delete(dest, range_start(&range).1, batch.len()).await.err(),
+                None => None,
+            };
+            return Err(ArchiveError { moved: outcome.moved, cause, rollback });
+        }
+        outcome.moved += batch.len();
+        outcome.batches += 1;
+    }
+    match dest {
+        Some(dest) => tracing::info!("archived {} rows from '{}' to '{}' in {} batches", outcome.moved, source, dest, outcome.batches),
+        None => tracing::info!("deleted {} rows from '{}' in {} batches", outcome.moved, source, outcome.batches),
+    }
+    Ok(outcome)
+}
+
+// Rows are deleted by position: make sure nobody inserted or removed rows
+// above them since they were read, or the wrong rows would go
+async fn unchanged(client: &SheetsClient, source: &str, numbers: &[usize], batch: &[(usize, &Vec<Value>)]) -> Result<(), SheetsError> {
+    let (first, last) = (numbers[0], numbers[numbers.len() - 1]);
+    let current = client.read_with(&A1Range::sheet(source).rows(first, last).to_string(), &RenderOptions::unformatted()).await?;
+    for (number, (_, expected)) in numbers.iter().zip(batch) {
+        if current.get(number - first).map_or(&[][..], Vec::as_slice) != expected.as_slice() {
+            return Err(SheetsError::Conflict(format!("row {} of '{}' changed since it was read", number, source)));
+        }
+    }
+    Ok(())
+}
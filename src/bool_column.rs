@@ -0,0 +1,95 @@
+use serde_json::Value;
+
+// How spreadsheet locales render checkbox values with FORMATTED_VALUE reads
+const LOCALE_WORDS: &[(&str, &str)] = &[
+    ("TRUE", "FALSE"),
+    ("VRAI", "FAUX"),            // fr
+    ("WAHR", "FALSCH"),          // de
+    ("VERDADERO", "FALSO"),      // es
+    ("VERO", "FALSO"),           // it
+    ("VERDADEIRO", "FALSO"),     // pt
+    ("WAAR", "ONWAAR"),          // nl
+    ("PRAWDA", "FAŁSZ"),         // pl
+    ("SANT", "FALSKT"),          // sv
+    ("SAND", "FALSK"),           // da
+    ("SANN", "USANN"),           // no
+    ("TOSI", "EPÄTOSI"),         // fi
+    ("ИСТИНА", "ЛОЖЬ"),          // ru
+    ("DOĞRU", "YANLIŞ"),         // tr
+    ("PRAVDA", "NEPRAVDA"),      // cs
+    ("IGAZ", "HAMIS"),           // hu
+];
+
+// Parser for a checkbox/flag column. Accepts real booleans (UNFORMATTED_VALUE
+// reads), the localized TRUE/FALSE words above in any case, and the custom
+// checked/unchecked values a checkbox validation can define.
+#[derive(Debug, Clone)]
+pub struct BoolColumn {
+    true_words: Vec<String>,
+    false_words: Vec<String>,
+    empty_is_false: bool,
+}
+
+impl Default for BoolColumn {
+    fn default() -> Self {
+        BoolColumn {
+            true_words: LOCALE_WORDS.iter().map(|(t, _)| t.to_string()).collect(),
+            false_words: LOCALE_WORDS.iter().map(|(_, f)| f.to_string()).collect(),
+            empty_is_false: false,
+        }
+    }
+}
+
+impl BoolColumn {
+    pub fn new() -> Self {
+        BoolColumn::default()
+    }
+
+    // Extra checked/unchecked values, e.g. ("yes", "no") or ("1", "0")
+    pub fn with_values(mut self, checked: &str, unchecked: &str) -> Self {
+        self.true_words.push(checked.trim().to_uppercase());
+        self.false_words.push(unchecked.trim().to_uppercase());
+        self
+    }
+
+    // Blank cells count as unchecked (rows appended without the checkbox)
+    pub fn empty_as_false(mut self) -> Self {
+        self.empty_is_false = true;
+        self
+    }
+
+    // None for anything that isn't a recognised flag value
+    pub fn parse(&self, cell: &Value) -> Option<bool> {
+        match cell {
+            Value::Bool(b) => Some(*b),
+            Value::Null => self.empty_is_false.then_some(false),
+            Value::String(s) => {
+                let word = s.trim().to_uppercase();
+                if word.is_empty() {
+                    self.empty_is_false.then_some(false)
+                } else if self.true_words.contains(&word) {
+                    Some(true)
+                } else if self.false_words.contains(&word) {
+                    Some(false)
+                } else {
+                    None
+                }
+            }
+            Value::Number(n) => {
+                let word = n.to_string();
+                if self.true_words.contains(&word) {
+                    Some(true)
+                } else if self.false_words.contains(&word) {
+                    Some(false)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    pub fn matches(&self, cell: &Value, expected: bool) -> bool {
+        self.parse(cell) == Some(expected)
+    }
+}
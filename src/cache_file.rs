@@ -0,0 +1,134 @@
+use crate::config;
+use crate::credentials::Credentials;
+use crate::metadata::{self, SpreadsheetMetadata};
+use crate::output::write_private;
+use crate::SecretString;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use zeroize::Zeroizing;
+
+// Tokens are reused until this many seconds before they expire
+const TOKEN_EXPIRY_MARGIN_SECS: i64 = 60;
+
+// Serializes read-modify-write of the file within this process
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+
+// Access tokens and spreadsheet metadata kept between CLI runs, so a quick
+// read skips the OAuth exchange and the metadata fetch. Everything in the file
+// belongs to one set of credentials; a different key or account discards it.
+#[derive(Default, Serialize, Deserialize)]
+struct CacheFile {
+    fingerprint: String,
+    #[serde(default)]
+    tokens: BTreeMap<String, CachedToken>, // Keyed by the space-joined scopes
+    #[serde(default)]
+    metadata: BTreeMap<String, CachedMetadata>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedToken {
+    access_token: String,
+    expires_at: i64, // Unix seconds
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedMetadata {
+    fetched_at: i64,
+    metadata: SpreadsheetMetadata,
+}
+
+// SHEETS_CACHE=0 turns the file cache off
+pub fn cache_enabled() -> bool {
+    !matches!(env::var("SHEETS_CACHE").as_deref().map(str::trim), Ok("0" | "false" | "off"))
+}
+
+// SHEETS_CACHE_FILE, else cache.json in the profile directory
+pub fn cache_path() -> PathBuf {
+    env::var_os("SHEETS_CACHE_FILE")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| config::profile_dir().join("cache.json"))
+}
+
+// Metadata entries belong to whatever credentials the environment configures
+fn current_fingerprint() -> Option<String> {
+    Credentials::from_env().ok().map(|credentials| credentials.fingerprint())
+}
+
+// The file for the current credentials, or an empty one
+fn load(expected: &str) -> CacheFile {
+    let Ok(bytes) = std::fs::read(cache_path()).map(Zeroizing::new) else {
+        return CacheFile { fingerprint: expected.to_string(), ..Default::default() };
+    };
+    match serde_json::from_slice::<CacheFile>(&bytes) {
+        Ok(file) if file.fingerprint == expected => file,
+        // Unreadable, or written for other credentials: start over
+        _ => CacheFile { fingerprint: expected.to_string(), ..Default::default() },
+    }
+}
+
+fn save(file: &CacheFile) {
+    if let Ok(bytes) = serde_json::to_vec(file).map(Zeroizing::new) {
+        // Best effort: a failed cache write only costs a token exchange next run
+        let _ = write_private(&cache_path(), &bytes);
+    }
+}
+
+fn update(fingerprint: Option<String>, edit: impl FnOnce(&mut CacheFile)) {
+    let Some(expected) = fingerprint.filter(|_| cache_enabled()) else { return };
+    let _guard = FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut file = load(&expected);
+    edit(&mut file);
+    save(&file);
+}
+
+pub fn load_token(credentials: &Credentials, scopes: &str) -> Option<SecretString> {
+    load_token_with_expiry(credentials, scopes).map(|(token, _)| token)
+}
+
+// The cached token and when it expires (Unix seconds)
+pub fn load_token_with_expiry(credentials: &Credentials, scopes: &str) -> Option<(SecretString, i64)> {
+    let expected = Some(credentials.fingerprint()).filter(|_| cache_enabled())?;
+    let _guard = FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let token = load(&expected).tokens.remove(scopes)?;
+    (token.expires_at - TOKEN_EXPIRY_MARGIN_SECS > Utc::now().timestamp())
+        .then(|| (SecretString::new(token.access_token), token.expires_at))
+}
+
+pub fn store_token(credentials: &Credentials, scopes: &str, token: &SecretString, expires_in_secs: i64) {
+    let expires_at = Utc::now().timestamp() + expires_in_secs;
+    update(Some(credentials.fingerprint()), |file| {
+        let token = CachedToken { access_token: token.expose_secret().to_string(), expires_at };
+        file.tokens.insert(scopes.to_string(), token);
+    });
+}
+
+pub fn load_metadata(spreadsheet_id: &str) -> Option<SpreadsheetMetadata> {
+    let expected = current_fingerprint().filter(|_| cache_enabled())?;
+    let _guard = FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let cached = load(&expected).metadata.remove(spreadsheet_id)?;
+    // Tab lists change rarely but do change; re-fetch after the same TTL as in memory
+    (Utc::now().timestamp() - cached.fetched_at < metadata::metadata_ttl().as_secs() as i64).then_some(cached.metadata)
+}
+
+pub fn store_metadata(metadata: &SpreadsheetMetadata) {
+    update(current_fingerprint(), |file| {
+        let entry = CachedMetadata { fetched_at: Utc::now().timestamp(), metadata: metadata.clone() };
+        file.metadata.insert(metadata.spreadsheet_id.clone(), entry);
+    });
+}
+
+pub fn clear_metadata() {
+    update(current_fingerprint(), |file| file.metadata.clear());
+}
+
+// Remove the whole file, e.g. after rotating keys
+pub fn clear() -> std::io::Result<()> {
+    match std::fs::remove_file(cache_path()) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
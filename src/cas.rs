@@ -0,0 +1,73 @@
+use crate::a1::encode_range;
+use crate::cell_value::{rows_to_json, CellValue};
+use crate::api::v4;
+use crate::policy::{self, Operation};
+use crate::{config, fetch_values, read_only, SecretString, SheetsError};
+use reqwest::Method;
+use serde_json::{json, Value};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CasOutcome {
+    Swapped,
+    // The cell didn't hold the expected value, nothing was written
+    Mismatch { current: String },
+    // Someone else's write landed after ours, before the readback
+    LostRace { current: String },
+}
+
+impl CasOutcome {
+    pub fn swapped(&self) -> bool {
+        matches!(self, CasOutcome::Swapped)
+    }
+}
+
+// Read one cell as text; empty cells come back as ""
+pub async fn read_cell(access_token: &SecretString, cell: &str) -> Result<String, SheetsError> {
+    let values = fetch_values(access_token, cell).await?;
+    Ok(values
+        .first()
+        .and_then(|row| row.first())
+        .map(|v| match v {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        })
+        .unwrap_or_default())
+}
+
+pub async fn write_cell(access_token: &SecretString, cell: &str, value: impl Into<CellValue>) -> Result<(), SheetsError> {
+    read_only::guard("write_cell")?;
+    policy::global()?.check_range(Operation::Update, cell)?;
+    let sheet_id = config::sheet_id()?;
+    let (values, value_input_option) = rows_to_json(&[vec![value.into()]]);
+    let url = format!(
+        "https://sheets.googleapis.com/v4/spreadsheets/{}/values/{}?valueInputOption={}",
+        sheet_id, encode_range(cell), value_input_option
+    );
+    v4::send(access_token, Method::PUT, &url, Some(&json!({ "values": values }))).await?;
+    Ok(())
+}
+
+// Write `new` into `cell` if it currently holds `expected`, then read it
+// back. Best effort, not atomic: Sheets has no conditional write (and
+// developerMetadata can't serve as a lock, since duplicate keys are allowed),
+// so two writers can both pass the check. The readback only catches a
+// loser whose write landed first; if each reads back before the other's
+// write lands, both see Swapped. Use it to make clashes rare, not impossible.
+pub async fn compare_and_set(
+    access_token: &SecretString,
+    cell: &str,
+    expected: &str,
+    new: &str,
+) -> Result<CasOutcome, SheetsError> {
+    let current = read_cell(access_token, cell).await?;
+    if current != expected {
+        return Ok(CasOutcome::Mismatch { current });
+    }
+    write_cell(access_token, cell, new).await?;
+    let after = read_cell(access_token, cell).await?;
+    if after == new {
+        Ok(CasOutcome::Swapped)
+    } else {
+        Ok(CasOutcome::LostRace { current: after })
+    }
+}
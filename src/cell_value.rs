@@ -0,0 +1,305 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Number, Value};
+
+// One cell to write (or as read back), keeping numbers and booleans typed
+// instead of flattening everything to text
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum CellValue {
+    String(String),
+    Number(f64),
+    Bool(bool),
+    // Including the leading '=': "=SUM(A1:A3)"
+    Formula(String),
+    #[default]
+    Empty,
+}
+
+impl CellValue {
+    pub fn formula(expression: &str) -> Self {
+        let expression = expression.trim();
+        if expression.starts_with('=') {
+            CellValue::Formula(expression.to_string())
+        } else {
+            CellValue::Formula(format!("={}", expression))
+        }
+    }
+
+    // A cell from the values API. Strings stay strings even if they start
+    // with '=', since only FORMULA reads return formulas that way.
+    pub fn from_json(value: &Value) -> Self {
+        match value {
+            Value::Null => CellValue::Empty,
+            Value::String(s) if s.is_empty() => CellValue::Empty,
+            Value::String(s) => CellValue::String(s.clone()),
+            Value::Bool(b) => CellValue::Bool(*b),
+            Value::Number(n) => n.as_f64().map_or(CellValue::Empty, CellValue::Number),
+            other => CellValue::String(other.to_string()),
+        }
+    }
+
+    // JSON for a values payload. With `quote` (USER_ENTERED sent only for
+    // formulas) strings get a leading apostrophe so Sheets keeps them as
+    // typed rather than parsing "1/2" into a date.
+    pub fn to_json(&self, quote: bool) -> Value {
+        match self {
+            CellValue::String(s) if quote && !s.is_empty() => Value::String(format!("'{}", s)),
+            CellValue::String(s) => Value::String(s.clone()),
+            CellValue::Number(n) => Number::from_f64(*n).map_or(Value::Null, Value::Number),
+            CellValue::Bool(b) => Value::Bool(*b),
+            CellValue::Formula(f) => Value::String(f.clone()),
+            CellValue::Empty => Value::String(String::new()),
+        }
+    }
+
+    // What the cell shows when read back formatted; None where that depends
+    // on the number format or a formula's result
+    pub fn expected_text(&self) -> Option<String> {
+        match self {
+            CellValue::String(s) => Some(s.clone()),
+            CellValue::Bool(b) => Some(if *b { "TRUE" } else { "FALSE" }.to_string()),
+            CellValue::Empty => Some(String::new()),
+            CellValue::Number(_) | CellValue::Formula(_) => None,
+        }
+    }
+
+    // Characters sent for this cell, for the per-cell and payload limits
+    pub fn text_len(&self) -> usize {
+        match self {
+            CellValue::String(s) | CellValue::Formula(s) => s.chars().count(),
+            CellValue::Number(n) => n.to_string().len(),
+            CellValue::Bool(_) => 5,
+            CellValue::Empty => 0,
+        }
+    }
+
+    pub fn is_formula(&self) -> bool {
+        matches!(self, CellValue::Formula(_))
+    }
+}
+
+// How Sheets treats written values. Raw stores everything literally, formulas
+// included; UserEntered parses every cell as if typed into the UI, so
+// "=SUM(A1:A10)" evaluates and "1/2/2024" becomes a date in the sheet's locale.
+// Auto (the default) sends RAW unless a row holds a CellValue::Formula, and
+// then quotes strings so only the formulas get parsed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ValueInputOption {
+    #[default]
+    Auto,
+    Raw,
+    UserEntered,
+}
+
+impl ValueInputOption {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().replace(['-', '_'], "").as_str() {
+            "auto" => Some(ValueInputOption::Auto),
+            "raw" => Some(ValueInputOption::Raw),
+            "userentered" => Some(ValueInputOption::UserEntered),
+            _ => None,
+        }
+    }
+
+    // The valueInputOption to send, and whether strings need quoting to stay literal
+    fn resolve(self, has_formula: bool) -> (&'static str, bool) {
+        match self {
+            ValueInputOption::Auto if has_formula => ("USER_ENTERED", true),
+            ValueInputOption::Auto | ValueInputOption::Raw => ("RAW", false),
+            ValueInputOption::UserEntered => ("USER_ENTERED", false),
+        }
+    }
+}
+
+// What an Empty cell (a None or a field the row doesn't have) does to the
+// cell it's written over. In a values payload null leaves the cell as it was
+// and "" clears it; a zero-length string (ISBLANK false) only exists as a
+// USER_ENTERED lone apostrophe.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NullPolicy {
+    Skip,
+    #[default]
+    Clear,
+    EmptyString,
+}
+
+impl NullPolicy {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().replace(['-', '_'], "").as_str() {
+            "skip" | "unchanged" | "keep" => Some(NullPolicy::Skip),
+            "clear" => Some(NullPolicy::Clear),
+            "empty" | "emptystring" => Some(NullPolicy::EmptyString),
+            _ => None,
+        }
+    }
+}
+
+// How one write encodes its cells. A plain ValueInputOption or NullPolicy
+// converts into this, so the `_with` functions take either.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WriteOptions {
+    pub input: ValueInputOption,
+    pub nulls: NullPolicy,
+}
+
+impl WriteOptions {
+    pub fn input(mut self, input: ValueInputOption) -> Self {
+        self.input = input;
+        self
+    }
+
+    pub fn nulls(mut self, nulls: NullPolicy) -> Self {
+        self.nulls = nulls;
+        self
+    }
+
+    // Empty strings force USER_ENTERED, so under Auto and Raw everything
+    // else is quoted to keep it literal (Raw formulas included)
+    fn encoding(self, has_formula: bool, has_empty: bool) -> Encoding {
+        if self.nulls == NullPolicy::EmptyString && has_empty {
+            let raw = self.input == ValueInputOption::Raw;
+            let quote = self.input != ValueInputOption::UserEntered;
+            return Encoding { name: "USER_ENTERED", quote, quote_formulas: raw, nulls: self.nulls };
+        }
+        let (name, quote) = self.input.resolve(has_formula);
+        Encoding { name, quote, quote_formulas: false, nulls: self.nulls }
+    }
+}
+
+impl From<ValueInputOption> for WriteOptions {
+    fn from(input: ValueInputOption) -> Self {
+        WriteOptions { input, ..Default::default() }
+    }
+}
+
+impl From<NullPolicy> for WriteOptions {
+    fn from(nulls: NullPolicy) -> Self {
+        WriteOptions { nulls, ..Default::default() }
+    }
+}
+
+struct Encoding {
+    name: &'static str,
+    quote: bool,
+    quote_formulas: bool,
+    nulls: NullPolicy,
+}
+
+impl Encoding {
+    fn of<'a>(options: WriteOptions, cells: impl Iterator<Item = &'a CellValue>) -> Self {
+        let (mut formula, mut empty) = (false, false);
+        for cell in cells {
+            formula |= cell.is_formula();
+            empty |= *cell == CellValue::Empty;
+        }
+        options.encoding(formula, empty)
+    }
+
+    fn cell(&self, cell: &CellValue) -> Value {
+        match (cell, self.nulls) {
+            (CellValue::Empty, NullPolicy::Skip) => Value::Null,
+            (CellValue::Empty, NullPolicy::Clear) => Value::String(String::new()),
+            (CellValue::Empty, NullPolicy::EmptyString) => Value::String("'".to_string()),
+            (CellValue::Formula(f), _) if self.quote_formulas => Value::String(format!("'{}", f)),
+            (cell, _) => cell.to_json(self.quote),
+        }
+    }
+
+    fn rows(&self, rows: &[Vec<CellValue>]) -> Vec<Vec<Value>> {
+        rows.iter().map(|row| row.iter().map(|cell| self.cell(cell)).collect()).collect()
+    }
+}
+
+// RAW keeps everything literal; formulas only evaluate with USER_ENTERED
+pub fn value_input_option(rows: &[Vec<CellValue>]) -> &'static str {
+    ValueInputOption::Auto.resolve(has_formula(rows)).0
+}
+
+fn has_formula(rows: &[Vec<CellValue>]) -> bool {
+    rows.iter().flatten().any(CellValue::is_formula)
+}
+
+// Rows as the `values` array of a request, with the matching valueInputOption
+pub fn rows_to_json(rows: &[Vec<CellValue>]) -> (Vec<Vec<Value>>, &'static str) {
+    rows_to_json_as(rows, ValueInputOption::Auto)
+}
+
+pub fn rows_to_json_as(rows: &[Vec<CellValue>], options: impl Into<WriteOptions>) -> (Vec<Vec<Value>>, &'static str) {
+    let encoding = Encoding::of(options.into(), rows.iter().flatten());
+    (encoding.rows(rows), encoding.name)
+}
+
+// Several ranges' rows under one valueInputOption, as one values:batchUpdate needs
+pub(crate) fn ranges_to_json(updates: &[(String, Vec<Vec<CellValue>>)], options: impl Into<WriteOptions>) -> (Vec<Value>, &'static str) {
+    let encoding = Encoding::of(options.into(), updates.iter().flat_map(|(_, rows)| rows.iter().flatten()));
+    let data = updates.iter().map(|(range, rows)| serde_json::json!({ "range": range, "values": encoding.rows(rows) })).collect();
+    (data, encoding.name)
+}
+
+pub fn into_cells<T: Into<CellValue>>(rows: Vec<Vec<T>>) -> Vec<Vec<CellValue>> {
+    rows.into_iter().map(|row| row.into_iter().map(Into::into).collect()).collect()
+}
+
+impl From<String> for CellValue {
+    fn from(s: String) -> Self {
+        if s.is_empty() {
+            CellValue::Empty
+        } else {
+            CellValue::String(s)
+        }
+    }
+}
+
+impl From<&str> for CellValue {
+    fn from(s: &str) -> Self {
+        CellValue::from(s.to_string())
+    }
+}
+
+impl From<&String> for CellValue {
+    fn from(s: &String) -> Self {
+        CellValue::from(s.clone())
+    }
+}
+
+impl From<f64> for CellValue {
+    fn from(n: f64) -> Self {
+        CellValue::Number(n)
+    }
+}
+
+impl From<i64> for CellValue {
+    fn from(n: i64) -> Self {
+        CellValue::Number(n as f64)
+    }
+}
+
+impl From<bool> for CellValue {
+    fn from(b: bool) -> Self {
+        CellValue::Bool(b)
+    }
+}
+
+impl From<Value> for CellValue {
+    fn from(value: Value) -> Self {
+        CellValue::from_json(&value)
+    }
+}
+
+impl<T: Into<CellValue>> From<Option<T>> for CellValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(CellValue::Empty, Into::into)
+    }
+}
+
+// Serialized as the RAW JSON form, so rows round-trip through files
+impl Serialize for CellValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_json(false).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CellValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(CellValue::from_json(&Value::deserialize(deserializer)?))
+    }
+}
@@ -0,0 +1,495 @@
+use crate::a1::{encode_range, range_sheet, A1Range};
+use crate::api::v4::{self, BASE_URL};
+use crate::cell_value::{into_cells, rows_to_json_as, CellValue, ValueInputOption, WriteOptions};
+use crate::config::ConfigError;
+use crate::data_source::{refresh_data_source_request, refresh_statuses, ExecutionStatus};
+use crate::dimensions::{check_span, delete_dimension_request, insert_dimension_request, Dimension};
+use crate::grid::{
+    append_cells_request, auto_fill_request, delete_duplicates_request, duplicate_rows, randomize_range_request, text_to_columns_request,
+    trim_whitespace_request, update_cells_request, CellData, GridRange,
+};
+use crate::limits::validate_rows;
+use crate::metadata::{metadata_ttl, SheetRef};
+use crate::policy::{deleted_rows_in, titles_from, Operation, Policy};
+use crate::read_only::{self, ReadOnlyViolation};
+use crate::records::{rows_as, struct_rows_with, Alignment};
+use crate::render::RenderOptions;
+use crate::rollover::quote_sheet;
+use crate::table::{interpret, ReadOptions, ReadResult};
+use crate::tabs::{add_sheet_request, delete_sheet_request, duplicate_sheet_request, new_sheet_id, rename_sheet_request};
+use crate::watch::{self, ChangeKind};
+use crate::{
+    config, confirm, summary, updated_ranges, values_batch_data, Credentials, SecretString, SheetsError, SpreadsheetId, TokenProvider,
+};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use reqwest::{Client, Method};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+// gid -> title, and when it was fetched
+type CachedTabs = (Instant, HashMap<u64, String>);
+
+// One spreadsheet plus the credentials to reach it, for using the crate as a
+// library. The free functions in the crate root read the same settings from
+// the environment instead.
+#[derive(Debug, Clone)]
+pub struct SheetsClient {
+    http: Client,
+    tokens: Arc<TokenProvider>, // Shared by clones, so they refresh once
+    spreadsheet: SpreadsheetId,
+    read_only: bool,
+    forced: bool,
+    policy: Arc<Policy>,
+    tabs: Arc<Mutex<Option<CachedTabs>>>, // Shared by clones
+}
+
+impl SheetsClient {
+    // Tokens get the credentials' scopes; with only read-only ones the client
+    // is read-only too, so writes fail here instead of with a 403
+    pub fn new(credentials: Credentials, spreadsheet: SpreadsheetId) -> Self {
+        let read_only = !credentials.can_write();
+        let tokens = Arc::new(TokenProvider::for_credentials(credentials));
+        SheetsClient { http: Client::new(), tokens, spreadsheet, read_only, forced: false, policy: Arc::new(Policy::unrestricted()), tabs: Arc::default() }
+    }
+
+    // Share a reqwest::Client (connection pool, proxy settings) with the host application
+    pub fn with_http_client(mut self, http: Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    // Every mutating method fails with ReadOnlyViolation before sending anything
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    // Allow deletes beyond MAX_DELETE_WITHOUT_CONFIRM (default 50 rows)
+    pub fn force(mut self) -> Self {
+        self.forced = true;
+        self
+    }
+
+    // Checked before every request; clones share it, and its delete budget
+    pub fn with_policy(mut self, policy: Policy) -> Self {
+        self.policy = Arc::new(policy);
+        self
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only || read_only::is_read_only()
+    }
+
+    pub(crate) fn is_forced(&self) -> bool {
+        self.forced
+    }
+
+    // SERVICE_ACCOUNT_EMAIL, PRIVATE_KEY and SHEET_ID, like the CLI, plus the POLICY_* limits
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Ok(SheetsClient::new(Credentials::from_env()?, config::spreadsheet()?).with_policy(Policy::from_env()?))
+    }
+
+    // Same credentials, HTTP pool and token cache, different spreadsheet
+    pub fn for_spreadsheet(&self, spreadsheet: SpreadsheetId) -> Self {
+        SheetsClient {
+            http: self.http.clone(),
+            tokens: self.tokens.clone(),
+            spreadsheet,
+            read_only: self.read_only,
+            forced: self.forced,
+            policy: self.policy.clone(),
+            tabs: Arc::default(),
+        }
+    }
+
+    pub fn spreadsheet_id(&self) -> &str {
+        self.spreadsheet.as_str()
+    }
+
+    pub fn credentials(&self) -> &Credentials {
+        self.tokens.credentials()
+    }
+
+    // Cached until shortly before it expires, then refreshed
+    pub async fn access_token(&self) -> Result<SecretString, SheetsError> {
+        self.tokens.token().await
+    }
+
+    fn url(&self, suffix: &str) -> String {
+        format!("{}spreadsheets/{}{}", BASE_URL, self.spreadsheet.as_str(), suffix)
+    }
+
+    fn guard(&self, operation: &str) -> Result<(), ReadOnlyViolation> {
+        if self.is_read_only() {
+            return Err(ReadOnlyViolation { operation: operation.to_string() });
+        }
+        Ok(())
+    }
+
+    // gid -> title, fetched only when the policy restricts tabs
+    async fn sheet_titles(&self) -> Result<HashMap<u64, String>, SheetsError> {
+        if !self.policy.restricts_sheets() {
+            return Ok(HashMap::new());
+        }
+        self.tabs(false).await
+    }
+
+    // gid -> title of every tab, from the client's cache unless `refresh`
+    async fn tabs(&self, refresh: bool) -> Result<HashMap<u64, String>, SheetsError> {
+        if !refresh {
+            // Same TTL as the free functions' metadata cache
+            let cached = self.tabs.lock().unwrap_or_else(|e| e.into_inner()).clone();
+            if let Some((_, tabs)) = cached.filter(|(fetched, _)| fetched.elapsed() < metadata_ttl()) {
+                return Ok(tabs);
+            }
+        }
+        let url = self.url("?fields=sheets.properties(sheetId,title)");
+        let tabs = titles_from(&self.send(Method::GET, &url, None).await?);
+        *self.tabs.lock().unwrap_or_else(|e| e.into_inner()) = Some((Instant::now(), tabs.clone()));
+        Ok(tabs)
+    }
+
+    // Forget cached tab titles; batch_update does this itself after adding,
+    // removing or renaming tabs
+    pub fn invalidate_tabs(&self) {
+        *self.tabs.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    // gid of a tab given by title or gid. Titles are looked up in the cached
+    // spreadsheets.get mapping, refreshed once if the title isn't there.
+    pub async fn sheet_gid(&self, sheet: impl Into<SheetRef>) -> Result<u64, SheetsError> {
+        let title = match sheet.into() {
+            SheetRef::Gid(gid) => return Ok(gid),
+            SheetRef::Title(title) => title,
+        };
+        let find = |tabs: &HashMap<u64, String>| tabs.iter().find(|(_, t)| **t == title).map(|(gid, _)| *gid);
+        if let Some(gid) = find(&self.tabs(false).await?) {
+            return Ok(gid);
+        }
+        find(&self.tabs(true).await?).ok_or_else(|| SheetsError::NotFound(format!("no tab named '{}'", title)))
+    }
+
+    async fn send(&self, method: Method, url: &str, body: Option<&Value>) -> Result<Value, SheetsError> {
+        let token = self.access_token().await?;
+        v4::send_with(&self.http, &token, method, url, body).await
+    }
+
+    // Raw cell values of `range`, header row included; empty ranges give no rows
+    pub async fn read(&self, range: &str) -> Result<Vec<Vec<Value>>, SheetsError> {
+        self.read_with(range, &RenderOptions::default()).await
+    }
+
+    // read, rendered as asked: raw numbers, formulas, date serials
+    pub async fn read_with(&self, range: &str, render: &RenderOptions) -> Result<Vec<Vec<Value>>, SheetsError> {
+        self.policy.check_range(Operation::Read, range)?;
+        let response = self.send(Method::GET, &self.url(&format!("/values/{}{}", encode_range(range), render)), None).await?;
+        let rows: Vec<Vec<Value>> = serde_json::from_value(response["values"].clone()).unwrap_or_default();
+        summary::rows_read(rows.len());
+        Ok(rows)
+    }
+
+    // Rows of `range` as structs, first row as the header (see records)
+    pub async fn read_as<T: DeserializeOwned>(&self, range: &str) -> Result<Vec<T>, SheetsError> {
+        self.read_as_with(range, &ReadOptions::default()).await
+    }
+
+    // read_as with table options, e.g. header_rows (see records::read_as_with)
+    pub async fn read_as_with<T: DeserializeOwned>(&self, range: &str, options: &ReadOptions) -> Result<Vec<T>, SheetsError> {
+        let values = self.read(range).await?;
+        let options = ReadOptions { require_header: true, ..*options };
+        match interpret(range, values, &options)? {
+            ReadResult::Empty => Ok(Vec::new()),
+            ReadResult::Table { header, rows } => Ok(rows_as(&header, &rows)?),
+        }
+    }
+
+    // Append items under the header of the tab `range` names
+    pub async fn append_struct<T: Serialize>(&self, range: &str, items: &[T]) -> Result<String, SheetsError> {
+        self.append_struct_with(range, items, &Alignment::default()).await
+    }
+
+    // append_struct with required columns or unknown fields dropped (see records::Alignment)
+    pub async fn append_struct_with<T: Serialize>(&self, range: &str, items: &[T], alignment: &Alignment) -> Result<String, SheetsError> {
+        let sheet = range_sheet(range).unwrap_or_else(|| range.trim_matches('\'').to_string());
+        let header = self.read(&format!("{}!1:1", quote_sheet(&sheet))).await?.into_iter().next().unwrap_or_default();
+        if header.is_empty() {
+            return Err(SheetsError::Invalid(format!("'{}' has no header row to map fields onto", sheet)));
+        }
+        let rows = struct_rows_with(&header, items, alignment).map_err(SheetsError::Invalid)?;
+        self.append_with(range, rows, alignment.nulls).await
+    }
+
+    // One read per range, at most `max_in_flight` in flight; rows per range
+    // in the order given
+    pub async fn read_ranges_concurrent(&self, ranges: &[&str], max_in_flight: usize) -> Result<Vec<Vec<Vec<Value>>>, SheetsError> {
+        stream::iter(ranges.iter().map(|range| self.read(range)))
+            .buffered(max_in_flight.max(1))
+            .try_collect()
+            .await
+    }
+
+    // Several ranges or tabs in one values:batchGet round trip; one Vec of rows
+    // per range, in the order given
+    pub async fn batch_get_values(&self, ranges: &[&str]) -> Result<Vec<Vec<Vec<Value>>>, SheetsError> {
+        self.batch_get_values_with(ranges, &RenderOptions::default()).await
+    }
+
+    pub async fn batch_get_values_with(&self, ranges: &[&str], render: &RenderOptions) -> Result<Vec<Vec<Vec<Value>>>, SheetsError> {
+        for range in ranges {
+            self.policy.check_range(Operation::Read, range)?;
+        }
+        let mut params: Vec<(&str, &str)> = ranges.iter().map(|r| ("ranges", *r)).collect();
+        params.push(("majorDimension", "ROWS"));
+        params.extend(render.params());
+        let url = reqwest::Url::parse_with_params(&self.url("/values:batchGet"), &params).map_err(|e| SheetsError::Parse(e.to_string()))?;
+        let response = self.send(Method::GET, url.as_str(), None).await?;
+        let ranges: Vec<Vec<Vec<Value>>> = response["valueRanges"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|range| serde_json::from_value(range["values"].clone()).unwrap_or_default())
+            .collect();
+        summary::rows_read(ranges.iter().map(Vec::len).sum());
+        Ok(ranges)
+    }
+
+    // Append after the last row of the table in `range`; returns the range written
+    pub async fn append(&self, range: &str, rows: Vec<Vec<impl Into<CellValue>>>) -> Result<String, SheetsError> {
+        self.append_with(range, rows, ValueInputOption::Auto).await
+    }
+
+    // append, with Sheets parsing the values as asked (see ValueInputOption)
+    pub async fn append_with(&self, range: &str, rows: Vec<Vec<impl Into<CellValue>>>, input: impl Into<WriteOptions>) -> Result<String, SheetsError> {
+        self.guard("append")?;
+        self.policy.check_range(Operation::Append, range)?;
+        let rows = into_cells(rows);
+        validate_rows(&rows)?;
+        let (values, value_input_option) = rows_to_json_as(&rows, input);
+        let url = self.url(&format!("/values/{}:append?valueInputOption={}&insertDataOption=INSERT_ROWS", encode_range(range), value_input_option));
+        let count = rows.len();
+        let response = self.send(Method::POST, &url, Some(&json!({ "values": values }))).await?;
+        summary::rows_written(count);
+        let updated_range = response["updates"]["updatedRange"].as_str().unwrap_or(range).to_string();
+        watch::publish_write(&updated_range, ChangeKind::Appended, &rows);
+        Ok(updated_range)
+    }
+
+    // Overwrite `range` starting at its top-left cell
+    pub async fn update(&self, range: &str, rows: Vec<Vec<impl Into<CellValue>>>) -> Result<String, SheetsError> {
+        self.update_with(range, rows, ValueInputOption::Auto).await
+    }
+
+    pub async fn update_with(&self, range: &str, rows: Vec<Vec<impl Into<CellValue>>>, input: impl Into<WriteOptions>) -> Result<String, SheetsError> {
+        self.guard("update")?;
+        self.policy.check_range(Operation::Update, range)?;
+        let rows = into_cells(rows);
+        validate_rows(&rows)?;
+        let (values, value_input_option) = rows_to_json_as(&rows, input);
+        let url = self.url(&format!("/values/{}?valueInputOption={}", encode_range(range), value_input_option));
+        let count = rows.len();
+        let response = self.send(Method::PUT, &url, Some(&json!({ "values": values }))).await?;
+        summary::rows_written(count);
+        let updated_range = response["updatedRange"].as_str().unwrap_or(range).to_string();
+        watch::publish_write(&updated_range, ChangeKind::Updated, &rows);
+        Ok(updated_range)
+    }
+
+    // Several ranges in one atomic values:batchUpdate; returns the range written per entry
+    pub async fn batch_update_values(&self, updates: Vec<(String, Vec<Vec<impl Into<CellValue>>>)>) -> Result<Vec<String>, SheetsError> {
+        self.batch_update_values_with(updates, ValueInputOption::Auto).await
+    }
+
+    pub async fn batch_update_values_with(
+        &self,
+        updates: Vec<(String, Vec<Vec<impl Into<CellValue>>>)>,
+        input: impl Into<WriteOptions>,
+    ) -> Result<Vec<String>, SheetsError> {
+        self.guard("batch_update_values")?;
+        for (range, _) in &updates {
+            self.policy.check_range(Operation::Update, range)?;
+        }
+        let (data, value_input_option) = values_batch_data(updates, input);
+        let body = json!({ "valueInputOption": value_input_option, "data": data });
+        let response = self.send(Method::POST, &self.url("/values:batchUpdate"), Some(&body)).await?;
+        summary::rows_written(response["totalUpdatedRows"].as_u64().unwrap_or(0) as usize);
+        Ok(updated_ranges(&response))
+    }
+
+    // Blank out the values in `range`, keeping rows and formatting; returns the range cleared
+    pub async fn clear_range(&self, range: &str) -> Result<String, SheetsError> {
+        self.guard("clear")?;
+        self.policy.check_range(Operation::Update, range)?;
+        let url = self.url(&format!("/values/{}:clear", encode_range(range)));
+        let response = self.send(Method::POST, &url, Some(&json!({}))).await?;
+        Ok(response["clearedRange"].as_str().unwrap_or(range).to_string())
+    }
+
+    pub async fn batch_clear(&self, ranges: &[&str]) -> Result<Vec<String>, SheetsError> {
+        self.guard("batch_clear")?;
+        for range in ranges {
+            self.policy.check_range(Operation::Update, range)?;
+        }
+        let response = self.send(Method::POST, &self.url("/values:batchClear"), Some(&json!({ "ranges": ranges }))).await?;
+        Ok(v4::cleared_ranges(&response))
+    }
+
+    // Remove `count` rows starting at 1-based `row` from a tab given by title or gid
+    pub async fn delete(&self, sheet: impl Into<SheetRef>, row: usize, count: usize) -> Result<(), SheetsError> {
+        self.guard("delete")?;
+        self.change_dimension(sheet, row, count, |gid| delete_dimension_request(gid, Dimension::Rows, row, count)).await
+    }
+
+    // Insert `count` empty rows before 1-based row `start`
+    pub async fn insert_rows(&self, sheet: impl Into<SheetRef>, start: usize, count: usize) -> Result<(), SheetsError> {
+        self.guard("insert_rows")?;
+        self.change_dimension(sheet, start, count, |gid| insert_dimension_request(gid, Dimension::Rows, start, count)).await
+    }
+
+    // Insert `count` empty columns before 1-based column `start` (1 = A)
+    pub async fn insert_columns(&self, sheet: impl Into<SheetRef>, start: usize, count: usize) -> Result<(), SheetsError> {
+        self.guard("insert_columns")?;
+        self.change_dimension(sheet, start, count, |gid| insert_dimension_request(gid, Dimension::Columns, start, count)).await
+    }
+
+    pub async fn delete_columns(&self, sheet: impl Into<SheetRef>, start: usize, count: usize) -> Result<(), SheetsError> {
+        self.guard("delete_columns")?;
+        self.change_dimension(sheet, start, count, |gid| delete_dimension_request(gid, Dimension::Columns, start, count)).await
+    }
+
+    async fn change_dimension(
+        &self,
+        sheet: impl Into<SheetRef>,
+        start: usize,
+        count: usize,
+        request: impl Fn(u64) -> Value,
+    ) -> Result<(), SheetsError> {
+        check_span(start, count).map_err(SheetsError::Invalid)?;
+        let sheet = sheet.into();
+        let gid = self.sheet_gid(sheet.clone()).await?;
+        match self.batch_update(vec![request(gid)]).await {
+            // The cached title pointed at a tab that's since gone; look it up once more
+            Err(e) if e.is_unknown_sheet() && matches!(sheet, SheetRef::Title(_)) => {
+                self.invalidate_tabs();
+                let gid = self.sheet_gid(sheet).await?;
+                self.batch_update(vec![request(gid)]).await?;
+            }
+            result => {
+                result?;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn update_cells(&self, range: GridRange, rows: &[Vec<CellData>]) -> Result<(), SheetsError> {
+        self.batch_update(vec![update_cells_request(range, rows)]).await?;
+        Ok(())
+    }
+
+    pub async fn append_cells(&self, sheet: impl Into<SheetRef>, rows: &[Vec<CellData>]) -> Result<(), SheetsError> {
+        let sheet_gid = self.sheet_gid(sheet).await?;
+        self.batch_update(vec![append_cells_request(sheet_gid, rows)]).await?;
+        Ok(())
+    }
+
+    // Extend `source` down by `fill_length` rows (see grid::auto_fill_request)
+    pub async fn auto_fill(&self, source: GridRange, fill_length: usize) -> Result<(), SheetsError> {
+        if fill_length == 0 {
+            return Ok(());
+        }
+        self.batch_update(vec![auto_fill_request(source, fill_length)]).await?;
+        Ok(())
+    }
+
+    // Split one column on `delimiter` server-side (see grid::text_to_columns_request)
+    pub async fn text_to_columns(&self, source: GridRange, delimiter: &str) -> Result<(), SheetsError> {
+        self.batch_update(vec![text_to_columns_request(source, delimiter)]).await?;
+        Ok(())
+    }
+
+    // Trim and collapse spaces in every cell of `range`; returns cells changed
+    pub async fn trim_whitespace(&self, range: GridRange) -> Result<u64, SheetsError> {
+        let replies = self.batch_update(vec![trim_whitespace_request(range)]).await?;
+        Ok(replies.first().and_then(|r| r["trimWhitespace"]["cellsChangedCount"].as_u64()).unwrap_or(0))
+    }
+
+    // Shuffle the rows of `range` in place (see grid::randomize_range_request)
+    pub async fn randomize_range(&self, range: GridRange) -> Result<(), SheetsError> {
+        self.batch_update(vec![randomize_range_request(range)]).await?;
+        Ok(())
+    }
+
+    // Re-run the query behind a Connected Sheets data source; statuses of the refreshed objects
+    pub async fn refresh_data_source(&self, data_source_id: &str, force: bool) -> Result<Vec<ExecutionStatus>, SheetsError> {
+        let replies = self.batch_update(vec![refresh_data_source_request(data_source_id, force)]).await?;
+        Ok(refresh_statuses(&replies))
+    }
+
+    // Remove repeated rows of `range` (see grid::delete_duplicates_request); returns rows removed
+    pub async fn delete_duplicates(&self, range: GridRange, comparison_columns: &[usize]) -> Result<u64, SheetsError> {
+        let replies = self.batch_update(vec![delete_duplicates_request(range, comparison_columns)]).await?;
+        let removed = replies.first().and_then(|r| r["deleteDuplicates"]["duplicatesRemovedCount"].as_u64()).unwrap_or(0);
+        summary::rows_deleted(removed as usize);
+        Ok(removed)
+    }
+
+    // Create an empty tab; returns its gid
+    pub async fn add_sheet(&self, title: &str) -> Result<u64, SheetsError> {
+        let replies = self.batch_update(vec![add_sheet_request(title)]).await?;
+        new_sheet_id(&replies, "addSheet")
+    }
+
+    pub async fn rename_sheet(&self, sheet: impl Into<SheetRef>, new_title: &str) -> Result<(), SheetsError> {
+        let gid = self.sheet_gid(sheet).await?;
+        self.batch_update(vec![rename_sheet_request(gid, new_title)]).await?;
+        Ok(())
+    }
+
+    // Copy a tab with its values, formats and charts; returns the copy's gid
+    pub async fn duplicate_sheet(&self, sheet: impl Into<SheetRef>, new_title: &str) -> Result<u64, SheetsError> {
+        let gid = self.sheet_gid(sheet).await?;
+        let replies = self.batch_update(vec![duplicate_sheet_request(gid, new_title, None)]).await?;
+        new_sheet_id(&replies, "duplicateSheet")
+    }
+
+    pub async fn delete_sheet(&self, sheet: impl Into<SheetRef>) -> Result<(), SheetsError> {
+        let gid = self.sheet_gid(sheet).await?;
+        self.batch_update(vec![delete_sheet_request(gid)]).await?;
+        Ok(())
+    }
+
+    // spreadsheets.batchUpdate; returns the replies array
+    pub async fn batch_update(&self, requests: Vec<Value>) -> Result<Vec<Value>, SheetsError> {
+        self.guard("batchUpdate")?;
+        self.policy.check_requests(&requests, &self.sheet_titles().await?)?;
+        let deleted = deleted_rows_in(&requests);
+        let duplicates = if self.forced || confirm::is_forced() { 0 } else { self.duplicates_in(&requests).await? };
+        confirm::check("batchUpdate", deleted + duplicates, self.forced)?;
+        let renames_tabs = requests.iter().any(|request| {
+            ["addSheet", "deleteSheet", "duplicateSheet", "updateSheetProperties"].iter().any(|kind| request.get(kind).is_some())
+        });
+        let result = self.send(Method::POST, &self.url(":batchUpdate"), Some(&json!({ "requests": requests }))).await;
+        if renames_tabs {
+            self.invalidate_tabs();
+        }
+        let response = result?;
+        summary::rows_deleted(deleted);
+        Ok(response["replies"].as_array().cloned().unwrap_or_default())
+    }
+
+    // Rows the deleteDuplicates among `requests` would remove; Sheets only
+    // reports the count afterwards, so each tab is read and counted first
+    async fn duplicates_in(&self, requests: &[Value]) -> Result<usize, SheetsError> {
+        let mut total = 0;
+        for body in requests.iter().filter_map(|request| request.get("deleteDuplicates")) {
+            let gid = body["range"]["sheetId"].as_u64().unwrap_or(0);
+            let title = self.tabs(false).await?.remove(&gid).ok_or_else(|| SheetsError::NotFound(format!("no tab with gid {}", gid)))?;
+            let rows = self.read_with(&A1Range::sheet(&title).to_string(), &RenderOptions::unformatted()).await?;
+            total += duplicate_rows(body, &rows);
+        }
+        Ok(total)
+    }
+}
@@ -0,0 +1,110 @@
+use crate::{SecretString, SheetsError};
+use reqwest::Method;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::env;
+use std::future::Future;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+
+// Identical writes (same method, URL, body and token) issued within the
+// window share one API call and its result: a double-submit from an HTTP
+// handler appends the row once and both callers see the same response.
+// A duplicate arriving while the first call is still in flight joins it,
+// however long that call takes. Failed calls aren't remembered, so retrying
+// after an error sends again. Off unless set_window or DEDUPE_WINDOW_MS is
+// used, since some jobs append the same row twice on purpose.
+// Every write through api::v4 is covered: SheetsClient calls and the free
+// functions behind AppendBuffer, import and routing alike.
+
+type Shared = Arc<OnceCell<Result<Value, Arc<SheetsError>>>>;
+
+struct Entry {
+    started: Instant,
+    result: Shared,
+}
+
+static WINDOW: Mutex<Option<Duration>> = Mutex::new(None);
+
+fn entries() -> &'static Mutex<HashMap<[u8; 32], Entry>> {
+    static ENTRIES: OnceLock<Mutex<HashMap<[u8; 32], Entry>>> = OnceLock::new();
+    ENTRIES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Zero turns coalescing off again
+pub fn set_window(window: Duration) {
+    *WINDOW.lock().unwrap_or_else(|e| e.into_inner()) = Some(window);
+}
+
+// set_window, else DEDUPE_WINDOW_MS, else off
+pub fn window() -> Duration {
+    if let Some(window) = *WINDOW.lock().unwrap_or_else(|e| e.into_inner()) {
+        return window;
+    }
+    env::var("DEDUPE_WINDOW_MS")
+        .ok()
+        .and_then(|ms| ms.trim().parse().ok())
+        .map(Duration::from_millis)
+        .unwrap_or(Duration::ZERO)
+}
+
+fn request_key(access_token: &SecretString, method: &Method, url: &str, body: Option<&Value>) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    // Different credentials may not be allowed the same write
+    hasher.update(access_token.expose_secret().as_bytes());
+    for part in [method.as_str(), url, &body.map(Value::to_string).unwrap_or_default()] {
+        hasher.update([0]);
+        hasher.update(part.as_bytes());
+    }
+    hasher.finalize().into()
+}
+
+// Run `send` unless an identical request is in flight or finished within the
+// window, in which case its result is returned instead. If the first caller
+// is cancelled before finishing, the next waiter sends the request itself.
+pub(crate) async fn coalesce<F, Fut>(
+    access_token: &SecretString,
+    method: &Method,
+    url: &str,
+    body: Option<&Value>,
+    send: F,
+) -> Result<Value, SheetsError>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<Value, SheetsError>>,
+{
+    let window = window();
+    if window.is_zero() {
+        return send().await;
+    }
+    let key = request_key(access_token, method, url, body);
+    let (result, joined) = {
+        let mut entries = entries().lock().unwrap_or_else(|e| e.into_inner());
+        entries.retain(|_, entry| entry.started.elapsed() < window || !entry.result.initialized());
+        match entries.get(&key) {
+            Some(entry) => (entry.result.clone(), true),
+            None => {
+                let result = Shared::default();
+                entries.insert(key, Entry { started: Instant::now(), result: result.clone() });
+                (result, false)
+            }
+        }
+    };
+    if joined {
+        tracing::debug!("coalesced duplicate {} {}", method, url.split('?').next().unwrap_or(url));
+    }
+    match result.get_or_init(|| async { send().await.map_err(Arc::new) }).await {
+        Ok(value) => Ok(value.clone()),
+        Err(e) => {
+            // Waiters that joined in flight share the failure, but a retry
+            // after it gets to send again
+            let mut entries = entries().lock().unwrap_or_else(|e| e.into_inner());
+            if entries.get(&key).is_some_and(|entry| Arc::ptr_eq(&entry.result, &result)) {
+                entries.remove(&key);
+            }
+            Err(SheetsError::shared(e))
+        }
+    }
+}
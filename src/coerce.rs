@@ -0,0 +1,100 @@
+use crate::bool_column::BoolColumn;
+use serde_json::{Number, Value};
+
+// Cells arrive as strings with the default FORMATTED_VALUE render, but as real
+// numbers and booleans with UNFORMATTED_VALUE. These helpers treat "12",
+// 12 and 12.0 (or "TRUE" and true) as the same value so filters and typed
+// reads work with either.
+
+// Text form of a cell: strings as is, numbers without a trailing ".0",
+// booleans as TRUE/FALSE like Sheets shows them
+pub fn cell_text(cell: &Value) -> String {
+    match cell {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        Value::Bool(true) => "TRUE".to_string(),
+        Value::Bool(false) => "FALSE".to_string(),
+        Value::Number(n) => match n.as_f64() {
+            Some(f) if f.fract() == 0.0 && f.abs() < 1e15 => format!("{}", f as i64),
+            _ => n.to_string(),
+        },
+        other => other.to_string(),
+    }
+}
+
+pub fn cell_bool(cell: &Value) -> Option<bool> {
+    match cell {
+        Value::Bool(b) => Some(*b),
+        Value::String(s) => match s.trim().to_ascii_uppercase().as_str() {
+            "TRUE" => Some(true),
+            "FALSE" => Some(false),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// Plain numbers only; use numbers::parse_number for "£1,234.50"
+pub fn cell_f64(cell: &Value) -> Option<f64> {
+    match cell {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok().filter(|f: &f64| f.is_finite()),
+        _ => None,
+    }
+}
+
+pub fn cell_i64(cell: &Value) -> Option<i64> {
+    match cell {
+        Value::Number(n) => n.as_i64().or_else(|| n.as_f64().filter(|f| f.fract() == 0.0).map(|f| f as i64)),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+// The typed JSON value a cell represents: "12" -> 12, "TRUE" -> true.
+// Anything else (including "") stays a string.
+pub fn coerce(cell: &Value) -> Value {
+    match cell {
+        Value::String(s) => {
+            if let Some(b) = cell_bool(cell) {
+                return Value::Bool(b);
+            }
+            let trimmed = s.trim();
+            if let Ok(i) = trimmed.parse::<i64>() {
+                return Value::Number(i.into());
+            }
+            match trimmed.parse::<f64>().ok().and_then(Number::from_f64) {
+                Some(n) if !trimmed.is_empty() => Value::Number(n),
+                _ => cell.clone(),
+            }
+        }
+        other => other.clone(),
+    }
+}
+
+pub fn coerce_row(row: &[Value]) -> Vec<Value> {
+    row.iter().map(coerce).collect()
+}
+
+// Does `cell` equal `expected` regardless of how it was rendered?
+// "FALSE" matches false and localized renderings like FAUX or FALSCH, "12"
+// matches 12 and 12.0; other text must match exactly.
+pub fn cell_matches(cell: &Value, expected: &str) -> bool {
+    if let Value::String(s) = cell {
+        if s == expected {
+            return true;
+        }
+    }
+    let expected_value = Value::String(expected.to_string());
+    // Only the filter side is strict, so a name like "Vero" stays text
+    if let Some(b) = cell_bool(&expected_value) {
+        if let Some(a) = BoolColumn::default().parse(cell) {
+            return a == b;
+        }
+    }
+    // Only for real numbers: two strings "007" and "7" are different IDs
+    if let (Value::Number(_), Some(a), Some(b)) = (cell, cell_f64(cell), cell_f64(&expected_value)) {
+        return a == b;
+    }
+    !matches!(cell, Value::String(_)) && cell_text(cell) == expected
+}
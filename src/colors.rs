@@ -0,0 +1,44 @@
+use crate::api;
+use crate::grid::Color;
+use crate::{SecretString, SheetsError};
+use serde_json::Value;
+
+// Rows as displayed, each with the background of one of its cells, for
+// sorting or sampling by the colors reviewers use to flag rows. The values
+// API can't see formatting, so this reads grid data instead.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColoredRow {
+    pub background: Color,
+    pub values: Vec<String>,
+}
+
+// Formatted values of `range` plus the background of `column` (0-based,
+// relative to the range) in each row. Unformatted cells read as white.
+pub async fn read_backgrounds(access_token: &SecretString, range: &str, column: usize) -> Result<Vec<ColoredRow>, SheetsError> {
+    let response = api::v4::get_grid_data(access_token, range, "formattedValue,effectiveFormat.backgroundColor").await?;
+    let rows = response["sheets"][0]["data"][0]["rowData"].as_array().cloned().unwrap_or_default();
+    Ok(rows.iter().map(|row| colored_row(row, column)).collect())
+}
+
+fn colored_row(row: &Value, column: usize) -> ColoredRow {
+    let cells = row["values"].as_array().map(Vec::as_slice).unwrap_or_default();
+    let background = cells
+        .get(column)
+        .and_then(|cell| cell.get("effectiveFormat")?.get("backgroundColor"))
+        .and_then(|color| serde_json::from_value(color.clone()).ok())
+        .unwrap_or(Color::WHITE);
+    let values = cells.iter().map(|cell| cell["formattedValue"].as_str().unwrap_or_default().to_string()).collect();
+    ColoredRow { background, values }
+}
+
+// Stable sort putting rows colored order[0] first, then order[1], and so on;
+// rows in other colors keep their order after those, like Sheets' "sort by
+// fill color"
+pub fn sort_by_background(rows: &mut [ColoredRow], order: &[Color]) {
+    rows.sort_by_key(|row| order.iter().position(|color| color.same_as(&row.background)).unwrap_or(order.len()));
+}
+
+// Just the rows in `color`
+pub fn with_background(rows: &[ColoredRow], color: Color) -> Vec<ColoredRow> {
+    rows.iter().filter(|row| row.background.same_as(&color)).cloned().collect()
+}
@@ -0,0 +1,239 @@
+use crate::aggregate::amount_in;
+use crate::numbers::{amount_is_zero, amount_to_f64, parse_amount, Amount, NumberLocale};
+use crate::row::column_key;
+use crate::schema::Schema;
+use serde_json::{Number, Value};
+use std::env;
+
+// A column added to every read row, computed from the other columns with
+// + - * / and parentheses. Columns are referenced as [HEADER TEXT] or by their
+// snake_case key, e.g. "NET = [GROSS] - refund_amount".
+#[derive(Debug, Clone)]
+pub struct ComputedColumn {
+    pub name: String,
+    expr: Expr,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Num(Amount),
+    Col(String),
+    Neg(Box<Expr>),
+    Bin(char, Box<Expr>, Box<Expr>),
+}
+
+impl ComputedColumn {
+    pub fn parse(name: &str, expression: &str) -> Result<Self, String> {
+        let tokens = tokenize(expression)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.expr()?;
+        if parser.pos != parser.tokens.len() {
+            return Err(format!("unexpected '{}' in '{}'", parser.tokens[parser.pos], expression));
+        }
+        Ok(ComputedColumn { name: name.trim().to_string(), expr })
+    }
+
+    // Non-numeric inputs and division by zero give an empty cell.
+    // With the `decimal` feature the arithmetic is exact. Text cells are read
+    // in the locale `schema` gives their column.
+    pub fn eval(&self, header: &[Value], row: &[Value], schema: &Schema) -> Value {
+        let Some(amount) = eval(&self.expr, header, row, schema) else {
+            return Value::String(String::new());
+        };
+        let n = amount_to_f64(amount);
+        if !n.is_finite() {
+            return Value::String(String::new());
+        }
+        let rounded = (n * 1e9).round() / 1e9; // hide binary float noise like 0.30000000000000004
+        Number::from_f64(rounded).map(Value::Number).unwrap_or(Value::Null)
+    }
+}
+
+// Parse "NET=[GROSS]-[REFUND]; MARGIN=net/[GROSS]"
+pub fn parse_computed_columns(spec: &str) -> Result<Vec<ComputedColumn>, String> {
+    spec.split(';')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let (name, expression) = part
+                .split_once('=')
+                .ok_or_else(|| format!("computed column '{}' must look like NAME=expression", part))?;
+            ComputedColumn::parse(name, expression)
+        })
+        .collect()
+}
+
+// From the COMPUTED_COLUMNS variable; unset means none
+pub fn computed_columns_from_env() -> Result<Vec<ComputedColumn>, String> {
+    match env::var("COMPUTED_COLUMNS") {
+        Ok(spec) => parse_computed_columns(&spec),
+        Err(_) => Ok(Vec::new()),
+    }
+}
+
+pub fn extend_header(columns: &[ComputedColumn], header: &[Value]) -> Vec<Value> {
+    let mut out = header.to_vec();
+    out.extend(columns.iter().map(|c| Value::String(c.name.clone())));
+    out
+}
+
+// Computed values are appended after the row's own cells, padded to the header width
+pub fn extend_row(columns: &[ComputedColumn], header: &[Value], row: &[Value], schema: &Schema) -> Vec<Value> {
+    let mut out = row.to_vec();
+    if !columns.is_empty() && out.len() < header.len() {
+        out.resize(header.len(), Value::String(String::new()));
+    }
+    out.extend(columns.iter().map(|c| c.eval(header, row, schema)));
+    out
+}
+
+fn eval(expr: &Expr, header: &[Value], row: &[Value], schema: &Schema) -> Option<Amount> {
+    match expr {
+        Expr::Num(n) => Some(*n),
+        Expr::Col(name) => {
+            let index = header.iter().position(|h| {
+                let h = h.as_str().unwrap_or_default();
+                h.trim().eq_ignore_ascii_case(name) || column_key(h) == *name
+            })?;
+            amount_in(row.get(index)?, schema.locale(header[index].as_str().unwrap_or_default()))
+        }
+        Expr::Neg(inner) => eval(inner, header, row, schema).map(|n| -n),
+        Expr::Bin(op, a, b) => {
+            let (a, b) = (eval(a, header, row, schema)?, eval(b, header, row, schema)?);
+            match op {
+                '+' => Some(a + b),
+                '-' => Some(a - b),
+                '*' => Some(a * b),
+                '/' if !amount_is_zero(b) => Some(a / b),
+                _ => None,
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(Amount),
+    Col(String),
+    Op(char),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Num(n) => write!(f, "{}", n),
+            Token::Col(c) => write!(f, "{}", c),
+            Token::Op(c) => write!(f, "{}", c),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if "+-*/()".contains(c) {
+            tokens.push(Token::Op(c));
+            i += 1;
+        } else if c == '[' {
+            let end = chars[i..]
+                .iter()
+                .position(|c| *c == ']')
+                .ok_or_else(|| format!("unclosed '[' in '{}'", input))?;
+            tokens.push(Token::Col(chars[i + 1..i + end].iter().collect::<String>().trim().to_string()));
+            i += end + 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let n = parse_amount(&text, NumberLocale::Dot).ok_or_else(|| format!("bad number '{}'", text))?;
+            tokens.push(Token::Num(n));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Col(chars[start..i].iter().collect::<String>().to_lowercase()));
+        } else {
+            return Err(format!("unexpected character '{}' in '{}'", c, input));
+        }
+    }
+    Ok(tokens)
+}
+
+// expr := term (('+'|'-') term)* ; term := factor (('*'|'/') factor)* ;
+// factor := number | column | '-' factor | '(' expr ')'
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek_op(&self) -> Option<char> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Op(c)) => Some(*c),
+            _ => None,
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        let mut left = self.term()?;
+        while let Some(op @ ('+' | '-')) = self.peek_op() {
+            self.pos += 1;
+            left = Expr::Bin(op, Box::new(left), Box::new(self.term()?));
+        }
+        Ok(left)
+    }
+
+    fn term(&mut self) -> Result<Expr, String> {
+        let mut left = self.factor()?;
+        while let Some(op @ ('*' | '/')) = self.peek_op() {
+            self.pos += 1;
+            left = Expr::Bin(op, Box::new(left), Box::new(self.factor()?));
+        }
+        Ok(left)
+    }
+
+    fn factor(&mut self) -> Result<Expr, String> {
+        let token = self.tokens.get(self.pos).cloned().ok_or("expression ends too early")?;
+        self.pos += 1;
+        match token {
+            Token::Num(n) => Ok(Expr::Num(n)),
+            Token::Col(name) => Ok(Expr::Col(name)),
+            Token::Op('-') => Ok(Expr::Neg(Box::new(self.factor()?))),
+            Token::Op('(') => {
+                let inner = self.expr()?;
+                if self.peek_op() != Some(')') {
+                    return Err("missing ')'".to_string());
+                }
+                self.pos += 1;
+                Ok(inner)
+            }
+            Token::Op(c) => Err(format!("unexpected '{}'", c)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reads_cells_in_the_column_locale() {
+        let columns = parse_computed_columns("NET = [GROSS] - [REFUND]").unwrap();
+        let header = [json!("GROSS"), json!("REFUND")];
+        // "1,500" is fifteen hundred unless the schema says comma decimals
+        let row = [json!("1,500"), json!("(0,5)")];
+        assert_eq!(columns[0].eval(&header, &row, &Schema::default()), json!(1500.5));
+        let eu = Schema::parse("GROSS:currency(eu)").unwrap();
+        assert_eq!(extend_row(&columns, &header, &row, &eu)[2], json!(2.0));
+        assert_eq!(columns[0].eval(&header, &[json!("n/a"), json!(1)], &eu), json!(""));
+    }
+}
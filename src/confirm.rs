@@ -0,0 +1,76 @@
+use std::env;
+use std::fmt;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Deleting more rows than this in one call needs confirmation
+pub const DEFAULT_MAX_DELETE_WITHOUT_CONFIRM: usize = 50;
+
+static FORCED: AtomicBool = AtomicBool::new(false);
+static INTERACTIVE: AtomicBool = AtomicBool::new(false);
+
+// A mass mutation stopped before it was sent. Library callers opt in with
+// force() (or SheetsClient::force), the CLI with --yes or at the prompt.
+#[derive(Debug, Clone)]
+pub struct ConfirmationRequired {
+    pub operation: String,
+    pub rows: usize,
+    pub threshold: usize,
+}
+
+impl fmt::Display for ConfirmationRequired {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} would delete {} rows, more than the {} allowed without confirmation (pass --yes, or call force())",
+            self.operation, self.rows, self.threshold
+        )
+    }
+}
+
+impl std::error::Error for ConfirmationRequired {}
+
+// MAX_DELETE_WITHOUT_CONFIRM, else the default
+pub fn threshold() -> usize {
+    env::var("MAX_DELETE_WITHOUT_CONFIRM")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_MAX_DELETE_WITHOUT_CONFIRM)
+}
+
+// Skip confirmation for the rest of the process
+pub fn force() {
+    FORCED.store(true, Ordering::Relaxed);
+}
+
+// force() was called (or --yes given)
+pub fn is_forced() -> bool {
+    FORCED.load(Ordering::Relaxed)
+}
+
+// Ask on the terminal instead of failing; the CLI turns this on
+pub fn set_interactive(on: bool) {
+    INTERACTIVE.store(on, Ordering::Relaxed);
+}
+
+// Ok if `rows` is within the threshold, forced, or confirmed at the prompt
+pub fn check(operation: &str, rows: usize, forced: bool) -> Result<(), ConfirmationRequired> {
+    let threshold = threshold();
+    if rows <= threshold || forced || FORCED.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+    if INTERACTIVE.load(Ordering::Relaxed) && io::stdin().is_terminal() && ask(operation, rows) {
+        return Ok(());
+    }
+    Err(ConfirmationRequired { operation: operation.to_string(), rows, threshold })
+}
+
+fn ask(operation: &str, rows: usize) -> bool {
+    eprint!("{} will delete {} rows. Continue? [y/N] ", operation, rows);
+    let _ = io::stderr().flush();
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes")
+}
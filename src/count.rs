@@ -0,0 +1,161 @@
+use crate::a1::column_letter;
+use crate::api;
+use crate::coerce::{cell_matches, cell_text};
+use crate::filter::Column;
+use crate::metadata::spreadsheet_metadata;
+use crate::rollover::quote_sheet;
+use crate::SecretString;
+use crate::SheetsError;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+// Rows fetched per request when scanning
+const WINDOW_ROWS: usize = 10_000;
+
+// Equality conditions on column indices, all of which must hold (the same
+// matching as the default read filter)
+pub type Conditions<'a> = [(usize, &'a str)];
+
+// Scan `sheet` below the header a window at a time, fetching only the columns
+// the conditions use. `visit` gets the number of matches per window and
+// returns false to stop early.
+async fn scan(
+    access_token: &SecretString,
+    sheet: &str,
+    conditions: &Conditions<'_>,
+    mut visit: impl FnMut(usize) -> bool,
+) -> Result<(), SheetsError> {
+    let mut columns: Vec<usize> = conditions.iter().map(|(c, _)| *c).collect();
+    columns.sort_unstable();
+    columns.dedup();
+    if columns.is_empty() {
+        return Err(SheetsError::Invalid("count_where/exists_where need at least one condition".to_string()));
+    }
+
+    // The grid size bounds the scan; blank tails in the projected columns don't end it early
+    let metadata = spreadsheet_metadata(access_token).await?;
+    let row_count = metadata
+        .sheet(sheet)
+        .ok_or_else(|| SheetsError::NotFound(format!("no tab named '{}'", sheet)))?
+        .grid_properties
+        .as_ref()
+        .map_or(0, |g| g.row_count as usize);
+
+    let mut start = 2; // Row 1 is the header
+    while start <= row_count {
+        let end = (start + WINDOW_ROWS - 1).min(row_count);
+        let ranges: Vec<String> = columns
+            .iter()
+            .map(|c| {
+                let letter = column_letter(*c);
+                format!("{}!{}{}:{}{}", quote_sheet(sheet), letter, start, letter, end)
+            })
+            .collect();
+        let columns_data = api::v4::batch_get(access_token, &ranges).await?;
+        let height = columns_data.iter().map(Vec::len).max().unwrap_or(0);
+
+        let cell = |column: usize, row: usize| -> Option<&Value> {
+            let slot = columns.iter().position(|c| *c == column)?;
+            columns_data.get(slot)?.get(row)?.first()
+        };
+        let matches = (0..height)
+            .filter(|row| {
+                conditions
+                    .iter()
+                    .all(|(column, expected)| cell(*column, *row).is_some_and(|v| cell_matches(v, expected)))
+            })
+            .count();
+        if !visit(matches) {
+            return Ok(());
+        }
+        start = end + 1;
+    }
+    Ok(())
+}
+
+// Number of data rows matching every condition, without downloading other columns
+pub async fn count_where(
+    access_token: &SecretString,
+    sheet: &str,
+    conditions: &Conditions<'_>,
+) -> Result<usize, SheetsError> {
+    let mut total = 0;
+    scan(access_token, sheet, conditions, |n| {
+        total += n;
+        true
+    })
+    .await?;
+    Ok(total)
+}
+
+// Stops at the first window containing a match
+pub async fn exists_where(
+    access_token: &SecretString,
+    sheet: &str,
+    conditions: &Conditions<'_>,
+) -> Result<bool, SheetsError> {
+    let mut found = false;
+    scan(access_token, sheet, conditions, |n| {
+        found = n > 0;
+        !found
+    })
+    .await?;
+    Ok(found)
+}
+
+// Every distinct value in `column` (header text or index) of `sheet`, sorted.
+// Blank cells are left out; values are compared as shown (see cell_text), so
+// "DEBENHAMS" and "Debenhams " are listed apart.
+pub async fn distinct_values(
+    access_token: &SecretString,
+    sheet: &str,
+    column: impl Into<Column>,
+) -> Result<Vec<String>, SheetsError> {
+    Ok(distinct_counts(access_token, sheet, column.into()).await?.into_keys().collect())
+}
+
+// distinct_values with how many rows hold each, most common first
+pub async fn distinct_value_counts(
+    access_token: &SecretString,
+    sheet: &str,
+    column: impl Into<Column>,
+) -> Result<Vec<(String, usize)>, SheetsError> {
+    let mut counts: Vec<(String, usize)> = distinct_counts(access_token, sheet, column.into()).await?.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    Ok(counts)
+}
+
+// Reads only the one column, a window at a time, keeping just the tally
+async fn distinct_counts(access_token: &SecretString, sheet: &str, column: Column) -> Result<BTreeMap<String, usize>, SheetsError> {
+    let index = match column {
+        Column::Index(index) => index,
+        Column::Name(name) => {
+            let header = api::v4::get_values(access_token, &format!("{}!1:1", quote_sheet(sheet))).await?.into_iter().next().unwrap_or_default();
+            header
+                .iter()
+                .position(|h| h.as_str().is_some_and(|h| h.trim().eq_ignore_ascii_case(name.trim())))
+                .ok_or_else(|| SheetsError::NotFound(format!("'{}' has no column '{}'", sheet, name)))?
+        }
+    };
+    let metadata = spreadsheet_metadata(access_token).await?;
+    let row_count = metadata
+        .sheet(sheet)
+        .ok_or_else(|| SheetsError::NotFound(format!("no tab named '{}'", sheet)))?
+        .grid_properties
+        .as_ref()
+        .map_or(0, |g| g.row_count as usize);
+
+    let letter = column_letter(index);
+    let mut counts = BTreeMap::new();
+    let mut start = 2;
+    while start <= row_count {
+        let end = (start + WINDOW_ROWS - 1).min(row_count);
+        let range = format!("{}!{}{}:{}{}", quote_sheet(sheet), letter, start, letter, end);
+        for row in api::v4::get_values(access_token, &range).await? {
+            let Some(text) = row.first().map(cell_text).filter(|text| !text.trim().is_empty()) else { continue };
+            *counts.entry(text).or_insert(0) += 1;
+        }
+        start = end + 1;
+    }
+    Ok(counts)
+}
@@ -0,0 +1,254 @@
+use crate::config::{self, Config, ConfigError};
+use crate::scope::Scope;
+use crate::SecretString;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+// The fields we need from a service-account.json key file, or from the
+// authorized_user file `gcloud auth application-default login` writes
+#[derive(Deserialize)]
+struct KeyFile {
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    client_email: Option<String>,
+    private_key: Option<SecretString>,
+    client_id: Option<String>,
+    client_secret: Option<SecretString>,
+    refresh_token: Option<SecretString>,
+    quota_project_id: Option<String>,
+}
+
+// How access tokens are obtained
+#[derive(Debug, Clone, Default)]
+pub enum CredentialKind {
+    // A JWT signed with private_key
+    #[default]
+    ServiceAccount,
+    // A user's refresh token from gcloud; its scopes were fixed at login
+    AuthorizedUser { client_id: String, client_secret: SecretString, refresh_token: SecretString },
+    // The service account attached to the GCE/GKE/Cloud Run instance, asked
+    // for tokens through the metadata server; nothing secret is kept here
+    MetadataServer,
+}
+
+// An identity that can mint access tokens. For anything but a service
+// account key, private_key is empty and client_email only names the source.
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    pub client_email: String,
+    pub private_key: SecretString, // PEM, with real newlines
+    pub kind: CredentialKind,
+    // What tokens minted by TokenProvider, SheetsClient and
+    // get_google_access_token may do
+    pub scopes: Vec<Scope>,
+    // Workspace user to act as (the JWT `sub` claim), for domain-wide delegation
+    pub subject: Option<String>,
+}
+
+// Sent as x-goog-user-project, which Google requires to bill user credentials'
+// API calls to a project (the gcloud client's own project has no Sheets API)
+static QUOTA_PROJECT: Mutex<Option<String>> = Mutex::new(None);
+
+impl Credentials {
+    pub fn new(client_email: &str, private_key: SecretString) -> Self {
+        Credentials { client_email: client_email.to_string(), private_key, kind: CredentialKind::ServiceAccount, scopes: vec![Scope::default()], subject: None }
+    }
+
+    // Tokens act as this Workspace user. The service account's client ID must
+    // be granted the scopes under domain-wide delegation in the Admin console;
+    // only service account keys can do this.
+    pub fn impersonate_user(mut self, email: &str) -> Self {
+        self.subject = Some(email.trim().to_string()).filter(|email| !email.is_empty());
+        self
+    }
+
+    // Ask for these scopes instead, e.g. &[Scope::SpreadsheetsReadonly] for a
+    // job that only reads
+    pub fn with_scopes(mut self, scopes: &[Scope]) -> Self {
+        self.scopes = if scopes.is_empty() { vec![Scope::default()] } else { scopes.to_vec() };
+        self
+    }
+
+    // Whether any scope lets tokens write to spreadsheets
+    pub fn can_write(&self) -> bool {
+        self.scopes.iter().any(|scope| scope.allows_writes())
+    }
+
+    // Tokens from the metadata server of the instance this runs on
+    pub fn metadata_server() -> Self {
+        Credentials { kind: CredentialKind::MetadataServer, ..Credentials::new("default", SecretString::default()) }
+    }
+
+    // SERVICE_ACCOUNT_EMAIL and PRIVATE_KEY (plus the env file). Without
+    // SERVICE_ACCOUNT_EMAIL, the key file named by GOOGLE_APPLICATION_CREDENTIALS.
+    // AUTH_MODE=adc follows the Application Default Credentials chain instead.
+    // SHEETS_SCOPES picks the scopes, IMPERSONATE_USER the user to act as.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        config::load_dotenv()?;
+        let scopes = Scope::from_env()?;
+        let credentials = if adc_mode()? {
+            Credentials::application_default()?
+        } else if let Some(path) = env::var_os("GOOGLE_APPLICATION_CREDENTIALS").filter(|_| env::var_os("SERVICE_ACCOUNT_EMAIL").is_none()) {
+            Credentials::from_adc_file(path)?
+        } else {
+            let config = Config::from_env()?;
+            Credentials::new(&config.service_account_email, config.private_key)
+        };
+        let credentials = credentials.with_scopes(&scopes);
+        Ok(match env::var("IMPERSONATE_USER") {
+            Ok(email) => credentials.impersonate_user(&email),
+            Err(_) => credentials,
+        })
+    }
+
+    // The key file named by GOOGLE_APPLICATION_CREDENTIALS, else the one
+    // `gcloud auth application-default login` wrote, else the metadata
+    // server, so the same build runs on Cloud Run or GCE without a key
+    pub fn application_default() -> Result<Self, ConfigError> {
+        if let Some(path) = env::var_os("GOOGLE_APPLICATION_CREDENTIALS").filter(|p| !p.is_empty()) {
+            return Credentials::from_adc_file(path);
+        }
+        let gcloud = gcloud_credentials_path();
+        if gcloud.is_file() {
+            return Credentials::from_adc_file(gcloud);
+        }
+        Ok(Credentials::metadata_server())
+    }
+
+    // A service-account.json key as downloaded from the Cloud console
+    pub fn from_json_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let credentials = Credentials::from_adc_file(&path)?;
+        match credentials.kind {
+            CredentialKind::ServiceAccount => Ok(credentials),
+            _ => Err(ConfigError::Syntax {
+                path: path.as_ref().to_path_buf(),
+                line: 1,
+                reason: "expected a service account key, found type \"authorized_user\"".to_string(),
+            }),
+        }
+    }
+
+    // A service account key or gcloud's authorized_user file
+    pub fn from_adc_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let text = SecretString::new(fs::read_to_string(path).map_err(|e| ConfigError::Io {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })?);
+        Credentials::parse_json(text.expose_secret(), path.to_path_buf())
+    }
+
+    pub fn from_json_str(json: &str) -> Result<Self, ConfigError> {
+        Credentials::parse_json(json, PathBuf::from("<service account JSON>"))
+    }
+
+    fn parse_json(json: &str, path: PathBuf) -> Result<Self, ConfigError> {
+        let syntax = |line: usize, reason: String| ConfigError::Syntax { path: path.clone(), line, reason };
+        // Syntax errors can quote part of the input, so only keep their position
+        let key: KeyFile = serde_json::from_str(json).map_err(|e| match e.classify() {
+            serde_json::error::Category::Data => syntax(e.line(), e.to_string()),
+            _ => syntax(e.line(), format!("invalid JSON at column {}", e.column())),
+        })?;
+        let missing = |field: &str| syntax(1, format!("missing field `{}`", field));
+        match key.kind.as_deref() {
+            None | Some("service_account") => {}
+            Some("authorized_user") => {
+                let client_id = key.client_id.ok_or_else(|| missing("client_id"))?;
+                let kind = CredentialKind::AuthorizedUser {
+                    client_secret: key.client_secret.ok_or_else(|| missing("client_secret"))?,
+                    refresh_token: key.refresh_token.ok_or_else(|| missing("refresh_token"))?,
+                    client_id: client_id.clone(),
+                };
+                if let Some(project) = key.quota_project_id.filter(|p| !p.is_empty()) {
+                    *QUOTA_PROJECT.lock().unwrap_or_else(|e| e.into_inner()) = Some(project);
+                }
+                return Ok(Credentials { kind, ..Credentials::new(&format!("user of {}", client_id), SecretString::default()) });
+            }
+            Some(kind) => return Err(syntax(1, format!("expected a service account key, found type \"{}\"", kind))),
+        }
+        let client_email = key.client_email.ok_or_else(|| missing("client_email"))?;
+        let private_key = SecretString::new(key.private_key.ok_or_else(|| missing("private_key"))?.expose_secret().replace('\r', ""));
+        if !private_key.expose_secret().trim().starts_with("-----BEGIN") {
+            return Err(syntax(1, "private_key is not a PEM key".to_string()));
+        }
+        Ok(Credentials::new(&client_email, private_key))
+    }
+
+    // Stable hash identifying these credentials without revealing the key
+    pub fn fingerprint(&self) -> String {
+        let mut hasher = Sha256::new();
+        match &self.kind {
+            CredentialKind::ServiceAccount => {
+                hasher.update(self.client_email.as_bytes());
+                hasher.update([0]);
+                hasher.update(self.private_key.expose_secret().as_bytes());
+                // Each impersonated user gets their own cached tokens
+                if let Some(subject) = &self.subject {
+                    hasher.update(b"\0sub\0");
+                    hasher.update(subject.as_bytes());
+                }
+            }
+            CredentialKind::AuthorizedUser { client_id, refresh_token, .. } => {
+                hasher.update(b"authorized_user\0");
+                hasher.update(client_id.as_bytes());
+                hasher.update([0]);
+                hasher.update(refresh_token.expose_secret().as_bytes());
+            }
+            CredentialKind::MetadataServer => {
+                hasher.update(b"metadata\0");
+                hasher.update(metadata_host().as_bytes());
+            }
+        }
+        hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+// AUTH_MODE: service-account (the default) or adc
+pub fn adc_mode() -> Result<bool, ConfigError> {
+    match env::var("AUTH_MODE").as_deref().map(str::trim) {
+        Err(_) | Ok("") | Ok("service-account") | Ok("service_account") => Ok(false),
+        Ok("adc") => Ok(true),
+        Ok(other) => Err(ConfigError::Invalid {
+            var: "AUTH_MODE".to_string(),
+            reason: format!("expected service-account or adc, got '{}'", other),
+        }),
+    }
+}
+
+// Where `gcloud auth application-default login` saves credentials
+pub fn gcloud_credentials_path() -> PathBuf {
+    let dir = match env::var_os("CLOUDSDK_CONFIG") {
+        Some(dir) => PathBuf::from(dir),
+        None if cfg!(windows) => PathBuf::from(env::var_os("APPDATA").unwrap_or_default()).join("gcloud"),
+        None => PathBuf::from(env::var_os("HOME").unwrap_or_default()).join(".config").join("gcloud"),
+    };
+    dir.join("application_default_credentials.json")
+}
+
+// GCE_METADATA_HOST, as the Google client libraries read it, for emulators
+pub fn metadata_host() -> String {
+    env::var("GCE_METADATA_HOST").ok().filter(|h| !h.trim().is_empty()).unwrap_or_else(|| "metadata.google.internal".to_string())
+}
+
+// GOOGLE_CLOUD_QUOTA_PROJECT, else the quota_project_id of loaded user credentials
+pub fn quota_project() -> Option<String> {
+    env::var("GOOGLE_CLOUD_QUOTA_PROJECT")
+        .ok()
+        .filter(|p| !p.trim().is_empty())
+        .or_else(|| QUOTA_PROJECT.lock().unwrap_or_else(|e| e.into_inner()).clone())
+}
+
+// Tag an API request (not a token request) with the quota project, if any
+pub(crate) fn add_quota_project(request: &mut reqwest::Request) {
+    let api = request.url().host_str().is_some_and(|h| h.ends_with(".googleapis.com") && h != "oauth2.googleapis.com");
+    if !api || request.headers().contains_key("x-goog-user-project") {
+        return;
+    }
+    if let Some(value) = quota_project().and_then(|p| reqwest::header::HeaderValue::from_str(&p).ok()) {
+        request.headers_mut().insert("x-goog-user-project", value);
+    }
+}
@@ -0,0 +1,163 @@
+use crate::a1::A1Range;
+use crate::api;
+use crate::{SecretString, SheetsError};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+// Connected Sheets: tabs and tables whose rows come from an external source
+// (BigQuery) rather than being typed in. The rows can't be written through
+// this crate, but they can be refreshed and read like any other tab.
+
+// Outcome of the last (or a just requested) query run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ExecutionStatus {
+    // NOT_STARTED, RUNNING, SUCCEEDED, FAILED, ...
+    pub state: String,
+    pub error_code: Option<String>,
+    pub error_message: Option<String>,
+    pub last_refresh_time: Option<String>,
+}
+
+impl ExecutionStatus {
+    pub fn failed(&self) -> bool {
+        self.state == "FAILED" || self.error_code.as_deref().is_some_and(|code| code != "DATA_EXECUTION_ERROR_CODE_UNSPECIFIED")
+    }
+}
+
+// A tab of sheetType DATA_SOURCE showing a data source's result rows
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataSourceSheet {
+    pub sheet_id: u64,
+    pub title: String,
+    pub data_source_id: String,
+    pub columns: Vec<String>,
+    pub status: Option<ExecutionStatus>,
+}
+
+// A data source with its spec as the API gives it ({"bigQuery": {...}})
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataSource {
+    pub id: String,
+    pub spec: Value,
+    pub sheets: Vec<DataSourceSheet>,
+}
+
+// A dataSourceTable anchored in an ordinary tab; its rows spill down and
+// right from the anchor cell (0-based)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataSourceTable {
+    pub sheet: String,
+    pub row: usize,
+    pub column: usize,
+    pub data_source_id: String,
+    pub columns: Vec<String>,
+    pub status: Option<ExecutionStatus>,
+}
+
+// Every data source in the spreadsheet and the DATA_SOURCE tabs showing it
+pub async fn data_sources(access_token: &SecretString) -> Result<Vec<DataSource>, SheetsError> {
+    let response = api::v4::get_spreadsheet(
+        access_token,
+        "dataSources(dataSourceId,spec),sheets.properties(sheetId,title,sheetType,dataSourceSheetProperties)",
+    )
+    .await?;
+    let sheets: Vec<DataSourceSheet> = response["sheets"].as_array().into_iter().flatten().filter_map(|s| data_source_sheet(&s["properties"])).collect();
+    Ok(response["dataSources"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|source| {
+            let id = source["dataSourceId"].as_str()?.to_string();
+            let sheets = sheets.iter().filter(|sheet| sheet.data_source_id == id).cloned().collect();
+            Some(DataSource { id, spec: source["spec"].clone(), sheets })
+        })
+        .collect())
+}
+
+fn data_source_sheet(properties: &Value) -> Option<DataSourceSheet> {
+    if properties["sheetType"] != "DATA_SOURCE" {
+        return None;
+    }
+    let data = &properties["dataSourceSheetProperties"];
+    Some(DataSourceSheet {
+        sheet_id: properties["sheetId"].as_u64()?,
+        title: properties["title"].as_str()?.to_string(),
+        data_source_id: data["dataSourceId"].as_str()?.to_string(),
+        columns: column_names(&data["columns"]),
+        status: status(&data["dataExecutionStatus"]),
+    })
+}
+
+fn column_names(columns: &Value) -> Vec<String> {
+    columns.as_array().into_iter().flatten().filter_map(|c| c["reference"]["name"].as_str().map(str::to_string)).collect()
+}
+
+fn status(value: &Value) -> Option<ExecutionStatus> {
+    value.is_object().then(|| serde_json::from_value(value.clone()).ok()).flatten()
+}
+
+// Data source tables anywhere in the spreadsheet. Needs grid data, but only
+// the dataSourceTable field of each cell comes back.
+pub async fn data_source_tables(access_token: &SecretString) -> Result<Vec<DataSourceTable>, SheetsError> {
+    let fields = "sheets(properties.title,data(startRow,startColumn,rowData.values.dataSourceTable(dataSourceId,columns,dataExecutionStatus)))";
+    let response = api::v4::get_with_grid_data(access_token, &[], fields).await?;
+    let mut tables = Vec::new();
+    for sheet in response["sheets"].as_array().into_iter().flatten() {
+        let title = sheet["properties"]["title"].as_str().unwrap_or_default();
+        for data in sheet["data"].as_array().into_iter().flatten() {
+            let (start_row, start_column) = (data["startRow"].as_u64().unwrap_or(0) as usize, data["startColumn"].as_u64().unwrap_or(0) as usize);
+            for (r, row) in data["rowData"].as_array().into_iter().flatten().enumerate() {
+                for (c, cell) in row["values"].as_array().into_iter().flatten().enumerate() {
+                    let table = &cell["dataSourceTable"];
+                    let Some(id) = table["dataSourceId"].as_str() else { continue };
+                    tables.push(DataSourceTable {
+                        sheet: title.to_string(),
+                        row: start_row + r,
+                        column: start_column + c,
+                        data_source_id: id.to_string(),
+                        columns: column_names(&table["columns"]),
+                        status: status(&table["dataExecutionStatus"]),
+                    });
+                }
+            }
+        }
+    }
+    Ok(tables)
+}
+
+// refreshDataSource for everything backed by one data source. Without
+// `force`, objects already refreshed recently are skipped.
+pub fn refresh_data_source_request(data_source_id: &str, force: bool) -> Value {
+    json!({ "refreshDataSource": { "dataSourceId": data_source_id, "force": force } })
+}
+
+// refreshDataSource for every data source object in the spreadsheet
+pub fn refresh_all_request(force: bool) -> Value {
+    json!({ "refreshDataSource": { "isAll": true, "force": force } })
+}
+
+// Statuses of the objects refreshed. A refresh runs the query, so this can
+// take as long as BigQuery does.
+pub async fn refresh_data_source(access_token: &SecretString, data_source_id: &str, force: bool) -> Result<Vec<ExecutionStatus>, SheetsError> {
+    let replies = api::v4::batch_update(access_token, vec![refresh_data_source_request(data_source_id, force)]).await?;
+    Ok(refresh_statuses(&replies))
+}
+
+pub async fn refresh_all_data_sources(access_token: &SecretString, force: bool) -> Result<Vec<ExecutionStatus>, SheetsError> {
+    let replies = api::v4::batch_update(access_token, vec![refresh_all_request(force)]).await?;
+    Ok(refresh_statuses(&replies))
+}
+
+pub(crate) fn refresh_statuses(replies: &[Value]) -> Vec<ExecutionStatus> {
+    replies
+        .iter()
+        .flat_map(|reply| reply["refreshDataSource"]["statuses"].as_array().cloned().unwrap_or_default())
+        .filter_map(|s| status(&s["dataExecutionStatus"]))
+        .collect()
+}
+
+// The result rows a DATA_SOURCE tab currently shows, header first
+pub async fn read_data_source_sheet(access_token: &SecretString, sheet: &DataSourceSheet) -> Result<Vec<Vec<Value>>, SheetsError> {
+    api::v4::get_values(access_token, &A1Range::sheet(&sheet.title).to_string()).await
+}
@@ -0,0 +1,101 @@
+use crate::a1::column_letter;
+use crate::rollover::quote_sheet;
+use crate::{append_rows_to_google_sheet, fetch_values, SecretString, SheetsError};
+use std::collections::HashSet;
+
+// Keys already present in a tab. Keep one around between calls to avoid
+// re-reading the key column on every append.
+#[derive(Debug, Clone, Default)]
+pub struct KeyCache {
+    sheet: String,
+    key_column: usize,
+    keys: Option<HashSet<String>>,
+}
+
+impl KeyCache {
+    pub fn new(sheet: &str, key_column: usize) -> Self {
+        KeyCache { sheet: sheet.to_string(), key_column, keys: None }
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.keys.as_ref().is_some_and(|keys| keys.contains(normalize_key(key)))
+    }
+
+    pub fn invalidate(&mut self) {
+        self.keys = None;
+    }
+
+    async fn load(&mut self, access_token: &SecretString) -> Result<&mut HashSet<String>, SheetsError> {
+        if self.keys.is_none() {
+            self.keys = Some(load_keys(access_token, &self.sheet, self.key_column).await?);
+        }
+        Ok(self.keys.get_or_insert_with(HashSet::new))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AppendOutcome {
+    pub inserted: Vec<Vec<String>>,
+    pub skipped: Vec<Vec<String>>, // Key already in the sheet (or earlier in this batch)
+}
+
+fn normalize_key(key: &str) -> &str {
+    key.trim()
+}
+
+// Read only the key column of `sheet` (header row excluded)
+pub async fn load_keys(
+    access_token: &SecretString,
+    sheet: &str,
+    key_column: usize,
+) -> Result<HashSet<String>, SheetsError> {
+    let letter = column_letter(key_column);
+    let range = format!("{}!{}2:{}", quote_sheet(sheet), letter, letter);
+    let values = fetch_values(access_token, &range).await?;
+    Ok(values
+        .iter()
+        .filter_map(|row| row.first())
+        .map(|cell| cell.as_str().map(str::to_string).unwrap_or_else(|| cell.to_string()))
+        .map(|key| normalize_key(&key).to_string())
+        .filter(|key| !key.is_empty())
+        .collect())
+}
+
+// Append only rows whose key (column `key_column`) isn't in `sheet` yet.
+// Pass a cache to reuse loaded keys across calls; it is updated with the new keys.
+pub async fn append_if_absent(
+    access_token: &SecretString,
+    sheet: &str,
+    rows: Vec<Vec<String>>,
+    key_column: usize,
+    cache: Option<&mut KeyCache>,
+) -> Result<AppendOutcome, SheetsError> {
+    let mut local = KeyCache::new(sheet, key_column);
+    let cache = match cache {
+        Some(cache) if cache.sheet == sheet && cache.key_column == key_column => cache,
+        Some(_) => return Err(SheetsError::Invalid("key cache was built for a different sheet or key column".to_string())),
+        None => &mut local,
+    };
+    let keys = cache.load(access_token).await?;
+
+    let mut outcome = AppendOutcome::default();
+    for row in rows {
+        let key = row.get(key_column).map(|k| normalize_key(k).to_string()).unwrap_or_default();
+        if !key.is_empty() && !keys.insert(key) {
+            outcome.skipped.push(row);
+        } else {
+            outcome.inserted.push(row);
+        }
+    }
+
+    if !outcome.inserted.is_empty() {
+        let result = append_rows_to_google_sheet(access_token, &quote_sheet(sheet), outcome.inserted.clone()).await;
+        if let Err(e) = result {
+            // Keys were recorded optimistically; reload next time
+            cache.invalidate();
+            return Err(e);
+        }
+    }
+    println!(" Inserted {} row(s), skipped {} duplicate(s)", outcome.inserted.len(), outcome.skipped.len());
+    Ok(outcome)
+}
@@ -0,0 +1,115 @@
+use crate::metadata::{self, SheetRef};
+use crate::{api, read_only, SecretString, SheetsError};
+use serde_json::{json, Value};
+
+// Inserting and deleting whole rows or columns. Positions are 1-based like
+// the row numbers and column letters in the UI (column 1 = A).
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dimension {
+    Rows,
+    Columns,
+}
+
+impl Dimension {
+    fn api_name(self) -> &'static str {
+        match self {
+            Dimension::Rows => "ROWS",
+            Dimension::Columns => "COLUMNS",
+        }
+    }
+}
+
+fn dimension_range(gid: u64, dimension: Dimension, start: usize, count: usize) -> Value {
+    json!({
+        "sheetId": gid,
+        "dimension": dimension.api_name(),
+        "startIndex": start - 1, // The API is zero-based
+        "endIndex": start - 1 + count,
+    })
+}
+
+// insertDimension adding `count` empty rows/columns before position `start`.
+// They take their formatting from the row or column before them, as when
+// inserting in the UI, except at the very start where there is none.
+pub fn insert_dimension_request(gid: u64, dimension: Dimension, start: usize, count: usize) -> Value {
+    json!({ "insertDimension": { "range": dimension_range(gid, dimension, start, count), "inheritFromBefore": start > 1 } })
+}
+
+pub fn delete_dimension_request(gid: u64, dimension: Dimension, start: usize, count: usize) -> Value {
+    json!({ "deleteDimension": { "range": dimension_range(gid, dimension, start, count) } })
+}
+
+// deleteDimension requests removing the given 1-based rows, one per run of
+// neighbouring rows and bottom run first, so each request's indices are
+// still valid after the ones before it
+pub fn delete_row_requests(gid: u64, rows: &[usize]) -> Vec<Value> {
+    let mut rows: Vec<usize> = rows.iter().copied().filter(|row| *row > 0).collect();
+    rows.sort_unstable();
+    rows.dedup();
+    let mut runs: Vec<(usize, usize)> = Vec::new(); // (start, count)
+    for row in rows {
+        match runs.last_mut() {
+            Some((start, count)) if *start + *count == row => *count += 1,
+            _ => runs.push((row, 1)),
+        }
+    }
+    runs.iter().rev().map(|&(start, count)| delete_dimension_request(gid, Dimension::Rows, start, count)).collect()
+}
+
+pub(crate) fn check_span(start: usize, count: usize) -> Result<(), String> {
+    if start == 0 || count == 0 {
+        return Err("positions start at 1 and count must be at least 1".to_string());
+    }
+    Ok(())
+}
+
+async fn send(
+    access_token: &SecretString,
+    operation: &str,
+    sheet: SheetRef,
+    request: impl Fn(u64) -> Value,
+) -> Result<(), SheetsError> {
+    read_only::guard(operation)?;
+    metadata::retry_with_fresh_metadata(|| async {
+        let gid = metadata::resolve_sheet(access_token, &sheet).await?;
+        api::v4::batch_update(access_token, vec![request(gid)]).await?;
+        Ok(())
+    })
+    .await?;
+    // Row and column counts changed
+    metadata::invalidate_metadata();
+    Ok(())
+}
+
+pub async fn insert_rows(
+    access_token: &SecretString,
+    sheet: impl Into<SheetRef>,
+    start: usize,
+    count: usize,
+) -> Result<(), SheetsError> {
+    check_span(start, count).map_err(SheetsError::Invalid)?;
+    send(access_token, "insert_rows", sheet.into(), |gid| insert_dimension_request(gid, Dimension::Rows, start, count)).await
+}
+
+pub async fn insert_columns(
+    access_token: &SecretString,
+    sheet: impl Into<SheetRef>,
+    start: usize,
+    count: usize,
+) -> Result<(), SheetsError> {
+    check_span(start, count).map_err(SheetsError::Invalid)?;
+    send(access_token, "insert_columns", sheet.into(), |gid| insert_dimension_request(gid, Dimension::Columns, start, count)).await
+}
+
+// Columns to the right move left; formulas pointing into the deleted
+// columns turn into #REF!
+pub async fn delete_columns(
+    access_token: &SecretString,
+    sheet: impl Into<SheetRef>,
+    start: usize,
+    count: usize,
+) -> Result<(), SheetsError> {
+    check_span(start, count).map_err(SheetsError::Invalid)?;
+    send(access_token, "delete_columns", sheet.into(), |gid| delete_dimension_request(gid, Dimension::Columns, start, count)).await
+}
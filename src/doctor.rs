@@ -0,0 +1,297 @@
+use crate::config::{self, ConfigError};
+use crate::har::SendRecorded;
+use crate::whoami::whoami;
+use crate::credentials::{self, CredentialKind};
+use crate::scope::{scope_urls, Scope};
+use crate::{exchange_token, Credentials, SHEETS_SCOPE};
+use chrono::{DateTime, Utc};
+use jsonwebtoken::EncodingKey;
+use reqwest::{Client, StatusCode};
+use std::env;
+use std::fmt;
+use std::path::Path;
+
+// Tokens are rejected with invalid_grant once the clock is off by a few minutes
+const MAX_CLOCK_SKEW_SECS: i64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Ok,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn ok(name: &'static str, detail: impl Into<String>) -> Self {
+        Check { name, status: CheckStatus::Ok, detail: detail.into(), fix: None }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Check { name, status: CheckStatus::Warn, detail: detail.into(), fix: Some(fix.into()) }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Check { name, status: CheckStatus::Fail, detail: detail.into(), fix: Some(fix.into()) }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DoctorReport {
+    pub checks: Vec<Check>,
+}
+
+impl DoctorReport {
+    pub fn has_failures(&self) -> bool {
+        self.checks.iter().any(|c| c.status == CheckStatus::Fail)
+    }
+}
+
+impl fmt::Display for DoctorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            let tag = match check.status {
+                CheckStatus::Ok => "[ok]  ",
+                CheckStatus::Warn => "[warn]",
+                CheckStatus::Fail => "[fail]",
+            };
+            writeln!(f, "{} {}: {}", tag, check.name, check.detail)?;
+            if let Some(fix) = &check.fix {
+                writeln!(f, "       fix: {}", fix)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Run every check in order; later checks are skipped when their inputs are broken
+pub async fn run_doctor() -> DoctorReport {
+    let mut report = DoctorReport::default();
+    let env_file = config::env_file_path();
+
+    match config::load_dotenv() {
+        Ok(()) if env_file.exists() => report.checks.push(Check::ok("env file", format!("loaded {}", env_file.display()))),
+        Ok(()) => report.checks.push(Check::warn(
+            "env file",
+            format!("{} not found, using process environment only", env_file.display()),
+            "create .env or point SHEETS_ENV_FILE at your config, e.g. via `init`",
+        )),
+        Err(e) => {
+            report.checks.push(Check::fail("env file", e.to_string(), "fix the line above; values spanning lines must be quoted"));
+            return report;
+        }
+    }
+
+    let adc = match credentials::adc_mode() {
+        Ok(adc) => adc,
+        Err(e) => {
+            report.checks.push(Check::fail("auth mode", e.to_string(), "set AUTH_MODE to service-account or adc, or remove it"));
+            return report;
+        }
+    };
+    if adc {
+        // No key to check; the token exchange below shows whether the chain works
+        match Credentials::from_env() {
+            Ok(credentials) => report.checks.push(Check::ok("credentials", describe_adc(&credentials))),
+            Err(e) => {
+                report.checks.push(Check::fail("credentials", e.to_string(), "fix the file GOOGLE_APPLICATION_CREDENTIALS or gcloud points at"));
+                return report;
+            }
+        }
+    }
+    let key_file = env::var_os("GOOGLE_APPLICATION_CREDENTIALS").filter(|_| !adc && env::var_os("SERVICE_ACCOUNT_EMAIL").is_none());
+    let (email, key) = match &key_file {
+        _ if adc => (Some("the signed-in account".to_string()), None),
+        Some(path) => match Credentials::from_json_file(path) {
+            Ok(credentials) => {
+                report.checks.push(Check::ok("service account", format!("{} (from {})", credentials.client_email, Path::new(path).display())));
+                (Some(credentials.client_email), Some(Ok(credentials.private_key)))
+            }
+            Err(e) => {
+                report.checks.push(Check::fail("service account", e.to_string(), "point GOOGLE_APPLICATION_CREDENTIALS at the service-account JSON key"));
+                return report;
+            }
+        },
+        None => {
+            let email = match config::require("SERVICE_ACCOUNT_EMAIL") {
+                Ok(email) => {
+                    report.checks.push(Check::ok("service account", email.clone()));
+                    Some(email)
+                }
+                Err(e) => {
+                    report.checks.push(Check::fail(
+                        "service account",
+                        e.to_string(),
+                        "set SERVICE_ACCOUNT_EMAIL to the client_email from the key file, or GOOGLE_APPLICATION_CREDENTIALS to the key file",
+                    ));
+                    None
+                }
+            };
+            (email, Some(config::private_key()))
+        }
+    };
+
+    let key_ok = match key {
+        None => true,
+        Some(Ok(key)) => match EncodingKey::from_rsa_pem(key.expose_secret().as_bytes()) {
+            Ok(_) => {
+                report.checks.push(Check::ok("private key", "valid RSA PEM"));
+                true
+            }
+            Err(e) => {
+                report.checks.push(Check::fail(
+                    "private key",
+                    format!("not a usable RSA key ({})", e),
+                    "copy private_key from the service-account JSON verbatim, including BEGIN/END lines",
+                ));
+                false
+            }
+        },
+        Some(Err(e)) => {
+            report.checks.push(Check::fail("private key", e.to_string(), private_key_fix(&e)));
+            false
+        }
+    };
+
+    let sheet_id = match config::sheet_id() {
+        Ok(id) => Some(id),
+        Err(e) => {
+            report.checks.push(Check::fail("spreadsheet", e.to_string(), "set SHEET_ID to the ID from the spreadsheet URL"));
+            None
+        }
+    };
+
+    let client = Client::new();
+    report.checks.push(check_clock(&client).await);
+
+    if email.is_none() || !key_ok {
+        return report;
+    }
+    let scopes = match Scope::from_env() {
+        Ok(scopes) => scope_urls(&scopes),
+        Err(e) => {
+            report.checks.push(Check::fail("token scopes", e.to_string(), "list spreadsheets.readonly, spreadsheets, drive.file or drive in SHEETS_SCOPES"));
+            return report;
+        }
+    };
+    let token = match exchange_token(&scopes).await {
+        Ok(token) => {
+            report.checks.push(Check::ok("token exchange", "access token issued"));
+            token
+        }
+        Err(e) => {
+            let message = e.to_string();
+            let fix = if adc {
+                "run `gcloud auth application-default login`, or check the instance has a service account attached"
+            } else if message.contains("unauthorized_client") && env::var_os("IMPERSONATE_USER").is_some() {
+                "grant the service account's client ID these scopes under domain-wide delegation in the Admin console"
+            } else if message.contains("invalid_grant") {
+                "check the clock skew above, and that the key has not been deleted in the Cloud console"
+            } else {
+                "make sure the key belongs to SERVICE_ACCOUNT_EMAIL and the account is enabled"
+            };
+            report.checks.push(Check::fail("token exchange", message, fix));
+            return report;
+        }
+    };
+
+    report.checks.push(match whoami(&token).await {
+        Ok(info) if info.can_write_sheets() => Check::ok("token scopes", info.scopes.join(" ")),
+        Ok(info) => Check::warn(
+            "token scopes",
+            format!("token lacks {} (has: {})", SHEETS_SCOPE, info.scopes.join(" ")),
+            "writes will fail with 403; request the spreadsheets scope",
+        ),
+        Err(e) => Check::warn("token scopes", e.to_string(), "check network access to oauth2.googleapis.com"),
+    });
+
+    // With impersonation the spreadsheet is opened as that user
+    let email = env::var("IMPERSONATE_USER").ok().filter(|user| !user.trim().is_empty()).or(email);
+    if let (Some(sheet_id), Some(email)) = (sheet_id, email) {
+        let url = format!(
+            "https://sheets.googleapis.com/v4/spreadsheets/{}?fields=properties.title",
+            sheet_id
+        );
+        let check = match crate::api::v4::dispatch(client.get(&url).bearer_auth(token.expose_secret())).await {
+            Ok(response) => match response.status() {
+                StatusCode::OK => {
+                    let body: serde_json::Value = response.json().await.unwrap_or_default();
+                    let title = body["properties"]["title"].as_str().unwrap_or("untitled").to_string();
+                    Check::ok("spreadsheet access", format!("can open '{}'", title))
+                }
+                StatusCode::FORBIDDEN => Check::fail(
+                    "spreadsheet access",
+                    "permission denied",
+                    format!("share the spreadsheet with {} (Editor for writes, Viewer for reads)", email),
+                ),
+                StatusCode::NOT_FOUND => Check::fail(
+                    "spreadsheet access",
+                    format!("spreadsheet '{}' not found", sheet_id),
+                    "copy the ID between /d/ and /edit in the spreadsheet URL",
+                ),
+                status => Check::fail("spreadsheet access", format!("unexpected HTTP {}", status), "retry; if it persists check Google's status page"),
+            },
+            Err(e) => Check::fail("spreadsheet access", format!("request failed: {}", e), "check network/proxy access to sheets.googleapis.com"),
+        };
+        report.checks.push(check);
+    }
+
+    report
+}
+
+fn describe_adc(credentials: &Credentials) -> String {
+    match &credentials.kind {
+        CredentialKind::ServiceAccount => format!("service account {} (Application Default Credentials)", credentials.client_email),
+        CredentialKind::AuthorizedUser { .. } => match credentials::quota_project() {
+            Some(project) => format!("gcloud user credentials, quota project {}", project),
+            None => "gcloud user credentials, no quota project (set GOOGLE_CLOUD_QUOTA_PROJECT if calls fail with 403)".to_string(),
+        },
+        CredentialKind::MetadataServer => format!("instance service account via the metadata server at {}", credentials::metadata_host()),
+    }
+}
+
+fn private_key_fix(error: &ConfigError) -> &'static str {
+    match error {
+        ConfigError::Missing { .. } => "set PRIVATE_KEY to private_key from the service-account JSON",
+        _ => "wrap the key in double quotes, or keep it on one line with \\n escapes",
+    }
+}
+
+// Compare the local clock against the Date header returned by Google
+async fn check_clock(client: &Client) -> Check {
+    let response = match client.get("https://oauth2.googleapis.com/token").send_recorded().await {
+        Ok(response) => response,
+        Err(e) => {
+            return Check::warn(
+                "clock skew",
+                format!("could not reach oauth2.googleapis.com: {}", e),
+                "check network/proxy access to Google",
+            )
+        }
+    };
+    let server_time = response
+        .headers()
+        .get(reqwest::header::DATE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| DateTime::parse_from_rfc2822(v).ok());
+    let Some(server_time) = server_time else {
+        return Check::warn("clock skew", "Google did not return a Date header", "verify NTP is running");
+    };
+    let skew = Utc::now().signed_duration_since(server_time).num_seconds();
+    if skew.abs() > MAX_CLOCK_SKEW_SECS {
+        Check::fail(
+            "clock skew",
+            format!("local clock is {}s {} Google", skew.abs(), if skew > 0 { "ahead of" } else { "behind" }),
+            "sync the system clock (e.g. enable NTP / `timedatectl set-ntp true`)",
+        )
+    } else {
+        Check::ok("clock skew", format!("{}s", skew))
+    }
+}
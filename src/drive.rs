@@ -0,0 +1,283 @@
+use crate::output::write_atomic;
+use crate::revisions::XLSX_MIME;
+use crate::{config, summary, SecretString, SheetsError};
+use chrono::{DateTime, SecondsFormat, Utc};
+use futures::stream::{self, Stream, TryStreamExt};
+use reqwest::{Client, Method};
+use serde::Deserialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::path::Path;
+
+// Drive calls need a token with one of these scopes as well as SHEETS_SCOPE,
+// e.g. access_token_for_scopes(&[SHEETS_SCOPE, DRIVE_METADATA_SCOPE])
+pub const DRIVE_METADATA_SCOPE: &str = "https://www.googleapis.com/auth/drive.metadata.readonly";
+pub const DRIVE_SCOPE: &str = "https://www.googleapis.com/auth/drive";
+pub const DRIVE_READONLY_SCOPE: &str = "https://www.googleapis.com/auth/drive.readonly";
+pub const DRIVE_FILE_SCOPE: &str = "https://www.googleapis.com/auth/drive.file";
+
+pub(crate) const FILES_URL: &str = "https://www.googleapis.com/drive/v3/files";
+const SPREADSHEET_MIME: &str = "application/vnd.google-apps.spreadsheet";
+const FILE_FIELDS: &str = "id,name,modifiedTime,parents,webViewLink,appProperties";
+
+// appProperties key set on every spreadsheet this tool tags
+pub const MANAGED_BY_KEY: &str = "managed_by";
+pub const MANAGED_BY_VALUE: &str = "google-sheet";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DriveFile {
+    pub id: String,
+    pub name: String,
+    pub modified_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub parents: Vec<String>,
+    pub web_view_link: Option<String>,
+    // Only the properties set by this service account's project are visible
+    #[serde(default)]
+    pub app_properties: BTreeMap<String, String>,
+}
+
+// Filters for list_spreadsheets; all set fields must match. Only spreadsheets
+// that aren't in the trash are listed.
+#[derive(Debug, Clone, Default)]
+pub struct DriveQuery {
+    pub name_contains: Option<String>,
+    pub modified_after: Option<DateTime<Utc>>,
+    pub modified_before: Option<DateTime<Utc>>,
+    pub parent: Option<String>, // Folder ID
+    pub app_properties: Vec<(String, String)>, // Tags set by tag_spreadsheet
+    pub raw: Option<String>,    // Extra Drive query clause, ANDed in as is
+}
+
+impl DriveQuery {
+    pub fn name_contains(name: &str) -> Self {
+        DriveQuery { name_contains: Some(name.to_string()), ..Default::default() }
+    }
+
+    // The Drive `q` parameter
+    pub fn to_q(&self) -> String {
+        let mut clauses = vec![format!("mimeType = '{}'", SPREADSHEET_MIME), "trashed = false".to_string()];
+        if let Some(name) = &self.name_contains {
+            clauses.push(format!("name contains '{}'", quote(name)));
+        }
+        if let Some(after) = &self.modified_after {
+            clauses.push(format!("modifiedTime > '{}'", after.to_rfc3339_opts(SecondsFormat::Secs, true)));
+        }
+        if let Some(before) = &self.modified_before {
+            clauses.push(format!("modifiedTime < '{}'", before.to_rfc3339_opts(SecondsFormat::Secs, true)));
+        }
+        if let Some(parent) = &self.parent {
+            clauses.push(format!("'{}' in parents", quote(parent)));
+        }
+        for (key, value) in &self.app_properties {
+            clauses.push(format!("appProperties has {{ key='{}' and value='{}' }}", quote(key), quote(value)));
+        }
+        if let Some(raw) = &self.raw {
+            clauses.push(format!("({})", raw));
+        }
+        clauses.join(" and ")
+    }
+}
+
+// Escape a value for a single-quoted Drive query string
+pub(crate) fn quote(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FileList {
+    #[serde(default)]
+    files: Vec<DriveFile>,
+    next_page_token: Option<String>,
+}
+
+struct Pager<'a> {
+    access_token: &'a SecretString,
+    q: String,
+    buffered: VecDeque<DriveFile>,
+    next_page: Option<String>,
+    done: bool,
+}
+
+// Spreadsheets visible to the service account, one page request at a time as
+// the stream is polled. nextPageToken is followed until Drive stops sending it.
+pub fn list_spreadsheets<'a>(
+    access_token: &'a SecretString,
+    query: &DriveQuery,
+) -> impl Stream<Item = Result<DriveFile, SheetsError>> + 'a {
+    let pager = Pager { access_token, q: query.to_q(), buffered: VecDeque::new(), next_page: None, done: false };
+    stream::try_unfold(pager, |mut pager| async move {
+        loop {
+            if let Some(file) = pager.buffered.pop_front() {
+                return Ok(Some((file, pager)));
+            }
+            if pager.done {
+                return Ok(None);
+            }
+            let page = fetch_page(pager.access_token, &pager.q, pager.next_page.as_deref()).await?;
+            pager.buffered.extend(page.files);
+            pager.done = page.next_page_token.is_none();
+            pager.next_page = page.next_page_token;
+        }
+    })
+}
+
+async fn fetch_page(
+    access_token: &SecretString,
+    q: &str,
+    page_token: Option<&str>,
+) -> Result<FileList, SheetsError> {
+    let fields = format!("nextPageToken,files({})", FILE_FIELDS);
+    let mut params = vec![
+        ("q", q),
+        ("fields", fields.as_str()),
+        ("pageSize", "100"),
+        ("orderBy", "modifiedTime desc"),
+        ("supportsAllDrives", "true"),
+        ("includeItemsFromAllDrives", "true"),
+    ];
+    if let Some(token) = page_token {
+        params.push(("pageToken", token));
+    }
+    let url = reqwest::Url::parse_with_params(FILES_URL, &params).map_err(|e| SheetsError::Invalid(e.to_string()))?;
+    let response = crate::api::v4::send(access_token, Method::GET, url.as_str(), None).await?;
+    Ok(serde_json::from_value(response)?)
+}
+
+// Collect the whole listing
+pub async fn list_all_spreadsheets(
+    access_token: &SecretString,
+    query: &DriveQuery,
+) -> Result<Vec<DriveFile>, SheetsError> {
+    list_spreadsheets(access_token, query).try_collect().await
+}
+
+// Add or overwrite appProperties on a spreadsheet (e.g. env=prod, owner=returns-team)
+// and mark it as managed by this tool. An empty value removes that tag.
+// Needs DRIVE_SCOPE unless the file was created through this project.
+pub async fn tag_spreadsheet(
+    access_token: &SecretString,
+    spreadsheet_id: &str,
+    properties: &[(&str, &str)],
+) -> Result<DriveFile, SheetsError> {
+    let mut tags = serde_json::Map::new();
+    tags.insert(MANAGED_BY_KEY.to_string(), MANAGED_BY_VALUE.into());
+    for (key, value) in properties {
+        let value = if value.is_empty() { serde_json::Value::Null } else { (*value).into() };
+        tags.insert(key.to_string(), value);
+    }
+    let url = format!("{}/{}?fields={}&supportsAllDrives=true", FILES_URL, spreadsheet_id, FILE_FIELDS);
+    let body = serde_json::json!({ "appProperties": tags });
+    let response = crate::api::v4::send(access_token, Method::PATCH, &url, Some(&body)).await?;
+    Ok(serde_json::from_value(response)?)
+}
+
+// Spreadsheets tagged by tag_spreadsheet, optionally narrowed by more tags
+pub fn list_managed_spreadsheets<'a>(
+    access_token: &'a SecretString,
+    tags: &[(&str, &str)],
+) -> impl Stream<Item = Result<DriveFile, SheetsError>> + 'a {
+    let mut app_properties = vec![(MANAGED_BY_KEY.to_string(), MANAGED_BY_VALUE.to_string())];
+    app_properties.extend(tags.iter().map(|(k, v)| (k.to_string(), v.to_string())));
+    list_spreadsheets(access_token, &DriveQuery { app_properties, ..Default::default() })
+}
+
+async fn set_trashed(
+    access_token: &SecretString,
+    spreadsheet_id: &str,
+    trashed: bool,
+) -> Result<(), SheetsError> {
+    let url = format!("{}/{}?supportsAllDrives=true", FILES_URL, spreadsheet_id);
+    let body = serde_json::json!({ "trashed": trashed });
+    crate::api::v4::send(access_token, Method::PATCH, &url, Some(&body)).await?;
+    Ok(())
+}
+
+// Move to the Drive trash; Drive empties it after 30 days. Needs DRIVE_SCOPE.
+pub async fn trash_spreadsheet(access_token: &SecretString, spreadsheet_id: &str) -> Result<(), SheetsError> {
+    set_trashed(access_token, spreadsheet_id, true).await
+}
+
+pub async fn restore_spreadsheet(access_token: &SecretString, spreadsheet_id: &str) -> Result<(), SheetsError> {
+    set_trashed(access_token, spreadsheet_id, false).await
+}
+
+// Permanent, skips the trash. Only the owner can delete.
+pub async fn delete_spreadsheet(access_token: &SecretString, spreadsheet_id: &str) -> Result<(), SheetsError> {
+    let url = format!("{}/{}?supportsAllDrives=true", FILES_URL, spreadsheet_id);
+    crate::api::v4::send(access_token, Method::DELETE, &url, None).await?;
+    Ok(())
+}
+
+// Download a spreadsheet converted to `mime` through files/export, e.g.
+// XLSX_MIME for every tab with its formatting; CSV and PDF exports only carry
+// the first tab. Drive refuses files over 10 MB. Needs DRIVE_READONLY_SCOPE
+// or DRIVE_SCOPE.
+pub async fn export_spreadsheet(
+    access_token: &SecretString,
+    spreadsheet_id: &str,
+    mime: &str,
+) -> Result<Vec<u8>, SheetsError> {
+    let url = reqwest::Url::parse_with_params(&format!("{}/{}/export", FILES_URL, spreadsheet_id), &[("mimeType", mime)]).map_err(|e| SheetsError::Invalid(e.to_string()))?;
+    summary::api_call();
+    let response = crate::api::v4::dispatch(Client::new().get(url).bearer_auth(access_token.expose_secret())).await?;
+    let (status, headers) = (response.status(), response.headers().clone());
+    let bytes = response.bytes().await?;
+    if !status.is_success() {
+        let body = serde_json::from_slice(&bytes).unwrap_or_default();
+        if let Some(error) = SheetsError::from_response(status, &headers, &body, &format!("exporting {}", spreadsheet_id)) {
+            return Err(error);
+        }
+    }
+    Ok(bytes.to_vec())
+}
+
+// The whole configured workbook (SHEET_ID) as an .xlsx file at `path`, for an
+// offline snapshot. Returns the bytes written.
+pub async fn export_spreadsheet_xlsx(access_token: &SecretString, path: &Path) -> Result<usize, SheetsError> {
+    let bytes = export_spreadsheet(access_token, &config::sheet_id()?, XLSX_MIME).await?;
+    write_atomic(path, &bytes, 0)?;
+    Ok(bytes.len())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetentionAction {
+    Trash,
+    Delete,
+}
+
+// Clean up generated per-run workbooks: managed spreadsheets matching `tags`
+// that haven't been modified for `max_age` are trashed (or deleted)
+#[derive(Debug, Clone)]
+pub struct RetentionPolicy {
+    pub max_age: chrono::Duration,
+    pub action: RetentionAction,
+    pub tags: Vec<(String, String)>,
+}
+
+// Returns the files acted on (or that would be, with `dry_run`)
+pub async fn apply_retention(
+    access_token: &SecretString,
+    policy: &RetentionPolicy,
+    dry_run: bool,
+) -> Result<Vec<DriveFile>, SheetsError> {
+    let cutoff = Utc::now() - policy.max_age;
+    let mut app_properties = vec![(MANAGED_BY_KEY.to_string(), MANAGED_BY_VALUE.to_string())];
+    app_properties.extend(policy.tags.iter().cloned());
+    let query = DriveQuery { modified_before: Some(cutoff), app_properties, ..Default::default() };
+    let expired = list_all_spreadsheets(access_token, &query).await?;
+
+    for file in &expired {
+        if dry_run {
+            println!(" Would {:?} '{}' ({})", policy.action, file.name, file.id);
+            continue;
+        }
+        match policy.action {
+            RetentionAction::Trash => trash_spreadsheet(access_token, &file.id).await?,
+            RetentionAction::Delete => delete_spreadsheet(access_token, &file.id).await?,
+        }
+        println!(" {:?}: '{}' ({})", policy.action, file.name, file.id);
+    }
+    Ok(expired)
+}
@@ -0,0 +1,182 @@
+use crate::config::ConfigError;
+use crate::confirm::ConfirmationRequired;
+use crate::limits::LimitError;
+use crate::policy::PolicyViolation;
+use crate::read_only::ReadOnlyViolation;
+use crate::records::RecordError;
+use crate::table::TableError;
+use crate::verify::WriteVerificationError;
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+
+// What went wrong talking to Google, for callers that want to react to it
+// (retry later, ask for access, create the missing tab) rather than print it
+#[derive(Debug, Error)]
+pub enum SheetsError {
+    // Token exchange failed, or the API answered 401
+    #[error("authentication failed: {0}")]
+    Auth(String),
+    // Network, TLS or timeout; no usable response
+    #[error("request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    // Any other error payload from the API
+    #[error("{message} ({status}, HTTP {code})")]
+    Api { code: u16, status: String, message: String },
+    // A response we couldn't decode
+    #[error("unexpected response: {0}")]
+    Parse(String),
+    // Still throttled after the retries in api::v4::send_with
+    #[error("rate limited: {message}")]
+    RateLimited { message: String, retry_after: Option<Duration> },
+    #[error("not found: {0}")]
+    NotFound(String),
+    // Usually the spreadsheet isn't shared with the service account
+    #[error("permission denied: {0}")]
+    PermissionDenied(String),
+    // A filter, setting or row of ours that doesn't fit the sheet, caught before sending
+    #[error("{0}")]
+    Invalid(String),
+    // The sheet changed between a read and the write that depended on it
+    #[error("conflicting change: {0}")]
+    Conflict(String),
+    // Saving output locally failed
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Record(#[from] RecordError),
+    #[error(transparent)]
+    Table(#[from] TableError),
+    #[error(transparent)]
+    Verification(#[from] WriteVerificationError),
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    #[error(transparent)]
+    ReadOnly(#[from] ReadOnlyViolation),
+    #[error(transparent)]
+    Policy(#[from] PolicyViolation),
+    #[error(transparent)]
+    Confirmation(#[from] ConfirmationRequired),
+    #[error(transparent)]
+    Limit(#[from] LimitError),
+    // Google couldn't be reached, so the write was saved for replay (see journal)
+    #[error("Google unreachable; write queued in the journal as {id}")]
+    Journaled { id: String },
+    // A network failure shared by coalesced duplicate requests (see coalesce)
+    #[error(transparent)]
+    Coalesced(Arc<SheetsError>),
+}
+
+impl From<serde_json::Error> for SheetsError {
+    fn from(e: serde_json::Error) -> Self {
+        SheetsError::Parse(e.to_string())
+    }
+}
+
+// Google's {"error": {"code", "status", "message"}} payload
+#[derive(Debug, Deserialize)]
+struct ErrorBody {
+    error: ErrorDetail,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorDetail {
+    #[serde(default)]
+    code: u16,
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    message: String,
+}
+
+impl SheetsError {
+    // Classify a failed response. `context` (e.g. "POST .../values:append")
+    // prefixes the message; None if `body` isn't an error and `status` is a success.
+    pub fn from_response(status: StatusCode, headers: &HeaderMap, body: &Value, context: &str) -> Option<Self> {
+        let detail = match ErrorBody::deserialize(body) {
+            Ok(ErrorBody { error }) => error,
+            Err(_) if status.is_success() => return None,
+            Err(_) => ErrorDetail { code: status.as_u16(), status: String::new(), message: status.to_string() },
+        };
+        let code = if detail.code == 0 { status.as_u16() } else { detail.code };
+        let message = format!("{}: {}", context, detail.message);
+        Some(match (code, detail.status.as_str()) {
+            (401, _) | (_, "UNAUTHENTICATED") => SheetsError::Auth(message),
+            (403, _) | (_, "PERMISSION_DENIED") => SheetsError::PermissionDenied(message),
+            (404, _) | (_, "NOT_FOUND") => SheetsError::NotFound(message),
+            (429, _) | (_, "RESOURCE_EXHAUSTED") => SheetsError::RateLimited { message, retry_after: retry_after(headers) },
+            _ => SheetsError::Api { code, status: detail.status, message },
+        })
+    }
+
+    // Worth retrying later without changing anything
+    pub fn is_transient(&self) -> bool {
+        match self {
+            SheetsError::RateLimited { .. } => true,
+            SheetsError::Http(e) => e.is_timeout() || e.is_connect(),
+            SheetsError::Api { code, .. } => *code >= 500,
+            SheetsError::Coalesced(e) => e.is_transient(),
+            _ => false,
+        }
+    }
+
+    // No connection was made, so the request certainly wasn't applied
+    pub fn is_unreachable(&self) -> bool {
+        match self {
+            SheetsError::Http(e) => e.is_connect(),
+            SheetsError::Coalesced(e) => e.is_unreachable(),
+            _ => false,
+        }
+    }
+
+    // A 400 naming a tab or gid that doesn't exist, which is what a stale
+    // metadata cache produces once someone renames or deletes a tab
+    pub fn is_unknown_sheet(&self) -> bool {
+        match self {
+            SheetsError::Api { code: 400, message, .. } => {
+                ["Unable to parse range", "No grid with id", "Invalid sheet"].iter().any(|hint| message.contains(hint))
+            }
+            SheetsError::Coalesced(e) => e.is_unknown_sheet(),
+            _ => false,
+        }
+    }
+
+    // The same error for every caller of a coalesced request. Http and Io can't
+    // be copied (their errors aren't Clone), so those stay behind an Arc.
+    pub(crate) fn shared(error: &Arc<SheetsError>) -> Self {
+        match &**error {
+            SheetsError::Auth(message) => SheetsError::Auth(message.clone()),
+            SheetsError::Api { code, status, message } => {
+                SheetsError::Api { code: *code, status: status.clone(), message: message.clone() }
+            }
+            SheetsError::Parse(message) => SheetsError::Parse(message.clone()),
+            SheetsError::RateLimited { message, retry_after } => {
+                SheetsError::RateLimited { message: message.clone(), retry_after: *retry_after }
+            }
+            SheetsError::NotFound(message) => SheetsError::NotFound(message.clone()),
+            SheetsError::PermissionDenied(message) => SheetsError::PermissionDenied(message.clone()),
+            SheetsError::Invalid(message) => SheetsError::Invalid(message.clone()),
+            SheetsError::Conflict(message) => SheetsError::Conflict(message.clone()),
+            SheetsError::Record(e) => SheetsError::Record(e.clone()),
+            SheetsError::Table(e) => SheetsError::Table(e.clone()),
+            SheetsError::Verification(e) => SheetsError::Verification(e.clone()),
+            SheetsError::Config(e) => SheetsError::Config(e.clone()),
+            SheetsError::ReadOnly(e) => SheetsError::ReadOnly(e.clone()),
+            SheetsError::Policy(e) => SheetsError::Policy(e.clone()),
+            SheetsError::Confirmation(e) => SheetsError::Confirmation(e.clone()),
+            SheetsError::Limit(e) => SheetsError::Limit(e.clone()),
+            SheetsError::Journaled { id } => SheetsError::Journaled { id: id.clone() },
+            SheetsError::Http(_) | SheetsError::Io(_) | SheetsError::Coalesced(_) => SheetsError::Coalesced(error.clone()),
+        }
+    }
+}
+
+// Retry-After in seconds; the HTTP-date form isn't used by Google
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let seconds = headers.get(reqwest::header::RETRY_AFTER)?.to_str().ok()?.trim().parse().ok()?;
+    Some(Duration::from_secs(seconds))
+}
@@ -0,0 +1,58 @@
+use serde_json::Value;
+use std::path::Path;
+
+// File formats for exported rows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    // {"header", "filtered_data", "count"} as read_google_sheet has always written
+    #[default]
+    Json,
+    // Header row then one line per row, for Excel or other tools
+    Csv,
+}
+
+impl OutputFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "json" => Some(OutputFormat::Json),
+            "csv" => Some(OutputFormat::Csv),
+            _ => None,
+        }
+    }
+
+    // By file extension: .csv is CSV, anything else JSON
+    pub fn for_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => OutputFormat::Csv,
+            _ => OutputFormat::Json,
+        }
+    }
+}
+
+// RFC 4180 CSV with CRLF line endings. Fields holding a comma, quote or
+// line break are quoted, with quotes doubled.
+pub fn to_csv(header: &[Value], rows: &[Vec<Value>]) -> String {
+    let mut csv = String::new();
+    for row in std::iter::once(header).chain(rows.iter().map(Vec::as_slice)) {
+        let fields: Vec<String> = row.iter().map(|cell| csv_field(&cell_text(cell))).collect();
+        csv.push_str(&fields.join(","));
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+fn cell_text(cell: &Value) -> String {
+    match cell {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
@@ -0,0 +1,78 @@
+use crate::drive::list_managed_spreadsheets;
+use crate::{SecretString, SheetsClient, SheetsError, SpreadsheetId};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use std::collections::{BTreeMap, HashMap};
+
+pub const DEFAULT_MAX_IN_FLIGHT: usize = 4;
+
+// Route key -> spreadsheet ID, from managed spreadsheets tagged
+// `<tag_key>=<route key>` (see drive::tag_spreadsheet). The token needs a
+// Drive scope. Two spreadsheets with the same tag value is an error.
+pub async fn managed_registry(
+    drive_token: &SecretString,
+    tag_key: &str,
+) -> Result<HashMap<String, String>, SheetsError> {
+    let files: Vec<_> = list_managed_spreadsheets(drive_token, &[]).try_collect().await?;
+    let mut registry = HashMap::new();
+    for file in files {
+        let Some(key) = file.app_properties.get(tag_key) else { continue };
+        if let Some(existing) = registry.insert(key.clone(), file.id.clone()) {
+            return Err(SheetsError::Conflict(format!("both {} and {} are tagged {}={}", existing, file.id, tag_key, key)));
+        }
+    }
+    Ok(registry)
+}
+
+#[derive(Debug, Default)]
+pub struct FanoutOutcome {
+    pub appended: BTreeMap<String, (String, usize)>, // Route key -> (spreadsheet ID, rows written)
+    pub failed: BTreeMap<String, String>,            // Route key -> error
+    pub unrouted: Vec<Vec<String>>,                  // No key, or a key missing from the registry
+}
+
+// Split `rows` by `route` (e.g. the channel column), then append each group to
+// `range` in its key's spreadsheet, at most `max_in_flight` spreadsheets at a
+// time. A failing destination doesn't stop the others; check `failed`.
+pub async fn fanout_append(
+    client: &SheetsClient,
+    registry: &HashMap<String, String>,
+    range: &str,
+    rows: Vec<Vec<String>>,
+    route: impl Fn(&[String]) -> Option<String>,
+    max_in_flight: usize,
+) -> FanoutOutcome {
+    let mut outcome = FanoutOutcome::default();
+    let mut groups: BTreeMap<String, Vec<Vec<String>>> = BTreeMap::new();
+    for row in rows {
+        match route(&row).filter(|key| registry.contains_key(key)) {
+            Some(key) => groups.entry(key).or_default().push(row),
+            None => outcome.unrouted.push(row),
+        }
+    }
+
+    let results: Vec<_> = stream::iter(groups)
+        .map(|(key, rows)| async move {
+            let id = registry[&key].clone();
+            let count = rows.len();
+            let result = match SpreadsheetId::new(&id) {
+                Ok(spreadsheet) => client.for_spreadsheet(spreadsheet).append(range, rows).await.map_err(|e| e.to_string()),
+                Err(e) => Err(e),
+            };
+            (key, id, count, result)
+        })
+        .buffer_unordered(max_in_flight.max(1))
+        .collect()
+        .await;
+
+    for (key, id, count, result) in results {
+        match result {
+            Ok(_) => {
+                outcome.appended.insert(key, (id, count));
+            }
+            Err(e) => {
+                outcome.failed.insert(key, e);
+            }
+        }
+    }
+    outcome
+}
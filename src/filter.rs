@@ -0,0 +1,336 @@
+use crate::aggregate::compare_cell;
+use crate::coerce::{cell_matches, cell_text};
+use crate::numbers::{parse_amount, Amount, NumberLocale};
+use crate::ordering::compare_cells;
+use crate::pattern::Pattern;
+use crate::schema::Schema;
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::env;
+use std::fmt;
+
+// Row filters over named columns, resolved against the header row:
+//
+//   Filter::eq("CHANNEL", "AryfS").and(Filter::gt("AMOUNT", "100"))
+//
+// Equality uses coerce::cell_matches, so "FALSE" matches a real boolean and
+// "12" a real number. gt/lt compare as amounts when both sides are numbers,
+// including money like "£1,234.50" read in the column's schema locale (exact
+// with the `decimal` feature), and as text otherwise (ISO dates sort
+// correctly either way).
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Column {
+    // Header text, matched trimmed and case-insensitively
+    Name(String),
+    // 0-based index, for sheets without a usable header
+    Index(usize),
+}
+
+impl From<&str> for Column {
+    fn from(name: &str) -> Self {
+        Column::Name(name.to_string())
+    }
+}
+
+impl From<String> for Column {
+    fn from(name: String) -> Self {
+        Column::Name(name)
+    }
+}
+
+impl From<usize> for Column {
+    fn from(index: usize) -> Self {
+        Column::Index(index)
+    }
+}
+
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Column::Name(name) => write!(f, "{}", name),
+            Column::Index(index) => write!(f, "column {}", index + 1),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Condition {
+    Eq(String),
+    Ne(String),
+    // Case-insensitive substring of the cell's text
+    Contains(String),
+    Gt(String),
+    Lt(String),
+    Regex(Pattern),
+}
+
+#[derive(Debug, Clone)]
+pub enum Filter {
+    Where { column: Column, condition: Condition },
+    All(Vec<Filter>),
+    Any(Vec<Filter>),
+}
+
+impl Filter {
+    fn of(column: impl Into<Column>, condition: Condition) -> Self {
+        Filter::Where { column: column.into(), condition }
+    }
+
+    // Matches every row
+    pub fn all() -> Self {
+        Filter::All(Vec::new())
+    }
+
+    pub fn eq(column: impl Into<Column>, value: &str) -> Self {
+        Filter::of(column, Condition::Eq(value.to_string()))
+    }
+
+    pub fn ne(column: impl Into<Column>, value: &str) -> Self {
+        Filter::of(column, Condition::Ne(value.to_string()))
+    }
+
+    pub fn contains(column: impl Into<Column>, text: &str) -> Self {
+        Filter::of(column, Condition::Contains(text.to_string()))
+    }
+
+    pub fn gt(column: impl Into<Column>, value: &str) -> Self {
+        Filter::of(column, Condition::Gt(value.to_string()))
+    }
+
+    pub fn lt(column: impl Into<Column>, value: &str) -> Self {
+        Filter::of(column, Condition::Lt(value.to_string()))
+    }
+
+    // Syntax as in pattern::Pattern; an invalid pattern is an error here,
+    // not a filter that silently matches nothing
+    pub fn regex(column: impl Into<Column>, pattern: &str) -> Result<Self, String> {
+        Ok(Filter::of(column, Condition::Regex(Pattern::new(pattern)?)))
+    }
+
+    pub fn and(self, other: Filter) -> Self {
+        match self {
+            Filter::All(mut filters) => {
+                filters.push(other);
+                Filter::All(filters)
+            }
+            filter => Filter::All(vec![filter, other]),
+        }
+    }
+
+    pub fn or(self, other: Filter) -> Self {
+        match self {
+            Filter::Any(mut filters) => {
+                filters.push(other);
+                Filter::Any(filters)
+            }
+            filter => Filter::Any(vec![filter, other]),
+        }
+    }
+
+    // ROW_FILTER, e.g. "CHANNEL = AryfS AND REFUNDED = FALSE OR NOTES ~ urgent".
+    // Operators: = != ~ (contains) > < =~ (regex). AND binds tighter than OR.
+    pub fn from_env() -> Result<Option<Self>, String> {
+        match env::var("ROW_FILTER") {
+            Ok(spec) if !spec.trim().is_empty() => Filter::parse(&spec).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let any = split_keyword(spec, "OR")
+            .into_iter()
+            .map(|clause| {
+                let all = split_keyword(clause, "AND").into_iter().map(parse_condition).collect::<Result<Vec<_>, _>>()?;
+                Ok(if all.len() == 1 { all.into_iter().next().unwrap() } else { Filter::All(all) })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(if any.len() == 1 { any.into_iter().next().unwrap() } else { Filter::Any(any) })
+    }
+
+    // Resolve column names against `header` once, before looping over rows
+    pub fn compile(&self, header: &[Value]) -> Result<CompiledFilter<'_>, String> {
+        self.compile_with(header, &Schema::default())
+    }
+
+    // compile, reading numbers in each column's locale from `schema`
+    pub fn compile_with(&self, header: &[Value], schema: &Schema) -> Result<CompiledFilter<'_>, String> {
+        Ok(match self {
+            Filter::Where { column, condition } => {
+                let index = match column {
+                    Column::Index(index) => *index,
+                    Column::Name(name) => header
+                        .iter()
+                        .position(|h| h.as_str().is_some_and(|h| h.trim().eq_ignore_ascii_case(name.trim())))
+                        .ok_or_else(|| format!("can't filter on '{}': no such column", name))?,
+                };
+                let locale = schema.locale(header.get(index).and_then(Value::as_str).unwrap_or_default());
+                let threshold = match condition {
+                    Condition::Gt(value) | Condition::Lt(value) => parse_amount(value, locale),
+                    _ => None,
+                };
+                CompiledFilter::Where { index, width: header.len(), condition, locale, threshold }
+            }
+            Filter::All(filters) => CompiledFilter::All(filters.iter().map(|f| f.compile_with(header, schema)).collect::<Result<_, _>>()?),
+            Filter::Any(filters) => CompiledFilter::Any(filters.iter().map(|f| f.compile_with(header, schema)).collect::<Result<_, _>>()?),
+        })
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |f: &mut fmt::Formatter<'_>, filters: &[Filter], keyword: &str| {
+            for (i, filter) in filters.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " {} ", keyword)?;
+                }
+                match filter {
+                    Filter::Where { .. } => write!(f, "{}", filter)?,
+                    _ => write!(f, "({})", filter)?,
+                }
+            }
+            Ok(())
+        };
+        match self {
+            Filter::Where { column, condition } => match condition {
+                Condition::Eq(value) => write!(f, "{} = '{}'", column, value),
+                Condition::Ne(value) => write!(f, "{} != '{}'", column, value),
+                Condition::Contains(text) => write!(f, "{} contains '{}'", column, text),
+                Condition::Gt(value) => write!(f, "{} > '{}'", column, value),
+                Condition::Lt(value) => write!(f, "{} < '{}'", column, value),
+                Condition::Regex(pattern) => write!(f, "{} =~ /{}/", column, pattern),
+            },
+            Filter::All(filters) if filters.is_empty() => write!(f, "all rows"),
+            Filter::All(filters) => join(f, filters, "and"),
+            Filter::Any(filters) => join(f, filters, "or"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CompiledFilter<'a> {
+    Where {
+        index: usize,
+        width: usize,
+        condition: &'a Condition,
+        locale: NumberLocale,
+        // A gt/lt value that reads as an amount
+        threshold: Option<Amount>,
+    },
+    All(Vec<CompiledFilter<'a>>),
+    Any(Vec<CompiledFilter<'a>>),
+}
+
+impl CompiledFilter<'_> {
+    pub fn matches(&self, row: &[Value]) -> bool {
+        match self {
+            CompiledFilter::Where { index, width, condition, locale, threshold } => {
+                // Trailing empty cells are missing from the response; within the
+                // header's width they read as ""
+                let blank = Value::String(String::new());
+                let Some(cell) = row.get(*index).or((index < width).then_some(&blank)) else { return false };
+                match condition {
+                    Condition::Eq(value) => cell_matches(cell, value),
+                    Condition::Ne(value) => !cell_matches(cell, value),
+                    Condition::Contains(text) => cell_text(cell).to_lowercase().contains(&text.to_lowercase()),
+                    Condition::Gt(value) => compare(cell, value, *locale, *threshold) == Ordering::Greater,
+                    Condition::Lt(value) => compare(cell, value, *locale, *threshold) == Ordering::Less,
+                    Condition::Regex(pattern) => pattern.is_match(&cell_text(cell)),
+                }
+            }
+            CompiledFilter::All(filters) => filters.iter().all(|f| f.matches(row)),
+            CompiledFilter::Any(filters) => filters.iter().any(|f| f.matches(row)),
+        }
+    }
+}
+
+// As amounts when both sides are, else as ordering::compare_cells sorts them
+fn compare(cell: &Value, value: &str, locale: NumberLocale, threshold: Option<Amount>) -> Ordering {
+    match threshold.and_then(|threshold| compare_cell(cell, locale, threshold)) {
+        Some(ordering) => ordering,
+        None => compare_cells(Some(cell), Some(&Value::String(value.to_string()))),
+    }
+}
+
+// Split on a whole-word, case-insensitive keyword ("a AND b" -> ["a", "b"])
+fn split_keyword<'a>(spec: &'a str, keyword: &str) -> Vec<&'a str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for word in spec.split_whitespace() {
+        if word.eq_ignore_ascii_case(keyword) {
+            // `word` borrows from `spec`, so the pointer difference is its offset
+            let offset = word.as_ptr() as usize - spec.as_ptr() as usize;
+            parts.push(spec[start..offset].trim());
+            start = offset + word.len();
+        }
+    }
+    parts.push(spec[start..].trim());
+    parts
+}
+
+// The first operator in the clause splits it, so the value may contain any of
+// them ("NOTES ~ a=b")
+fn parse_condition(clause: &str) -> Result<Filter, String> {
+    let invalid = || format!("can't parse filter clause '{}': expected COLUMN <op> VALUE with = != ~ > < or =~", clause);
+    let at = clause.find(['=', '!', '~', '>', '<']).ok_or_else(invalid)?;
+    let op = ["!=", "=~", "=", "~", ">", "<"].into_iter().find(|op| clause[at..].starts_with(op)).ok_or_else(invalid)?;
+    let (column, value) = (clause[..at].trim(), clause[at + op.len()..].trim());
+    if column.is_empty() {
+        return Err(invalid());
+    }
+    match op {
+        "!=" => Ok(Filter::ne(column, value)),
+        "=~" => Filter::regex(column, value),
+        "=" => Ok(Filter::eq(column, value)),
+        "~" => Ok(Filter::contains(column, value)),
+        ">" => Ok(Filter::gt(column, value)),
+        _ => Ok(Filter::lt(column, value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn matches(spec: &str, header: &Value, row: &Value) -> bool {
+        let header = header.as_array().unwrap();
+        Filter::parse(spec).unwrap().compile(header).unwrap().matches(row.as_array().unwrap())
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let filter = Filter::parse("CHANNEL = AryfS and REFUNDED = FALSE OR NOTES ~ urgent").unwrap();
+        assert_eq!(filter.to_string(), "(CHANNEL = 'AryfS' and REFUNDED = 'FALSE') or NOTES contains 'urgent'");
+        assert_eq!(Filter::parse("  A != b ").unwrap().to_string(), "A != 'b'");
+    }
+
+    #[test]
+    fn first_operator_splits_the_clause() {
+        assert_eq!(Filter::parse("NOTES ~ a=b").unwrap().to_string(), "NOTES contains 'a=b'");
+        assert_eq!(Filter::parse("CODE =~ ^A-\\d+$").unwrap().to_string(), "CODE =~ /^A-\\d+$/");
+        assert_eq!(Filter::parse("AMOUNT > 100").unwrap().to_string(), "AMOUNT > '100'");
+        assert_eq!(Filter::parse("AMOUNT<-5").unwrap().to_string(), "AMOUNT < '-5'");
+        // Only whole words are keywords
+        assert_eq!(Filter::parse("BRAND = ORANGE").unwrap().to_string(), "BRAND = 'ORANGE'");
+    }
+
+    #[test]
+    fn malformed_clauses_are_errors() {
+        assert!(Filter::parse("no operator").is_err());
+        assert!(Filter::parse("= value").is_err());
+        assert!(Filter::parse("A = 1 AND").is_err());
+        assert!(Filter::parse("CODE =~ (").is_err());
+    }
+
+    #[test]
+    fn parsed_filters_match_rows() {
+        let header = json!(["CHANNEL", "REFUNDED", "AMOUNT", "NOTES"]);
+        let row = json!(["AryfS", false, "£1,250.00", "Urgent: call back"]);
+        assert!(matches("channel = AryfS AND refunded = FALSE", &header, &row));
+        assert!(matches("AMOUNT > 999 AND AMOUNT < 2000", &header, &row));
+        assert!(matches("NOTES ~ urgent", &header, &row));
+        assert!(!matches("CHANNEL != AryfS OR AMOUNT < 100", &header, &row));
+        assert!(Filter::parse("MISSING = x").unwrap().compile(header.as_array().unwrap()).is_err());
+    }
+}
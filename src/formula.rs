@@ -0,0 +1,659 @@
+use crate::a1::column_index;
+use crate::cell_value::CellValue;
+use serde_json::{Number, Value};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+
+// A small evaluator for the formulas the Workbook model holds, so computed
+// columns can be checked before they're written. It covers numbers, strings,
+// TRUE/FALSE, cell and range references (Tab!A1, 'My tab'!$B$2:C9, A:A),
+// + - * / ^ & and comparisons, and SUM, IF and VLOOKUP. Anything else is
+// #NAME?, so a result that differs from Sheets is a sign of a gap here.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormulaValue {
+    Number(f64),
+    Text(String),
+    Bool(bool),
+    Empty,
+    // As Sheets shows it: "#DIV/0!", "#N/A", "#REF!", "#VALUE!", "#NAME?", "#ERROR!"
+    Error(&'static str),
+}
+
+impl FormulaValue {
+    pub fn is_error(&self) -> bool {
+        matches!(self, FormulaValue::Error(_))
+    }
+
+    // The JSON a values.get with UNFORMATTED_VALUE would give
+    pub fn to_json(&self) -> Value {
+        match self {
+            FormulaValue::Number(n) => Number::from_f64(*n).map_or(Value::Null, Value::Number),
+            FormulaValue::Text(s) => Value::String(s.clone()),
+            FormulaValue::Bool(b) => Value::Bool(*b),
+            FormulaValue::Empty => Value::String(String::new()),
+            FormulaValue::Error(e) => Value::String(e.to_string()),
+        }
+    }
+
+    fn number(&self) -> Result<f64, &'static str> {
+        match self {
+            FormulaValue::Number(n) => Ok(*n),
+            FormulaValue::Bool(b) => Ok(if *b { 1.0 } else { 0.0 }),
+            FormulaValue::Empty => Ok(0.0),
+            FormulaValue::Text(s) if s.trim().is_empty() => Ok(0.0),
+            FormulaValue::Text(s) => s.trim().parse().map_err(|_| "#VALUE!"),
+            FormulaValue::Error(e) => Err(e),
+        }
+    }
+
+    fn truthy(&self) -> Result<bool, &'static str> {
+        match self {
+            FormulaValue::Bool(b) => Ok(*b),
+            FormulaValue::Number(n) => Ok(*n != 0.0),
+            FormulaValue::Empty => Ok(false),
+            FormulaValue::Text(s) if s.eq_ignore_ascii_case("true") => Ok(true),
+            FormulaValue::Text(s) if s.eq_ignore_ascii_case("false") => Ok(false),
+            FormulaValue::Text(_) => Err("#VALUE!"),
+            FormulaValue::Error(e) => Err(e),
+        }
+    }
+
+    fn text(&self) -> String {
+        match self {
+            FormulaValue::Number(n) => format_number(*n),
+            FormulaValue::Text(s) => s.clone(),
+            FormulaValue::Bool(b) => if *b { "TRUE" } else { "FALSE" }.to_string(),
+            FormulaValue::Empty => String::new(),
+            FormulaValue::Error(e) => e.to_string(),
+        }
+    }
+
+    fn from_cell(cell: &CellValue) -> Self {
+        match cell {
+            CellValue::Number(n) => FormulaValue::Number(*n),
+            CellValue::String(s) => FormulaValue::Text(s.clone()),
+            CellValue::Bool(b) => FormulaValue::Bool(*b),
+            CellValue::Empty | CellValue::Formula(_) => FormulaValue::Empty,
+        }
+    }
+}
+
+impl fmt::Display for FormulaValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+fn format_number(n: f64) -> String {
+    if n.fract() == 0.0 && n.abs() < 1e15 {
+        format!("{}", n as i64)
+    } else {
+        let rounded = (n * 1e9).round() / 1e9;
+        rounded.to_string()
+    }
+}
+
+// Where references are looked up; the Workbook model implements it
+pub(crate) trait Cells {
+    // None when there's no such tab
+    fn cell(&self, sheet: &str, column: usize, row: usize) -> Option<CellValue>;
+    fn used_rows(&self, sheet: &str) -> Option<usize>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Text(String),
+    Word(String),  // Function names, cell references, TRUE/FALSE
+    Sheet(String), // The tab before a '!'
+    Op(&'static str),
+}
+
+const OPS: &[&str] = &["<>", "<=", ">=", "+", "-", "*", "/", "^", "&", "=", "<", ">", "(", ")", ",", ":"];
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '"' || c == '\'' {
+            // "text" or a quoted tab name; the quote doubles to escape itself
+            let mut text = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => return Err(format!("unclosed {} in '{}'", c, input)),
+                    Some(q) if *q == c && chars.get(i + 1) == Some(&c) => {
+                        text.push(c);
+                        i += 2;
+                    }
+                    Some(q) if *q == c => break,
+                    Some(other) => {
+                        text.push(*other);
+                        i += 1;
+                    }
+                }
+            }
+            i += 1;
+            if c == '"' {
+                tokens.push(Token::Text(text));
+            } else if chars.get(i) == Some(&'!') {
+                tokens.push(Token::Sheet(text));
+                i += 1;
+            } else {
+                return Err(format!("quoted tab name '{}' without a '!'", text));
+            }
+        } else if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(char::is_ascii_digit)) {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                i += 1;
+                if i < chars.len() && (chars[i] == '+' || chars[i] == '-') {
+                    i += 1;
+                }
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            tokens.push(Token::Number(text.parse().map_err(|_| format!("bad number '{}'", text))?));
+        } else if c.is_alphabetic() || c == '_' || c == '$' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || "_.$".contains(chars[i])) {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            if chars.get(i) == Some(&'!') {
+                tokens.push(Token::Sheet(word));
+                i += 1;
+            } else {
+                tokens.push(Token::Word(word));
+            }
+        } else if let Some(op) = OPS.iter().find(|op| rest.starts_with(**op)) {
+            tokens.push(Token::Op(op));
+            i += op.len();
+        } else {
+            return Err(format!("unexpected '{}' in '{}'", c, input));
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Ref {
+    sheet: Option<String>,
+    column: usize,
+    row: Option<usize>, // None for a whole column (A:A)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(f64),
+    Text(String),
+    Bool(bool),
+    Cell(Ref),
+    Range(Ref, Ref),
+    Neg(Box<Expr>),
+    Bin(&'static str, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+// expr := concat (cmp concat)? ; concat := sum ('&' sum)* ; sum := product (('+'|'-') product)* ;
+// product := power (('*'|'/') power)* ; power := unary ('^' unary)* ; unary := ('-'|'+') unary | primary
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek_op(&self) -> Option<&'static str> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Op(op)) => Some(op),
+            _ => None,
+        }
+    }
+
+    fn eat(&mut self, op: &str) -> bool {
+        if self.peek_op() == Some(op) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn binary(&mut self, ops: &[&str], next: fn(&mut Self) -> Result<Expr, String>) -> Result<Expr, String> {
+        let mut left = next(self)?;
+        while let Some(op) = self.peek_op().filter(|op| ops.contains(op)) {
+            self.pos += 1;
+            left = Expr::Bin(op, Box::new(left), Box::new(next(self)?));
+        }
+        Ok(left)
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        let left = self.concat()?;
+        match self.peek_op().filter(|op| ["=", "<>", "<", ">", "<=", ">="].contains(op)) {
+            Some(op) => {
+                self.pos += 1;
+                Ok(Expr::Bin(op, Box::new(left), Box::new(self.concat()?)))
+            }
+            None => Ok(left),
+        }
+    }
+
+    fn concat(&mut self) -> Result<Expr, String> {
+        self.binary(&["&"], Self::sum)
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        self.binary(&["+", "-"], Self::product)
+    }
+
+    fn product(&mut self) -> Result<Expr, String> {
+        self.binary(&["*", "/"], Self::power)
+    }
+
+    fn power(&mut self) -> Result<Expr, String> {
+        self.binary(&["^"], Self::unary)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat("-") {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        if self.eat("+") {
+            return self.unary();
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        let token = self.tokens.get(self.pos).cloned().ok_or("formula ends too soon")?;
+        self.pos += 1;
+        match token {
+            Token::Number(n) => Ok(Expr::Number(n)),
+            Token::Text(s) => Ok(Expr::Text(s)),
+            Token::Op("(") => {
+                let inner = self.expr()?;
+                if !self.eat(")") {
+                    return Err("missing ')'".to_string());
+                }
+                Ok(inner)
+            }
+            Token::Sheet(sheet) => match self.tokens.get(self.pos).cloned() {
+                Some(Token::Word(word)) => {
+                    self.pos += 1;
+                    self.reference(Some(sheet), &word)
+                }
+                _ => Err(format!("expected a cell after '{}!'", sheet)),
+            },
+            Token::Word(word) if self.peek_op() == Some("(") => {
+                self.pos += 1;
+                let mut args = Vec::new();
+                if !self.eat(")") {
+                    loop {
+                        args.push(self.expr()?);
+                        if self.eat(")") {
+                            break;
+                        }
+                        if !self.eat(",") {
+                            return Err(format!("expected ',' or ')' in {}()", word));
+                        }
+                    }
+                }
+                Ok(Expr::Call(word.to_ascii_uppercase(), args))
+            }
+            Token::Word(word) if word.eq_ignore_ascii_case("true") => Ok(Expr::Bool(true)),
+            Token::Word(word) if word.eq_ignore_ascii_case("false") => Ok(Expr::Bool(false)),
+            Token::Word(word) => self.reference(None, &word),
+            Token::Op(op) => Err(format!("unexpected '{}'", op)),
+        }
+    }
+
+    // A1, $B$2, A1:C9 or A:A
+    fn reference(&mut self, sheet: Option<String>, word: &str) -> Result<Expr, String> {
+        let start = parse_ref(sheet.clone(), word)?;
+        if !self.eat(":") {
+            if start.row.is_none() {
+                return Err(format!("'{}' isn't a cell", word));
+            }
+            return Ok(Expr::Cell(start));
+        }
+        let end = match self.tokens.get(self.pos).cloned() {
+            Some(Token::Word(word)) => parse_ref(sheet, &word)?,
+            _ => return Err("expected a cell after ':'".to_string()),
+        };
+        self.pos += 1;
+        if start.row.is_none() != end.row.is_none() {
+            return Err("can't mix a column and a cell in one range".to_string());
+        }
+        Ok(Expr::Range(start, end))
+    }
+}
+
+fn parse_ref(sheet: Option<String>, word: &str) -> Result<Ref, String> {
+    let plain = word.replace('$', "");
+    let letters: String = plain.chars().take_while(char::is_ascii_alphabetic).collect();
+    let digits = &plain[letters.len()..];
+    let column = column_index(&letters).ok_or_else(|| format!("unknown name '{}'", word))?;
+    let row = match digits {
+        "" => None,
+        digits => Some(digits.parse::<usize>().ok().filter(|r| *r > 0).ok_or_else(|| format!("unknown name '{}'", word))?),
+    };
+    Ok(Ref { sheet, column, row })
+}
+
+fn parse(formula: &str) -> Result<Expr, String> {
+    let body = formula.trim().strip_prefix('=').unwrap_or(formula.trim());
+    let mut parser = Parser { tokens: tokenize(body)?, pos: 0 };
+    let expr = parser.expr()?;
+    if parser.pos != parser.tokens.len() {
+        return Err(format!("unexpected {:?} in '{}'", parser.tokens[parser.pos], formula));
+    }
+    Ok(expr)
+}
+
+// Evaluates formulas against `cells`, following references into other
+// formula cells. Results are memoised for one evaluator's lifetime, and a
+// reference back into a cell still being evaluated is a #REF! cycle.
+pub(crate) struct Evaluator<'a, C: Cells> {
+    cells: &'a C,
+    done: RefCell<HashMap<(String, usize, usize), FormulaValue>>,
+    active: RefCell<Vec<(String, usize, usize)>>,
+}
+
+impl<'a, C: Cells> Evaluator<'a, C> {
+    pub(crate) fn new(cells: &'a C) -> Self {
+        Evaluator { cells, done: RefCell::default(), active: RefCell::default() }
+    }
+
+    // A formula as if typed into a cell of `sheet`
+    pub(crate) fn formula(&self, sheet: &str, formula: &str) -> FormulaValue {
+        match parse(formula) {
+            Ok(expr) => self.eval(sheet, &expr),
+            Err(e) => {
+                tracing::debug!("can't parse '{}': {}", formula, e);
+                FormulaValue::Error("#ERROR!")
+            }
+        }
+    }
+
+    // A cell's value, with formulas replaced by their result
+    pub(crate) fn cell(&self, sheet: &str, column: usize, row: usize) -> FormulaValue {
+        let Some(cell) = self.cells.cell(sheet, column, row) else { return FormulaValue::Error("#REF!") };
+        let CellValue::Formula(formula) = cell else { return FormulaValue::from_cell(&cell) };
+        let key = (sheet.to_lowercase(), column, row);
+        if let Some(value) = self.done.borrow().get(&key) {
+            return value.clone();
+        }
+        if self.active.borrow().contains(&key) {
+            return FormulaValue::Error("#REF!");
+        }
+        self.active.borrow_mut().push(key.clone());
+        let value = self.formula(sheet, &formula);
+        self.active.borrow_mut().pop();
+        self.done.borrow_mut().insert(key, value.clone());
+        value
+    }
+
+    fn eval(&self, sheet: &str, expr: &Expr) -> FormulaValue {
+        match expr {
+            Expr::Number(n) => FormulaValue::Number(*n),
+            Expr::Text(s) => FormulaValue::Text(s.clone()),
+            Expr::Bool(b) => FormulaValue::Bool(*b),
+            Expr::Cell(r) => self.cell(r.sheet.as_deref().unwrap_or(sheet), r.column, r.row.unwrap_or(1)),
+            Expr::Range(..) => FormulaValue::Error("#VALUE!"), // Only functions take ranges
+            Expr::Neg(inner) => match self.eval(sheet, inner).number() {
+                Ok(n) => FormulaValue::Number(-n),
+                Err(e) => FormulaValue::Error(e),
+            },
+            Expr::Bin(op, a, b) => self.binary(op, self.eval(sheet, a), self.eval(sheet, b)),
+            Expr::Call(name, args) => self.call(sheet, name, args),
+        }
+    }
+
+    fn binary(&self, op: &str, a: FormulaValue, b: FormulaValue) -> FormulaValue {
+        if let FormulaValue::Error(e) = a {
+            return FormulaValue::Error(e);
+        }
+        if let FormulaValue::Error(e) = b {
+            return FormulaValue::Error(e);
+        }
+        if op == "&" {
+            return FormulaValue::Text(format!("{}{}", a.text(), b.text()));
+        }
+        if let Some(ordering) = compare_op(op) {
+            return FormulaValue::Bool(ordering(compare(&a, &b)));
+        }
+        let (a, b) = match (a.number(), b.number()) {
+            (Ok(a), Ok(b)) => (a, b),
+            (Err(e), _) | (_, Err(e)) => return FormulaValue::Error(e),
+        };
+        let n = match op {
+            "+" => a + b,
+            "-" => a - b,
+            "*" => a * b,
+            "/" if b == 0.0 => return FormulaValue::Error("#DIV/0!"),
+            "/" => a / b,
+            _ => a.powf(b),
+        };
+        if n.is_finite() {
+            FormulaValue::Number(n)
+        } else {
+            FormulaValue::Error("#NUM!")
+        }
+    }
+
+    // The cells of a range, row by row; a single cell counts as a 1x1 range
+    fn range(&self, sheet: &str, expr: &Expr) -> Result<Vec<Vec<FormulaValue>>, &'static str> {
+        let (start, end) = match expr {
+            Expr::Range(start, end) => (start, end),
+            Expr::Cell(cell) => (cell, cell),
+            _ => return Err("#VALUE!"),
+        };
+        let sheet = start.sheet.as_deref().unwrap_or(sheet);
+        let (first_row, last_row) = match (start.row, end.row) {
+            (Some(a), Some(b)) => (a.min(b), a.max(b)),
+            _ => (1, self.cells.used_rows(sheet).ok_or("#REF!")?),
+        };
+        let (first_column, last_column) = (start.column.min(end.column), start.column.max(end.column));
+        Ok((first_row..=last_row)
+            .map(|row| (first_column..=last_column).map(|column| self.cell(sheet, column, row)).collect())
+            .collect())
+    }
+
+    fn call(&self, sheet: &str, name: &str, args: &[Expr]) -> FormulaValue {
+        let result = match name {
+            "SUM" => self.sum(sheet, args),
+            "IF" => self.if_(sheet, args),
+            "VLOOKUP" => self.vlookup(sheet, args),
+            _ => Err("#NAME?"),
+        };
+        result.unwrap_or_else(FormulaValue::Error)
+    }
+
+    // Numbers in ranges are added and anything else there skipped; a direct
+    // argument must read as a number
+    fn sum(&self, sheet: &str, args: &[Expr]) -> Result<FormulaValue, &'static str> {
+        let mut total = 0.0;
+        for arg in args {
+            if matches!(arg, Expr::Range(..)) {
+                for value in self.range(sheet, arg)?.into_iter().flatten() {
+                    match value {
+                        FormulaValue::Number(n) => total += n,
+                        FormulaValue::Error(e) => return Err(e),
+                        _ => {}
+                    }
+                }
+            } else {
+                total += self.eval(sheet, arg).number()?;
+            }
+        }
+        Ok(FormulaValue::Number(total))
+    }
+
+    fn if_(&self, sheet: &str, args: &[Expr]) -> Result<FormulaValue, &'static str> {
+        let [condition, then, rest @ ..] = args else { return Err("#N/A") };
+        if rest.len() > 1 {
+            return Err("#N/A");
+        }
+        if self.eval(sheet, condition).truthy()? {
+            Ok(self.eval(sheet, then))
+        } else {
+            Ok(rest.first().map_or(FormulaValue::Bool(false), |otherwise| self.eval(sheet, otherwise)))
+        }
+    }
+
+    // VLOOKUP(key, range, index, [is_sorted]); is_sorted defaults to TRUE as
+    // in Sheets, returning the last row whose key is <= the one looked up
+    fn vlookup(&self, sheet: &str, args: &[Expr]) -> Result<FormulaValue, &'static str> {
+        let [key, range, index, rest @ ..] = args else { return Err("#N/A") };
+        let key = self.eval(sheet, key);
+        if let FormulaValue::Error(e) = key {
+            return Err(e);
+        }
+        let rows = self.range(sheet, range)?;
+        let index = self.eval(sheet, index).number()?.trunc();
+        let width = rows.first().map_or(0, Vec::len);
+        if index < 1.0 || index as usize > width {
+            return Err("#REF!");
+        }
+        let sorted = match rest.first() {
+            Some(flag) => self.eval(sheet, flag).truthy()?,
+            None => true,
+        };
+        let found = if sorted {
+            let mut last = None;
+            for (i, row) in rows.iter().enumerate() {
+                if row[0] == FormulaValue::Empty {
+                    continue;
+                }
+                match compare(&row[0], &key) {
+                    std::cmp::Ordering::Greater => break,
+                    _ => last = Some(i),
+                }
+            }
+            last
+        } else {
+            rows.iter().position(|row| row[0] != FormulaValue::Empty && compare(&row[0], &key) == std::cmp::Ordering::Equal)
+        };
+        let row = found.ok_or("#N/A")?;
+        Ok(rows[row][index as usize - 1].clone())
+    }
+}
+
+fn compare_op(op: &str) -> Option<fn(std::cmp::Ordering) -> bool> {
+    use std::cmp::Ordering::*;
+    Some(match op {
+        "=" => |o| o == Equal,
+        "<>" => |o| o != Equal,
+        "<" => |o| o == Less,
+        ">" => |o| o == Greater,
+        "<=" => |o| o != Greater,
+        ">=" => |o| o != Less,
+        _ => return None,
+    })
+}
+
+// Sheets' ordering: numbers < text < booleans; text ignores case, and an
+// empty cell counts as 0 or "" depending on the other side
+fn compare(a: &FormulaValue, b: &FormulaValue) -> std::cmp::Ordering {
+    use FormulaValue::*;
+    fn rank(v: &FormulaValue) -> u8 {
+        match v {
+            Number(_) | Empty => 0,
+            Text(_) => 1,
+            Bool(_) => 2,
+            Error(_) => 3,
+        }
+    }
+    match (a, b) {
+        (Empty, Text(s)) => "".cmp(s.to_lowercase().as_str()),
+        (Text(s), Empty) => s.to_lowercase().as_str().cmp(""),
+        (Text(x), Text(y)) => x.to_lowercase().cmp(&y.to_lowercase()),
+        (Bool(x), Bool(y)) => x.cmp(y),
+        (Number(_) | Empty, Number(_) | Empty) => {
+            let (x, y) = (a.number().unwrap_or(0.0), b.number().unwrap_or(0.0));
+            x.partial_cmp(&y).unwrap_or(std::cmp::Ordering::Equal)
+        }
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Tab name -> rows of cells
+    struct Sheet(HashMap<String, Vec<Vec<CellValue>>>);
+
+    impl Cells for Sheet {
+        fn cell(&self, sheet: &str, column: usize, row: usize) -> Option<CellValue> {
+            let rows = self.0.get(sheet)?;
+            Some(rows.get(row - 1).and_then(|r| r.get(column)).cloned().unwrap_or_default())
+        }
+
+        fn used_rows(&self, sheet: &str) -> Option<usize> {
+            self.0.get(sheet).map(Vec::len)
+        }
+    }
+
+    fn sheet() -> Sheet {
+        let n = CellValue::Number;
+        let s = |v: &str| CellValue::String(v.to_string());
+        let rows = vec![
+            vec![s("SKU"), s("Qty"), s("Price")],
+            vec![s("A-1"), n(2.0), n(3.5)],
+            vec![s("B-2"), n(4.0), n(1.25)],
+            vec![s("C-3"), CellValue::Formula("=B2+B3".to_string()), CellValue::Formula("=C4".to_string())],
+        ];
+        Sheet(HashMap::from([("Data".to_string(), rows)]))
+    }
+
+    fn eval(formula: &str) -> FormulaValue {
+        Evaluator::new(&sheet()).formula("Data", formula)
+    }
+
+    #[test]
+    fn arithmetic_and_precedence() {
+        assert_eq!(eval("=1+2*3"), FormulaValue::Number(7.0));
+        assert_eq!(eval("=(1+2)*3"), FormulaValue::Number(9.0));
+        assert_eq!(eval("=2^3-1"), FormulaValue::Number(7.0));
+        assert_eq!(eval("=-B2+1"), FormulaValue::Number(-1.0));
+        assert_eq!(eval("=1/0"), FormulaValue::Error("#DIV/0!"));
+    }
+
+    #[test]
+    fn concatenation() {
+        assert_eq!(eval("=A2&\"/\"&B2"), FormulaValue::Text("A-1/2".to_string()));
+    }
+
+    #[test]
+    fn references_and_functions() {
+        assert_eq!(eval("=SUM(B2:B3)"), FormulaValue::Number(6.0));
+        assert_eq!(eval("=Data!$B$4"), FormulaValue::Number(6.0));
+        assert_eq!(eval("=IF(B2>B3,\"more\",\"less\")"), FormulaValue::Text("less".to_string()));
+        assert_eq!(eval("=VLOOKUP(\"B-2\",A2:C3,3,FALSE)"), FormulaValue::Number(1.25));
+        assert_eq!(eval("=Missing!A1"), FormulaValue::Error("#REF!"));
+        assert_eq!(eval("=NOSUCH(1)"), FormulaValue::Error("#NAME?"));
+    }
+
+    #[test]
+    fn long_names_are_not_references() {
+        assert!(eval("=Reconciliations").is_error());
+    }
+
+    #[test]
+    fn cycles_are_ref_errors() {
+        assert_eq!(Evaluator::new(&sheet()).cell("Data", 2, 4), FormulaValue::Error("#REF!"));
+    }
+}
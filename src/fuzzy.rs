@@ -0,0 +1,162 @@
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+// Matches messy text cells ("DEBENHAMS ", "Debenhams UK", "Debenhms") against
+// a target value. Both sides are trimmed, lowercased and have runs of
+// whitespace collapsed before scoring.
+#[derive(Debug, Clone)]
+pub struct FuzzyFilter {
+    pub column: usize,
+    pub value: String,
+    pub threshold: f64, // 0.0..=1.0, 1.0 means an exact (normalized) match
+}
+
+// Build a filter on column index `col`. 0.85-0.9 works well for names.
+pub fn fuzzy_eq(col: usize, value: &str, threshold: f64) -> FuzzyFilter {
+    FuzzyFilter { column: col, value: value.to_string(), threshold: threshold.clamp(0.0, 1.0) }
+}
+
+// One row that passed the filter, with the cell text that matched
+#[derive(Debug, Clone)]
+pub struct FuzzyMatch {
+    pub row_index: usize, // Index into the rows passed in
+    pub variant: String,
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FuzzyResult {
+    pub matches: Vec<FuzzyMatch>,
+    pub variants: BTreeMap<String, usize>, // Each matched spelling and how often it appeared
+}
+
+impl FuzzyFilter {
+    // Score for a row, or None if it's below the threshold
+    pub fn score_row(&self, row: &[Value]) -> Option<(String, f64)> {
+        let text = row.get(self.column)?.as_str()?;
+        let score = similarity(text, &self.value);
+        (score >= self.threshold).then(|| (text.to_string(), score))
+    }
+
+    pub fn matches(&self, row: &[Value]) -> bool {
+        self.score_row(row).is_some()
+    }
+
+    // Rows without the header
+    pub fn apply(&self, rows: &[Vec<Value>]) -> FuzzyResult {
+        let mut result = FuzzyResult::default();
+        for (row_index, row) in rows.iter().enumerate() {
+            if let Some((variant, score)) = self.score_row(row) {
+                *result.variants.entry(variant.clone()).or_insert(0) += 1;
+                result.matches.push(FuzzyMatch { row_index, variant, score });
+            }
+        }
+        result
+    }
+}
+
+fn normalize(text: &str) -> Vec<char> {
+    text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase().chars().collect()
+}
+
+// Best of Jaro-Winkler (good for typos) and a prefix-containment score (good
+// for suffixes like "Debenhams UK"), on normalized text
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (normalize(a), normalize(b));
+    if a == b {
+        return 1.0;
+    }
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let (short, long) = if a.len() <= b.len() { (&a, &b) } else { (&b, &a) };
+    let contained = if long.starts_with(short) && long.get(short.len()) == Some(&' ') {
+        // A whole-word prefix: "debenhams" in "debenhams uk"
+        0.9 + 0.1 * short.len() as f64 / long.len() as f64
+    } else {
+        0.0
+    };
+    jaro_winkler(&a, &b).max(contained)
+}
+
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+fn jaro_winkler(a: &[char], b: &[char]) -> f64 {
+    let window = (a.len().max(b.len()) / 2).saturating_sub(1);
+    let mut b_used = vec![false; b.len()];
+    let mut a_matched = Vec::new();
+    for (i, ca) in a.iter().enumerate() {
+        let lo = i.saturating_sub(window);
+        let hi = (i + window + 1).min(b.len());
+        if let Some(j) = (lo..hi).find(|j| !b_used[*j] && b[*j] == *ca) {
+            b_used[j] = true;
+            a_matched.push(*ca);
+        }
+    }
+    let m = a_matched.len();
+    if m == 0 {
+        return 0.0;
+    }
+    let b_matched = b.iter().zip(&b_used).filter(|(_, used)| **used).map(|(c, _)| *c);
+    let transpositions = a_matched.iter().zip(b_matched).filter(|(x, y)| **x != *y).count() / 2;
+    let m = m as f64;
+    let jaro = (m / a.len() as f64 + m / b.len() as f64 + (m - transpositions as f64) / m) / 3.0;
+    let prefix = a.iter().zip(b).take(4).take_while(|(x, y)| x == y).count();
+    jaro + prefix as f64 * 0.1 * (1.0 - jaro)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalized_text_is_an_exact_match() {
+        assert_eq!(similarity("  DEBENHAMS ", "debenhams"), 1.0);
+        assert_eq!(similarity("Debenhams   UK", "debenhams uk"), 1.0);
+        assert_eq!(similarity("", "debenhams"), 0.0);
+    }
+
+    #[test]
+    fn typos_and_suffixes_score_high() {
+        assert!(similarity("Debenhms", "Debenhams") > 0.9);
+        assert!(similarity("Debenhams UK", "Debenhams") >= 0.9);
+        // A prefix that isn't a whole word gets no containment bonus
+        assert!(similarity("Debenhamsuk", "Debenhams") < similarity("Debenhams uk", "Debenhams"));
+        assert!(similarity("ASOS", "Debenhams") < 0.5);
+    }
+
+    #[test]
+    fn similarity_is_symmetric() {
+        for (a, b) in [("Debenhms", "Debenhams"), ("Debenhams UK", "debenhams"), ("martha", "marhta")] {
+            assert_eq!(similarity(a, b), similarity(b, a));
+        }
+    }
+
+    #[test]
+    fn levenshtein_distances() {
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("same", "same"), 0);
+    }
+
+    #[test]
+    fn filter_counts_variants() {
+        let rows: Vec<Vec<Value>> = ["Debenhams", "DEBENHAMS ", "Debenhms", "ASOS"].iter().map(|s| vec![Value::from(*s)]).collect();
+        let result = fuzzy_eq(0, "debenhams", 0.9).apply(&rows);
+        assert_eq!(result.matches.iter().map(|m| m.row_index).collect::<Vec<_>>(), [0, 1, 2]);
+        assert_eq!(result.variants.len(), 3);
+        assert!(!fuzzy_eq(0, "debenhams", 0.9).matches(&[Value::from(12)]));
+    }
+}
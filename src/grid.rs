@@ -0,0 +1,428 @@
+use crate::{api, summary, CellValue, SecretString, SheetsError};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+
+// Typed cells for updateCells/appendCells, which set value, format, note and
+// validation in one request instead of a values write plus a format pass.
+
+// Zero-based, end-exclusive; None means unbounded
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GridRange {
+    pub sheet_id: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_row_index: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_row_index: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_column_index: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_column_index: Option<usize>,
+}
+
+impl GridRange {
+    pub fn new(sheet_id: u64, rows: std::ops::Range<usize>, columns: std::ops::Range<usize>) -> Self {
+        GridRange {
+            sheet_id,
+            start_row_index: Some(rows.start),
+            end_row_index: Some(rows.end),
+            start_column_index: Some(columns.start),
+            end_column_index: Some(columns.end),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum ExtendedValue {
+    #[serde(rename = "stringValue")]
+    String(String),
+    #[serde(rename = "numberValue")]
+    Number(f64),
+    #[serde(rename = "boolValue")]
+    Bool(bool),
+    #[serde(rename = "formulaValue")]
+    Formula(String),
+}
+
+// Components 0.0-1.0; the API leaves out zero components, so black is {}
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Color {
+    pub red: f64,
+    pub green: f64,
+    pub blue: f64,
+}
+
+impl Color {
+    pub const WHITE: Color = Color { red: 1.0, green: 1.0, blue: 1.0 };
+
+    pub fn rgb(red: u8, green: u8, blue: u8) -> Self {
+        Color { red: red as f64 / 255.0, green: green as f64 / 255.0, blue: blue as f64 / 255.0 }
+    }
+
+    // Equal at 8 bits per channel; colors read back aren't exactly what was set
+    pub fn same_as(&self, other: &Color) -> bool {
+        let byte = |c: f64| (c * 255.0).round() as i64;
+        [(self.red, other.red), (self.green, other.green), (self.blue, other.blue)].iter().all(|&(a, b)| byte(a) == byte(b))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextFormat {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bold: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub italic: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font_size: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NumberFormat {
+    #[serde(rename = "type")]
+    pub kind: String, // NUMBER, CURRENCY, PERCENT, DATE, ...
+    pub pattern: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CellFormat {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub number_format: Option<NumberFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background_color: Option<Color>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_format: Option<TextFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub horizontal_alignment: Option<String>, // LEFT, CENTER, RIGHT
+}
+
+// A dropdown (ONE_OF_LIST) or any other condition type with its values
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataValidation {
+    pub condition: String,
+    pub values: Vec<String>,
+    pub strict: bool, // Reject invalid input rather than just flagging it
+    pub show_dropdown: bool,
+}
+
+impl DataValidation {
+    pub fn one_of(values: &[&str]) -> Self {
+        DataValidation {
+            condition: "ONE_OF_LIST".to_string(),
+            values: values.iter().map(|v| v.to_string()).collect(),
+            strict: true,
+            show_dropdown: true,
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        let values: Vec<Value> = self.values.iter().map(|v| json!({ "userEnteredValue": v })).collect();
+        json!({
+            "condition": { "type": self.condition, "values": values },
+            "strict": self.strict,
+            "showCustomUi": self.show_dropdown,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CellData {
+    pub value: Option<ExtendedValue>,
+    pub format: Option<CellFormat>,
+    pub note: Option<String>,
+    pub validation: Option<DataValidation>,
+}
+
+impl CellData {
+    pub fn text(value: &str) -> Self {
+        CellData { value: Some(ExtendedValue::String(value.to_string())), ..Default::default() }
+    }
+
+    pub fn number(value: f64) -> Self {
+        CellData { value: Some(ExtendedValue::Number(value)), ..Default::default() }
+    }
+
+    pub fn formula(formula: &str) -> Self {
+        CellData { value: Some(ExtendedValue::Formula(formula.to_string())), ..Default::default() }
+    }
+
+    // From a read cell: strings, numbers and booleans keep their type; null is empty
+    pub fn from_json(value: &Value) -> Self {
+        let value = match value {
+            Value::Null => None,
+            Value::Bool(b) => Some(ExtendedValue::Bool(*b)),
+            Value::Number(n) => n.as_f64().map(ExtendedValue::Number),
+            Value::String(s) => Some(ExtendedValue::String(s.clone())),
+            other => Some(ExtendedValue::String(other.to_string())),
+        };
+        CellData { value, ..Default::default() }
+    }
+
+    pub fn from_cell(cell: &CellValue) -> Self {
+        let value = match cell {
+            CellValue::String(s) => Some(ExtendedValue::String(s.clone())),
+            CellValue::Number(n) => Some(ExtendedValue::Number(*n)),
+            CellValue::Bool(b) => Some(ExtendedValue::Bool(*b)),
+            CellValue::Formula(f) => Some(ExtendedValue::Formula(f.clone())),
+            CellValue::Empty => None,
+        };
+        CellData { value, ..Default::default() }
+    }
+
+    pub fn with_format(mut self, format: CellFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    pub fn with_note(mut self, note: &str) -> Self {
+        self.note = Some(note.to_string());
+        self
+    }
+
+    pub fn with_validation(mut self, validation: DataValidation) -> Self {
+        self.validation = Some(validation);
+        self
+    }
+
+    fn to_json(&self) -> Value {
+        let mut cell = serde_json::Map::new();
+        if let Some(value) = &self.value {
+            cell.insert("userEnteredValue".into(), json!(value));
+        }
+        if let Some(format) = &self.format {
+            cell.insert("userEnteredFormat".into(), json!(format));
+        }
+        if let Some(note) = &self.note {
+            cell.insert("note".into(), json!(note));
+        }
+        if let Some(validation) = &self.validation {
+            cell.insert("dataValidation".into(), validation.to_json());
+        }
+        Value::Object(cell)
+    }
+
+    fn field_paths(&self, fields: &mut BTreeSet<&'static str>) {
+        if self.value.is_some() {
+            fields.insert("userEnteredValue");
+        }
+        if let Some(format) = &self.format {
+            if format.number_format.is_some() {
+                fields.insert("userEnteredFormat.numberFormat");
+            }
+            if format.background_color.is_some() {
+                fields.insert("userEnteredFormat.backgroundColor");
+            }
+            if format.text_format.is_some() {
+                fields.insert("userEnteredFormat.textFormat");
+            }
+            if format.horizontal_alignment.is_some() {
+                fields.insert("userEnteredFormat.horizontalAlignment");
+            }
+        }
+        if self.note.is_some() {
+            fields.insert("note");
+        }
+        if self.validation.is_some() {
+            fields.insert("dataValidation");
+        }
+    }
+}
+
+// Written fields are the union of what any cell sets, so a cell without a
+// note in a batch where others have one gets its note cleared
+fn fields_mask(rows: &[Vec<CellData>]) -> String {
+    let mut fields = BTreeSet::new();
+    rows.iter().flatten().for_each(|cell| cell.field_paths(&mut fields));
+    if fields.is_empty() {
+        fields.insert("userEnteredValue");
+    }
+    fields.into_iter().collect::<Vec<_>>().join(",")
+}
+
+fn rows_json(rows: &[Vec<CellData>]) -> Vec<Value> {
+    rows.iter()
+        .map(|row| json!({ "values": row.iter().map(CellData::to_json).collect::<Vec<_>>() }))
+        .collect()
+}
+
+// updateCells request writing `rows` from the top-left of `range`
+pub fn update_cells_request(range: GridRange, rows: &[Vec<CellData>]) -> Value {
+    json!({ "updateCells": { "range": range, "rows": rows_json(rows), "fields": fields_mask(rows) } })
+}
+
+// appendCells request adding `rows` after the last row with data in tab `sheet_id`
+pub fn append_cells_request(sheet_id: u64, rows: &[Vec<CellData>]) -> Value {
+    json!({ "appendCells": { "sheetId": sheet_id, "rows": rows_json(rows), "fields": fields_mask(rows) } })
+}
+
+// autoFill request extending the pattern or formulas in `source` down by
+// `fill_length` rows, e.g. source = row 2 of the formula columns and
+// fill_length = the number of rows just appended below it. Relative references
+// shift per row as if the cells had been dragged down in the UI.
+pub fn auto_fill_request(source: GridRange, fill_length: usize) -> Value {
+    json!({ "autoFill": { "sourceAndDestination": {
+        "source": source,
+        "dimension": "ROWS",
+        "fillLength": fill_length,
+    }}})
+}
+
+pub async fn auto_fill(
+    access_token: &SecretString,
+    source: GridRange,
+    fill_length: usize,
+) -> Result<(), SheetsError> {
+    if fill_length == 0 {
+        return Ok(());
+    }
+    api::v4::batch_update(access_token, vec![auto_fill_request(source, fill_length)]).await?;
+    Ok(())
+}
+
+// textToColumns request splitting the single column `source` on `delimiter`.
+// The pieces overwrite the columns to the right of it, so leave room first.
+pub fn text_to_columns_request(source: GridRange, delimiter: &str) -> Value {
+    let delimiter_type = match delimiter {
+        "," => "COMMA",
+        ";" => "SEMICOLON",
+        "." => "PERIOD",
+        " " => "SPACE",
+        _ => "CUSTOM",
+    };
+    let mut request = json!({ "textToColumns": { "source": source, "delimiterType": delimiter_type } });
+    if delimiter_type == "CUSTOM" {
+        request["textToColumns"]["delimiter"] = json!(delimiter);
+    }
+    request
+}
+
+pub async fn text_to_columns(
+    access_token: &SecretString,
+    source: GridRange,
+    delimiter: &str,
+) -> Result<(), SheetsError> {
+    api::v4::batch_update(access_token, vec![text_to_columns_request(source, delimiter)]).await?;
+    Ok(())
+}
+
+// trimWhitespace request: strips leading/trailing spaces and collapses runs of
+// spaces in every cell of `range`
+pub fn trim_whitespace_request(range: GridRange) -> Value {
+    json!({ "trimWhitespace": { "range": range } })
+}
+
+// deleteDuplicates request: removes rows of `range` that repeat an earlier
+// row in the 0-based `comparison_columns` (all columns when empty). The first
+// occurrence is kept; rows below move up.
+pub fn delete_duplicates_request(range: GridRange, comparison_columns: &[usize]) -> Value {
+    let columns: Vec<Value> = comparison_columns
+        .iter()
+        .map(|&column| json!({ "sheetId": range.sheet_id, "dimension": "COLUMNS", "startIndex": column, "endIndex": column + 1 }))
+        .collect();
+    let mut request = json!({ "deleteDuplicates": { "range": range } });
+    if !columns.is_empty() {
+        request["deleteDuplicates"]["comparisonColumns"] = json!(columns);
+    }
+    request
+}
+
+// Rows a deleteDuplicates request body would remove, counted from `tab`, the
+// values of its whole tab, so the confirmation threshold can apply before
+// sending it. Blank grid rows past the values aren't seen, so the count can
+// fall short by those.
+pub(crate) fn duplicate_rows(request: &Value, tab: &[Vec<Value>]) -> usize {
+    let range = &request["range"];
+    let index = |name: &str| range[name].as_u64().map(|i| i as usize);
+    let rows = &tab[index("startRowIndex").unwrap_or(0).min(tab.len())..index("endRowIndex").unwrap_or(tab.len()).min(tab.len())];
+    let (first, end) = (index("startColumnIndex").unwrap_or(0), index("endColumnIndex"));
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let compared: Vec<usize> = match request["comparisonColumns"].as_array() {
+        Some(columns) => columns
+            .iter()
+            .flat_map(|c| c["startIndex"].as_u64().unwrap_or(0) as usize..c["endIndex"].as_u64().unwrap_or(0) as usize)
+            .collect(),
+        None => (first..end.unwrap_or(width).max(first)).collect(),
+    };
+    let text = |row: &Vec<Value>, column: usize| match row.get(column) {
+        Some(Value::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+        None => String::new(),
+    };
+    let mut seen = BTreeSet::new();
+    rows.iter().filter(|row| !seen.insert(compared.iter().map(|&c| text(row, c)).collect::<Vec<_>>())).count()
+}
+
+// randomizeRange request: shuffles the rows of `range` server-side. Only the
+// rows within the range move, so leave the header row out of it.
+pub fn randomize_range_request(range: GridRange) -> Value {
+    json!({ "randomizeRange": { "range": range } })
+}
+
+pub async fn randomize_range(access_token: &SecretString, range: GridRange) -> Result<(), SheetsError> {
+    api::v4::batch_update(access_token, vec![randomize_range_request(range)]).await?;
+    Ok(())
+}
+
+// Cells changed by trimWhitespace
+pub async fn trim_whitespace(access_token: &SecretString, range: GridRange) -> Result<u64, SheetsError> {
+    let replies = api::v4::batch_update(access_token, vec![trim_whitespace_request(range)]).await?;
+    Ok(replies.first().and_then(|r| r["trimWhitespace"]["cellsChangedCount"].as_u64()).unwrap_or(0))
+}
+
+// Rows removed by deleteDuplicates
+pub async fn delete_duplicates(
+    access_token: &SecretString,
+    range: GridRange,
+    comparison_columns: &[usize],
+) -> Result<u64, SheetsError> {
+    let replies = api::v4::batch_update(access_token, vec![delete_duplicates_request(range, comparison_columns)]).await?;
+    let removed = replies.first().and_then(|r| r["deleteDuplicates"]["duplicatesRemovedCount"].as_u64()).unwrap_or(0);
+    summary::rows_deleted(removed as usize);
+    Ok(removed)
+}
+
+pub async fn update_cells(
+    access_token: &SecretString,
+    range: GridRange,
+    rows: &[Vec<CellData>],
+) -> Result<(), SheetsError> {
+    api::v4::batch_update(access_token, vec![update_cells_request(range, rows)]).await?;
+    Ok(())
+}
+
+pub async fn append_cells(
+    access_token: &SecretString,
+    sheet_id: u64,
+    rows: &[Vec<CellData>],
+) -> Result<(), SheetsError> {
+    api::v4::batch_update(access_token, vec![append_cells_request(sheet_id, rows)]).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tab() -> Vec<Vec<Value>> {
+        let rows = [["SKU", "Store", "Qty"], ["A", "LDN", "1"], ["A", "LDN", "2"], ["B", "LDN", "1"], ["A", "LDN", "1"], ["B", "MAN", "1"]];
+        rows.iter().map(|row| row.iter().map(|cell| json!(cell)).collect()).collect()
+    }
+
+    #[test]
+    fn counts_duplicates_like_delete_duplicates() {
+        let body = |request: Value| request["deleteDuplicates"].clone();
+        let all = GridRange { sheet_id: 0, start_row_index: Some(1), ..Default::default() };
+        // Whole rows: only row 5 repeats row 2
+        assert_eq!(duplicate_rows(&body(delete_duplicates_request(all, &[])), &tab()), 1);
+        // SKU and store: rows 3 and 5 repeat row 2
+        assert_eq!(duplicate_rows(&body(delete_duplicates_request(all, &[0, 1])), &tab()), 2);
+        // Store alone, header included in the range: LDN three more times
+        let with_header = GridRange { sheet_id: 0, ..Default::default() };
+        assert_eq!(duplicate_rows(&body(delete_duplicates_request(with_header, &[1])), &tab()), 3);
+        // Rows 2-3 only
+        assert_eq!(duplicate_rows(&body(delete_duplicates_request(GridRange::new(0, 1..3, 0..2), &[])), &tab()), 1);
+    }
+}
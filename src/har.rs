@@ -0,0 +1,232 @@
+use crate::secret::{redact, SecretString};
+use reqwest::header::{HeaderMap, AUTHORIZATION};
+use reqwest::{Client, Request, RequestBuilder, Response};
+use serde_json::{json, Value};
+use std::env;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+// HAR_PATH=run.har (or record_to) writes every API request and response of
+// the run to a HAR 1.2 file that browser dev tools and HAR viewers can open,
+// for attaching to bug reports. Tokens, keys and the JWT assertion are
+// replaced with "[redacted]"; cell values are kept, so check a HAR before
+// sharing it. The file is rewritten after every exchange, so it survives a
+// crash.
+
+const REDACTED: &str = "[redacted]";
+
+// Header, query, form and JSON names whose values never reach the file
+const SECRET_NAMES: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "x-goog-api-key",
+    "key",
+    "access_token",
+    "refresh_token",
+    "id_token",
+    "assertion",
+    "private_key",
+    "private_key_id",
+    "client_secret",
+];
+
+struct Recorder {
+    path: PathBuf,
+    entries: Mutex<Vec<Value>>,
+}
+
+static PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+// Call before the first request; later calls have no effect
+pub fn record_to(path: impl Into<PathBuf>) {
+    *PATH.lock().unwrap_or_else(|e| e.into_inner()) = Some(path.into());
+}
+
+fn recorder() -> Option<&'static Recorder> {
+    static RECORDER: OnceLock<Option<Recorder>> = OnceLock::new();
+    RECORDER
+        .get_or_init(|| {
+            let path = PATH.lock().unwrap_or_else(|e| e.into_inner()).clone().or_else(|| env::var_os("HAR_PATH").map(PathBuf::from))?;
+            Some(Recorder { path, entries: Mutex::new(Vec::new()) })
+        })
+        .as_ref()
+}
+
+pub fn enabled() -> bool {
+    recorder().is_some()
+}
+
+pub(crate) trait SendRecorded {
+    // RequestBuilder::send, recording the exchange when HAR output is on
+    fn send_recorded(self) -> impl Future<Output = reqwest::Result<Response>> + Send;
+}
+
+impl SendRecorded for RequestBuilder {
+    async fn send_recorded(self) -> reqwest::Result<Response> {
+        let (client, request) = self.build_split();
+        execute(&client, request?).await
+    }
+}
+
+// Client::execute, recording the exchange when HAR output is on
+pub(crate) async fn execute(client: &Client, request: Request) -> reqwest::Result<Response> {
+    let Some(recorder) = recorder() else { return client.execute(request).await };
+    let started = chrono::Utc::now();
+    let timer = Instant::now();
+    let har_request = request_entry(&request);
+    // Redacted by header name above; this also catches it in a URL or an echoed body
+    let bearer = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| SecretString::new(token.to_string()));
+    let response = client.execute(request).await?;
+    let (status, version, headers) = (response.status(), response.version(), response.headers().clone());
+    let body = response.bytes().await?;
+    recorder.push(scrub(json!({
+        "startedDateTime": started.to_rfc3339(),
+        "time": timer.elapsed().as_millis() as u64,
+        "request": har_request,
+        "response": {
+            "status": status.as_u16(),
+            "statusText": status.canonical_reason().unwrap_or_default(),
+            "httpVersion": format!("{:?}", version),
+            "headers": header_entries(&headers),
+            "cookies": [],
+            "content": content(&headers, &body),
+            "redirectURL": "",
+            "headersSize": -1,
+            "bodySize": body.len(),
+        },
+        "cache": {},
+        "timings": { "send": 0, "wait": timer.elapsed().as_millis() as u64, "receive": 0 },
+    }), bearer.as_ref()));
+    // The body was consumed for the file; hand the caller an equivalent response
+    let mut rebuilt = http::Response::new(body);
+    *rebuilt.status_mut() = status;
+    *rebuilt.version_mut() = version;
+    *rebuilt.headers_mut() = headers;
+    Ok(Response::from(rebuilt))
+}
+
+impl Recorder {
+    fn push(&self, entry: Value) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.push(entry);
+        let har = json!({
+            "log": {
+                "version": "1.2",
+                "creator": { "name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION") },
+                "entries": *entries,
+            }
+        });
+        // Losing the debug file must not fail the run
+        if let Err(e) = std::fs::write(&self.path, har.to_string()) {
+            tracing::warn!("could not write HAR file '{}': {}", self.path.display(), e);
+        }
+    }
+}
+
+// Every occurrence of `secret` in the entry, wherever it turned up
+fn scrub(entry: Value, secret: Option<&SecretString>) -> Value {
+    let Some(secret) = secret else { return entry };
+    let text = entry.to_string();
+    if !text.contains(secret.expose_secret()) {
+        return entry;
+    }
+    serde_json::from_str(&redact(&text, &[secret])).unwrap_or(entry)
+}
+
+fn is_secret(name: &str) -> bool {
+    SECRET_NAMES.iter().any(|secret| secret.eq_ignore_ascii_case(name))
+}
+
+fn request_entry(request: &Request) -> Value {
+    let mut url = request.url().clone();
+    let query: Vec<(String, String)> =
+        url.query_pairs().map(|(name, value)| (name.to_string(), if is_secret(&name) { REDACTED.to_string() } else { value.to_string() })).collect();
+    if !query.is_empty() {
+        url.query_pairs_mut().clear().extend_pairs(&query);
+    }
+    let mut entry = json!({
+        "method": request.method().as_str(),
+        "url": url.as_str(),
+        "httpVersion": format!("{:?}", request.version()),
+        "headers": header_entries(request.headers()),
+        "queryString": query.iter().map(|(name, value)| json!({ "name": name, "value": value })).collect::<Vec<_>>(),
+        "cookies": [],
+        "headersSize": -1,
+        "bodySize": request.body().and_then(|b| b.as_bytes()).map_or(0, <[u8]>::len),
+    });
+    if let Some(body) = request.body().and_then(|b| b.as_bytes()) {
+        entry["postData"] = content(request.headers(), body);
+    }
+    entry
+}
+
+fn header_entries(headers: &HeaderMap) -> Vec<Value> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if is_secret(name.as_str()) { REDACTED.into() } else { String::from_utf8_lossy(value.as_bytes()) };
+            json!({ "name": name.as_str(), "value": value })
+        })
+        .collect()
+}
+
+// {mimeType, text} with secrets redacted from JSON and form bodies
+fn content(headers: &HeaderMap, body: &[u8]) -> Value {
+    let mime = headers.get(reqwest::header::CONTENT_TYPE).and_then(|v| v.to_str().ok()).unwrap_or_default();
+    if !is_text(mime) {
+        // Exports and other downloads: size only
+        return json!({ "size": body.len(), "mimeType": mime, "comment": "binary body not recorded" });
+    }
+    let text = String::from_utf8_lossy(body);
+    let text = if mime.starts_with("application/x-www-form-urlencoded") {
+        redact_form(&text)
+    } else {
+        match serde_json::from_str::<Value>(&text) {
+            Ok(mut value) => {
+                redact_json(&mut value);
+                value.to_string()
+            }
+            Err(_) => text.into_owned(),
+        }
+    };
+    json!({ "size": body.len(), "mimeType": mime, "text": text })
+}
+
+fn is_text(mime: &str) -> bool {
+    mime.is_empty() || mime.starts_with("text/") || mime.contains("json") || mime.contains("x-www-form-urlencoded")
+}
+
+fn redact_form(text: &str) -> String {
+    text.split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((name, _)) if is_secret(name) => format!("{}={}", name, REDACTED),
+            _ => pair.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn redact_json(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (name, value) in map.iter_mut() {
+                if is_secret(name) {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_json(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_json),
+        _ => {}
+    }
+}
@@ -0,0 +1,177 @@
+use crate::a1::{range_sheet, range_start, A1Range};
+use crate::cell_value::{CellValue, ValueInputOption};
+use crate::{append_rows_to_google_sheet_with, batch_update_values_with, clear_range, limits, SecretString, SheetsError};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+// Loading local CSV files into a sheet. The file is read a batch of rows at
+// a time, so its size is bounded by the sheet rather than by memory, and each
+// batch goes out in as many requests as the payload limit needs.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportMode {
+    // After the last row of the table in the target range
+    Append,
+    // From the target range's top-left cell down, leaving cells past the
+    // file's rows and columns as they were
+    Overwrite,
+    // Clear the target range, then write as Overwrite does
+    Replace,
+}
+
+impl ImportMode {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "append" => Some(ImportMode::Append),
+            "overwrite" => Some(ImportMode::Overwrite),
+            "replace" => Some(ImportMode::Replace),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+    pub delimiter: char,
+    // Leave out the file's first row, e.g. when appending under an existing header
+    pub skip_header: bool,
+    // UserEntered (the default) lets Sheets turn "12" and "2024-01-31" into a
+    // number and a date, as File > Import does; Raw keeps every cell as text
+    pub input: ValueInputOption,
+    // Rows held in memory at once
+    pub batch_rows: usize,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions { delimiter: ',', skip_header: false, input: ValueInputOption::UserEntered, batch_rows: 5_000 }
+    }
+}
+
+// RFC 4180 records from a reader: quoted fields may hold the delimiter,
+// doubled quotes and line breaks; CRLF and LF both end a record
+pub struct CsvReader<R> {
+    reader: R,
+    delimiter: char,
+    line: usize,
+}
+
+impl<R: BufRead> CsvReader<R> {
+    pub fn new(reader: R, delimiter: char) -> Self {
+        CsvReader { reader, delimiter, line: 0 }
+    }
+
+    pub fn next_record(&mut self) -> io::Result<Option<Vec<String>>> {
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut in_quotes = false;
+        let mut started = self.line;
+        let mut text = String::new();
+        loop {
+            text.clear();
+            if self.reader.read_line(&mut text)? == 0 {
+                if in_quotes {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, format!("line {}: quoted field never closed", started + 1)));
+                }
+                if fields.is_empty() && field.is_empty() && self.line == started {
+                    return Ok(None);
+                }
+                break;
+            }
+            if self.line == 0 {
+                // Excel's UTF-8 byte order mark
+                if let Some(rest) = text.strip_prefix('\u{feff}') {
+                    text = rest.to_string();
+                }
+            }
+            if !in_quotes {
+                started = self.line;
+            }
+            self.line += 1;
+            let mut chars = text.strip_suffix('\n').map_or(text.as_str(), |t| t.strip_suffix('\r').unwrap_or(t)).chars().peekable();
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' if in_quotes && chars.peek() == Some(&'"') => {
+                        field.push('"');
+                        chars.next();
+                    }
+                    '"' if in_quotes => in_quotes = false,
+                    '"' if field.is_empty() => in_quotes = true,
+                    c if c == self.delimiter && !in_quotes => fields.push(std::mem::take(&mut field)),
+                    c => field.push(c),
+                }
+            }
+            if !in_quotes {
+                break;
+            }
+            // The line break belongs to the quoted field
+            field.push('\n');
+        }
+        fields.push(field);
+        Ok(Some(fields))
+    }
+}
+
+// Import `path` into `target_range` ("Imports!A1", or a bare tab name).
+// Returns the rows written. A failure part way through leaves the batches
+// before it in the sheet.
+pub async fn import_csv(
+    access_token: &SecretString,
+    path: impl AsRef<Path>,
+    target_range: &str,
+    mode: ImportMode,
+) -> Result<usize, SheetsError> {
+    import_csv_with(access_token, path, target_range, mode, &CsvOptions::default()).await
+}
+
+pub async fn import_csv_with(
+    access_token: &SecretString,
+    path: impl AsRef<Path>,
+    target_range: &str,
+    mode: ImportMode,
+    options: &CsvOptions,
+) -> Result<usize, SheetsError> {
+    let path = path.as_ref();
+    let file = File::open(path).map_err(|e| io::Error::new(e.kind(), format!("opening '{}': {}", path.display(), e)))?;
+    let mut reader = CsvReader::new(BufReader::new(file), options.delimiter);
+    if options.skip_header {
+        reader.next_record()?;
+    }
+    let sheet = range_sheet(target_range).unwrap_or_else(|| target_range.trim_matches('\'').to_string());
+    let (column, first_row) = if target_range.contains('!') { range_start(target_range) } else { (0, 1) };
+    if mode == ImportMode::Replace {
+        clear_range(access_token, target_range).await?;
+    }
+
+    let mut written = 0;
+    loop {
+        let mut batch = Vec::new();
+        while batch.len() < options.batch_rows.max(1) {
+            match reader.next_record().map_err(|e| io::Error::new(e.kind(), format!("reading '{}': {}", path.display(), e)))? {
+                Some(record) => batch.push(record.into_iter().map(CellValue::from).collect::<Vec<_>>()),
+                None => break,
+            }
+        }
+        if batch.is_empty() {
+            break;
+        }
+        let rows = batch.len();
+        match mode {
+            ImportMode::Append => {
+                append_rows_to_google_sheet_with(access_token, target_range, batch, options.input).await?;
+            }
+            ImportMode::Overwrite | ImportMode::Replace => {
+                let mut offset = written;
+                for chunk in limits::split_by_payload(batch, limits::MAX_REQUEST_BYTES)? {
+                    let range = A1Range::sheet(&sheet).cell(column, first_row + offset).to_string();
+                    offset += chunk.len();
+                    batch_update_values_with(access_token, vec![(range, chunk)], options.input).await?;
+                }
+            }
+        }
+        written += rows;
+        tracing::debug!("imported {} rows of '{}'", written, path.display());
+    }
+    Ok(written)
+}
@@ -0,0 +1,112 @@
+use crate::config::{self, format_env_line};
+use crate::output::write_private;
+use crate::{fetch_values, get_google_access_token, Credentials, SecretString, SpreadsheetId};
+use std::env;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use zeroize::Zeroizing;
+
+// Interactive first-run setup. Returns the path of the written config file.
+pub async fn run_init() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+
+    println!("Google Sheets setup");
+    println!("  1) service-account JSON key file");
+    println!("  2) paste service-account email and private key");
+    let method = prompt(&mut input, "Auth method [1]: ")?;
+
+    let (email, private_key) = match method.as_str() {
+        "" | "1" => {
+            let path = prompt(&mut input, "Path to key file: ")?;
+            let key = Credentials::from_json_file(path.trim_matches('"'))
+                .map_err(|e| format!("'{}' is not a usable service-account key file: {}", path, e))?;
+            (key.client_email, key.private_key)
+        }
+        "2" => {
+            let email = prompt(&mut input, "Service account email: ")?;
+            println!("Paste the private key (ends at the -----END PRIVATE KEY----- line):");
+            (email, SecretString::new(read_pasted_key(&mut input)?))
+        }
+        other => return Err(format!("unknown auth method '{}'", other).into()),
+    };
+
+    let url = prompt(&mut input, "Spreadsheet URL or ID: ")?;
+    let spreadsheet = SpreadsheetId::from_url(&url)?;
+    let sheet_id = spreadsheet.as_str().to_string();
+    println!("Spreadsheet ID: {}", sheet_id);
+
+    let profile = prompt(&mut input, "Profile name (blank = ./.env): ")?;
+    let target = if profile.is_empty() {
+        PathBuf::from(".env")
+    } else {
+        config::profile_path(&profile)
+    };
+
+    // Use the new values for the test read, ahead of any existing .env
+    env::set_var("SERVICE_ACCOUNT_EMAIL", &email);
+    env::set_var("PRIVATE_KEY", private_key.expose_secret());
+    env::set_var("SHEET_ID", &sheet_id);
+    print!("Testing access... ");
+    io::stdout().flush()?;
+    let test = async {
+        let token = get_google_access_token().await?;
+        fetch_values(&token, "A1:Z5").await
+    };
+    match test.await {
+        Ok(rows) => println!("ok ({} rows read)", rows.len()),
+        Err(e) => {
+            println!("failed: {}", e);
+            println!("(run `doctor` afterwards for details)");
+            let answer = prompt(&mut input, "Save the profile anyway? [y/N]: ")?;
+            if !answer.eq_ignore_ascii_case("y") {
+                return Err("setup aborted, nothing written".into());
+            }
+        }
+    }
+
+    if target.exists() {
+        let answer = prompt(&mut input, &format!("{} exists, overwrite? [y/N]: ", target.display()))?;
+        if !answer.eq_ignore_ascii_case("y") {
+            return Err("setup aborted, nothing written".into());
+        }
+    }
+    let contents = Zeroizing::new(
+        [
+            format_env_line("SERVICE_ACCOUNT_EMAIL", &email),
+            format_env_line("PRIVATE_KEY", private_key.expose_secret()),
+            format_env_line("SHEET_ID", &sheet_id),
+        ]
+        .join("\n")
+            + "\n",
+    );
+    write_private(&target, contents.as_bytes())?;
+    Ok(target)
+}
+
+fn prompt(input: &mut impl BufRead, message: &str) -> io::Result<String> {
+    print!("{}", message);
+    io::stdout().flush()?;
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "input closed"));
+    }
+    Ok(line.trim().to_string())
+}
+
+fn read_pasted_key(input: &mut impl BufRead) -> io::Result<String> {
+    let mut key = String::new();
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            break;
+        }
+        let line = line.trim_end();
+        key.push_str(line);
+        key.push('\n');
+        if line.contains("-----END") {
+            break;
+        }
+    }
+    Ok(key)
+}
@@ -0,0 +1,207 @@
+use crate::api::v4;
+use crate::output::write_private;
+use crate::{SecretString, SheetsError};
+use chrono::{DateTime, Utc};
+use reqwest::{Client, Method};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+// JOURNAL_PATH=writes.jsonl (or journal_to) keeps writes that couldn't reach
+// Google, so rows collected during an outage aren't lost. A write whose
+// connection failed is appended to the journal and its caller gets
+// SheetsError::Journaled; once any write goes out again, the journal is
+// replayed first, in order, so later writes don't overtake earlier ones.
+// `sheets replay-journal` does the same by hand. Only connection failures
+// are journaled: after a timeout the write may already have been applied.
+// The file holds the request bodies (cell data), so it's created 0600.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub id: String,
+    pub queued_at: DateTime<Utc>,
+    pub method: String,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Value>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplayOutcome {
+    pub replayed: usize,
+    // Refused by the API (the tab is gone, the range is invalid); moved to
+    // the .rejected file next to the journal with the error
+    pub rejected: usize,
+    // Still queued because Google is still unreachable
+    pub remaining: usize,
+}
+
+static PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+static PENDING: AtomicBool = AtomicBool::new(false);
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+pub fn journal_to(path: impl Into<PathBuf>) {
+    *PATH.lock().unwrap_or_else(|e| e.into_inner()) = Some(path.into());
+    init_pending(true);
+}
+
+// journal_to, else JOURNAL_PATH, else journaling is off
+pub fn path() -> Option<PathBuf> {
+    if let Some(path) = PATH.lock().unwrap_or_else(|e| e.into_inner()).clone() {
+        return Some(path);
+    }
+    env::var("JOURNAL_PATH").ok().filter(|p| !p.trim().is_empty()).map(PathBuf::from)
+}
+
+pub fn enabled() -> bool {
+    path().is_some()
+}
+
+// Entries waiting for replay, oldest first
+pub fn entries() -> io::Result<Vec<JournalEntry>> {
+    let Some(path) = path() else { return Ok(Vec::new()) };
+    read_entries(&path)
+}
+
+fn read_entries(path: &Path) -> io::Result<Vec<JournalEntry>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)))
+        .collect()
+}
+
+// Whether a journal left by an earlier run (or this one) needs replaying; the
+// file is only looked at once
+fn init_pending(force: bool) {
+    static CHECKED: OnceLock<()> = OnceLock::new();
+    if force || CHECKED.set(()).is_ok() {
+        let waiting = path().and_then(|p| fs::metadata(p).ok()).is_some_and(|m| m.len() > 0);
+        PENDING.store(waiting, Ordering::Relaxed);
+    }
+}
+
+pub(crate) fn pending() -> bool {
+    init_pending(false);
+    PENDING.load(Ordering::Relaxed)
+}
+
+fn append_line(path: &Path, line: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let mut options = OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    file.write_all(format!("{}\n", line).as_bytes())?;
+    file.sync_data()
+}
+
+// Save a write for later; returns its journal id
+pub(crate) fn queue(method: &Method, url: &str, body: Option<&Value>) -> io::Result<String> {
+    let path = path().ok_or_else(|| io::Error::other("journaling is off"))?;
+    let _guard = write_lock();
+    let entry = JournalEntry {
+        id: format!("{}-{}", Utc::now().format("%Y%m%dT%H%M%S%.3f"), SEQUENCE.fetch_add(1, Ordering::Relaxed)),
+        queued_at: Utc::now(),
+        method: method.to_string(),
+        url: url.to_string(),
+        body: body.cloned(),
+    };
+    append_line(&path, &serde_json::to_string(&entry)?)?;
+    PENDING.store(true, Ordering::Relaxed);
+    tracing::warn!("Google unreachable; journaled {} {} as {}", entry.method, url.split('?').next().unwrap_or(url), entry.id);
+    Ok(entry.id)
+}
+
+fn write_lock() -> std::sync::MutexGuard<'static, ()> {
+    static LOCK: Mutex<()> = Mutex::new(());
+    LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// Send every journaled write, oldest first, with this token
+pub async fn replay_journal(access_token: &SecretString) -> Result<ReplayOutcome, SheetsError> {
+    replay(v4::shared_client(), access_token).await
+}
+
+// Stops at the first entry that can't go out yet, keeping it and the rest.
+// Errors that aren't the entry's fault (auth, read-only, throttling) are
+// returned with the journal left as it was from that entry on.
+pub(crate) async fn replay(client: &Client, access_token: &SecretString) -> Result<ReplayOutcome, SheetsError> {
+    // One replay at a time; a second caller waits and then finds it done
+    static REPLAYING: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+    let _replaying = REPLAYING.lock().await;
+    let Some(path) = path() else { return Ok(ReplayOutcome::default()) };
+    let entries = read_entries(&path).map_err(|e| SheetsError::Parse(format!("reading journal '{}': {}", path.display(), e)))?;
+    let mut outcome = ReplayOutcome::default();
+    let mut done = 0;
+    let mut failure = None;
+    for entry in &entries {
+        let method = Method::from_bytes(entry.method.as_bytes()).map_err(|e| SheetsError::Parse(e.to_string()))?;
+        match v4::send_direct(client, access_token, method, &entry.url, entry.body.as_ref()).await {
+            Ok(_) => outcome.replayed += 1,
+            Err(e) if rejected(&e) => {
+                tracing::warn!("journal entry {} rejected: {}", entry.id, e);
+                let record = serde_json::json!({ "entry": entry, "error": e.to_string(), "rejected_at": Utc::now() });
+                if let Err(io) = append_line(&rejected_path(&path), &record.to_string()) {
+                    failure = Some(SheetsError::Parse(format!("saving rejected journal entry {}: {}", entry.id, io)));
+                    break;
+                }
+                outcome.rejected += 1;
+            }
+            Err(e) => {
+                if !e.is_unreachable() {
+                    failure = Some(e);
+                }
+                break;
+            }
+        }
+        done += 1;
+    }
+    let rest = &entries[done..];
+    let _guard = write_lock();
+    // Entries queued while replaying were appended after the ones read above
+    let mut keep: Vec<JournalEntry> = rest.to_vec();
+    keep.extend(read_entries(&path).unwrap_or_default().into_iter().skip(entries.len()));
+    let contents: String = keep.iter().filter_map(|e| serde_json::to_string(e).ok()).map(|line| line + "\n").collect();
+    write_private(&path, contents.as_bytes()).map_err(|e| SheetsError::Parse(format!("rewriting journal '{}': {}", path.display(), e)))?;
+    outcome.remaining = keep.len();
+    PENDING.store(!keep.is_empty(), Ordering::Relaxed);
+    if outcome.replayed + outcome.rejected > 0 {
+        tracing::info!("journal replay: {} sent, {} rejected, {} left", outcome.replayed, outcome.rejected, keep.len());
+    }
+    match failure {
+        Some(e) => Err(e),
+        None => Ok(outcome),
+    }
+}
+
+// Refusals that sending again won't fix
+fn rejected(error: &SheetsError) -> bool {
+    match error {
+        SheetsError::Api { code, .. } => (400..500).contains(code) && *code != 429,
+        SheetsError::NotFound(_) | SheetsError::Policy(_) | SheetsError::Limit(_) => true,
+        SheetsError::Coalesced(e) => rejected(e),
+        _ => false,
+    }
+}
+
+fn rejected_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+    name.push(".rejected");
+    path.with_file_name(name)
+}
@@ -0,0 +1,620 @@
+use futures::stream::{self, StreamExt, TryStreamExt};
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use reqwest::{Client, Method};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+use zeroize::Zeroizing;
+use crate::a1::{encode_range, A1Range};
+use crate::dimensions::Dimension;
+use crate::export::OutputFormat;
+use crate::har::SendRecorded;
+use crate::metadata::SheetRef;
+use crate::policy::Operation;
+use crate::schema::Schema;
+use crate::scope::scope_urls;
+use crate::secret::redact;
+
+pub mod a1;
+pub mod aggregate;
+pub mod api;
+pub mod append_buffer;
+pub mod archive;
+pub mod bool_column;
+pub mod cache_file;
+pub mod cas;
+pub mod cell_value;
+pub mod client;
+pub mod coalesce;
+pub mod coerce;
+pub mod colors;
+pub mod computed;
+pub mod config;
+pub mod confirm;
+pub mod count;
+pub mod credentials;
+pub mod data_source;
+pub mod dimensions;
+pub mod dedupe;
+pub mod doctor;
+pub mod drive;
+pub mod error;
+pub mod export;
+pub mod fanout;
+pub mod filter;
+pub mod formula;
+pub mod fuzzy;
+pub mod grid;
+pub mod har;
+pub mod import;
+pub mod init;
+pub mod journal;
+pub mod limits;
+pub mod metadata;
+pub mod monitor;
+pub mod multi_tab;
+pub mod normalize;
+pub mod numbers;
+pub mod ordering;
+pub mod output;
+pub mod paged;
+pub mod pattern;
+pub mod pii;
+pub mod policy;
+pub mod propagate;
+pub mod provenance;
+pub mod provision;
+pub mod rate_limit;
+pub mod read_only;
+pub mod queue;
+pub mod records;
+pub mod redaction;
+pub mod references;
+pub mod render;
+pub mod report;
+pub mod reshape;
+pub mod revisions;
+pub mod rollover;
+pub mod routing;
+pub mod row;
+pub mod sample;
+pub mod schema;
+pub mod scope;
+#[cfg(feature = "scripting")]
+pub mod script;
+pub mod secret;
+pub mod spill;
+pub mod spreadsheet_id;
+pub mod summary;
+pub mod sweep;
+pub mod table;
+pub mod tabs;
+pub mod token;
+pub mod trace;
+#[cfg(feature = "handlebars")]
+pub mod template;
+pub mod verify;
+pub mod watch;
+#[cfg(feature = "wasm")]
+pub mod wasm_transform;
+pub mod whoami;
+pub mod workbook;
+
+pub use cell_value::{CellValue, NullPolicy, ValueInputOption, WriteOptions};
+pub use client::SheetsClient;
+pub use config::{Config, ConfigError};
+pub use credentials::{CredentialKind, Credentials};
+pub use error::SheetsError;
+pub use filter::Filter;
+pub use output::OutputConfig;
+pub use redaction::Redactor;
+pub use scope::Scope;
+pub use render::RenderOptions;
+pub use secret::SecretString;
+pub use spreadsheet_id::SpreadsheetId;
+pub use token::TokenProvider;
+
+#[derive(Serialize, Deserialize)]
+struct Claims {
+    iss: String,   // Service account email
+    scope: String, // Space-separated API scopes
+    aud: String,   // Token URL
+    exp: u64,      // Expiration time
+    iat: u64,      // Issued at time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sub: Option<String>, // User to impersonate (domain-wide delegation)
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: Option<SecretString>,
+    expires_in: Option<i64>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+pub const SHEETS_SCOPE: &str = "https://www.googleapis.com/auth/spreadsheets"; // Full access needed to write
+pub const SHEETS_READONLY_SCOPE: &str = "https://www.googleapis.com/auth/spreadsheets.readonly";
+
+// Function to get Google OAuth2 token, for the scopes the credentials carry
+// (SHEETS_SCOPES; full spreadsheets access by default)
+pub async fn get_google_access_token() -> Result<SecretString, SheetsError> {
+    let credentials = Credentials::from_env()?;
+    access_token_for(&credentials, &scope_urls(&credentials.scopes)).await
+}
+
+// Same, for extra APIs such as Drive (see drive::DRIVE_METADATA_SCOPE)
+pub async fn access_token_for_scopes(scopes: &[&str]) -> Result<SecretString, SheetsError> {
+    access_token_for(&Credentials::from_env()?, scopes).await // .env (or SHEETS_ENV_FILE) plus environment
+}
+
+pub async fn access_token_for(credentials: &Credentials, scopes: &[&str]) -> Result<SecretString, SheetsError> {
+    if let Some(token) = cache_file::load_token(credentials, &scopes.join(" ")) {
+        return Ok(token); // Still valid from an earlier run (SHEETS_CACHE=0 disables)
+    }
+    exchange_token_for(credentials, scopes).await
+}
+
+// Always does the JWT exchange, skipping the cache (the result is still cached)
+pub async fn exchange_token(scopes: &[&str]) -> Result<SecretString, SheetsError> {
+    exchange_token_for(&Credentials::from_env()?, scopes).await
+}
+
+pub async fn exchange_token_for(credentials: &Credentials, scopes: &[&str]) -> Result<SecretString, SheetsError> {
+    Ok(mint_token(credentials, scopes).await?.0)
+}
+
+// The token exchange itself; returns the token and its lifetime in seconds
+pub(crate) async fn mint_token(
+    credentials: &Credentials,
+    scopes: &[&str],
+) -> Result<(SecretString, i64), SheetsError> {
+    let scope = scopes.join(" ");
+    let client = Client::new();
+    summary::api_call();
+    if credentials.subject.is_some() && !matches!(credentials.kind, CredentialKind::ServiceAccount) {
+        return Err(SheetsError::Auth("impersonating a user needs a service account key".to_string()));
+    }
+    let response = match &credentials.kind {
+        CredentialKind::ServiceAccount => {
+            let jwt = sign_assertion(credentials, &scope)?;
+            client
+                .post("https://oauth2.googleapis.com/token")
+                .form(&[
+                    ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                    ("assertion", jwt.as_str()),
+                ])
+                .send_recorded()
+                .await?
+        }
+        // The refresh token keeps the scopes it was granted; `scope` is ignored
+        CredentialKind::AuthorizedUser { client_id, client_secret, refresh_token } => {
+            client
+                .post("https://oauth2.googleapis.com/token")
+                .form(&[
+                    ("grant_type", "refresh_token"),
+                    ("client_id", client_id.as_str()),
+                    ("client_secret", client_secret.expose_secret()),
+                    ("refresh_token", refresh_token.expose_secret()),
+                ])
+                .send_recorded()
+                .await?
+        }
+        CredentialKind::MetadataServer => {
+            let url = format!("http://{}/computeMetadata/v1/instance/service-accounts/default/token", credentials::metadata_host());
+            let response = client
+                .get(&url)
+                .query(&[("scopes", scopes.join(","))])
+                .header("Metadata-Flavor", "Google")
+                .send_recorded()
+                .await
+                .map_err(|e| SheetsError::Auth(format!("no metadata server at {} (not running on Google Cloud?): {}", url, e)))?;
+            if !response.status().is_success() {
+                return Err(SheetsError::Auth(format!("metadata server refused a token: HTTP {}", response.status())));
+            }
+            response
+        }
+    };
+    let response = response.json::<TokenResponse>().await?;
+
+    match response.access_token {
+        Some(token) => {
+            let expires_in = response.expires_in.unwrap_or(3600);
+            cache_file::store_token(credentials, &scope, &token, expires_in);
+            Ok((token, expires_in))
+        }
+        None => {
+            let message = format!(
+                "token request rejected: {} {}",
+                response.error.unwrap_or_else(|| "unknown_error".to_string()),
+                response.error_description.unwrap_or_default()
+            );
+            // The description is free text from Google and gets logged; never let it echo a credential
+            let secrets = match &credentials.kind {
+                CredentialKind::AuthorizedUser { client_secret, refresh_token, .. } => vec![client_secret, refresh_token],
+                _ => vec![&credentials.private_key],
+            };
+            Err(SheetsError::Auth(redact(&message, &secrets)))
+        }
+    }
+}
+
+// The signed JWT a service account trades for a token
+fn sign_assertion(credentials: &Credentials, scope: &str) -> Result<Zeroizing<String>, SheetsError> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|_| SheetsError::Auth("system clock is before 1970".to_string()))?
+        .as_secs();
+    let claims = Claims {
+        iss: credentials.client_email.clone(),
+        scope: scope.to_string(),
+        aud: "https://oauth2.googleapis.com/token".to_string(),
+        exp: now + 3600,
+        iat: now,
+        sub: credentials.subject.clone(),
+    };
+
+    let key = EncodingKey::from_rsa_pem(credentials.private_key.expose_secret().as_bytes())
+        .map_err(|e| SheetsError::Auth(format!("private key is not a usable RSA key: {}", e)))?;
+    Ok(Zeroizing::new(
+        encode(&Header::new(Algorithm::RS256), &claims, &key).map_err(|e| SheetsError::Auth(format!("signing the JWT failed: {}", e)))?,
+    ))
+}
+
+// Function to fetch the raw cell values of a range (rows of cells)
+pub async fn fetch_values(
+    access_token: &SecretString,
+    range: &str,
+) -> Result<Vec<Vec<Value>>, SheetsError> {
+    fetch_values_with(access_token, range, &RenderOptions::default()).await
+}
+
+// fetch_values with a choice of rendering, e.g. RenderOptions::unformatted()
+// for real numbers instead of formatted strings
+pub async fn fetch_values_with(
+    access_token: &SecretString,
+    range: &str,
+    render: &RenderOptions,
+) -> Result<Vec<Vec<Value>>, SheetsError> {
+    api::v4::get_values_with(access_token, range, render).await
+}
+
+// Like fetch_values for several ranges at once (one values:batchGet request)
+pub async fn batch_get_values(access_token: &SecretString, ranges: &[&str]) -> Result<Vec<Vec<Vec<Value>>>, SheetsError> {
+    batch_get_values_with(access_token, ranges, &RenderOptions::default()).await
+}
+
+pub async fn batch_get_values_with(
+    access_token: &SecretString,
+    ranges: &[&str],
+    render: &RenderOptions,
+) -> Result<Vec<Vec<Vec<Value>>>, SheetsError> {
+    let ranges: Vec<String> = ranges.iter().map(|r| r.to_string()).collect();
+    api::v4::batch_get_with(access_token, &ranges, render).await
+}
+
+// One values.get per range, up to `max_in_flight` at a time; rows come back
+// in the order of `ranges`. For ranges batchGet can't do together, or so many
+// tabs that one batchGet response would be huge. Stops at the first error.
+pub async fn read_ranges_concurrent(
+    access_token: &SecretString,
+    ranges: &[&str],
+    max_in_flight: usize,
+) -> Result<Vec<Vec<Vec<Value>>>, SheetsError> {
+    stream::iter(ranges.iter().map(|range| fetch_values(access_token, range)))
+        .buffered(max_in_flight.max(1))
+        .try_collect()
+        .await
+}
+
+// Blank out `range` without deleting rows; returns the range cleared
+pub async fn clear_range(access_token: &SecretString, range: &str) -> Result<String, SheetsError> {
+    api::v4::clear_values(access_token, range).await
+}
+
+// Blank out several ranges in one call
+pub async fn batch_clear(access_token: &SecretString, ranges: &[&str]) -> Result<Vec<String>, SheetsError> {
+    let ranges: Vec<String> = ranges.iter().map(|r| r.to_string()).collect();
+    api::v4::batch_clear(access_token, &ranges).await
+}
+
+// Write several scattered ranges in one values:batchUpdate call, which Google
+// applies all-or-nothing. Returns the range written for each entry, in order.
+pub async fn batch_update_values(
+    access_token: &SecretString,
+    updates: Vec<(String, Vec<Vec<impl Into<CellValue>>>)>,
+) -> Result<Vec<String>, SheetsError> {
+    batch_update_values_with(access_token, updates, ValueInputOption::Auto).await
+}
+
+pub async fn batch_update_values_with(
+    access_token: &SecretString,
+    updates: Vec<(String, Vec<Vec<impl Into<CellValue>>>)>,
+    input: impl Into<WriteOptions>,
+) -> Result<Vec<String>, SheetsError> {
+    read_only::guard("batch_update_values")?;
+    let (data, value_input_option) = values_batch_data(updates, input);
+    let response = api::v4::values_batch_update(access_token, value_input_option, data).await?;
+    Ok(updated_ranges(&response))
+}
+
+// `data` entries for values:batchUpdate; one valueInputOption covers them all
+pub(crate) fn values_batch_data(
+    updates: Vec<(String, Vec<Vec<impl Into<CellValue>>>)>,
+    input: impl Into<WriteOptions>,
+) -> (Vec<Value>, &'static str) {
+    let updates: Vec<(String, Vec<Vec<CellValue>>)> = updates.into_iter().map(|(range, rows)| (range, cell_value::into_cells(rows))).collect();
+    cell_value::ranges_to_json(&updates, input)
+}
+
+pub(crate) fn updated_ranges(response: &Value) -> Vec<String> {
+    response["responses"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|r| r["updatedRange"].as_str().map(str::to_string))
+        .collect()
+}
+
+// Function to read Google Sheets data
+pub async fn read_google_sheet(access_token: &SecretString, filter: &Filter) -> Result<(), SheetsError> {
+    let output = OutputConfig::from_env().map_err(SheetsError::Invalid)?; // OUTPUT_PATH / OUTPUT_KEEP / OUTPUT_PROVENANCE / OUTPUT_SORT
+    let range = A1Range::sheet("RETURNS MAIN").to_string(); // Reads entire sheet
+    export_filtered(access_token, &range, filter, &output, true).await?;
+    Ok(())
+}
+
+// Rows of `range` matching `filter`, redacted and with computed columns, saved
+// to `output`. `echo` also prints the header and every matching row.
+// Returns the number of matching rows.
+pub async fn export_filtered(
+    access_token: &SecretString,
+    range: &str,
+    filter: &Filter,
+    output: &OutputConfig,
+    echo: bool,
+) -> Result<usize, SheetsError> {
+    let sheet_id = config::sheet_id()?;
+    let redactor = Redactor::from_env().map_err(SheetsError::Invalid)?; // REDACT_COLUMNS, e.g. "EMAIL=hash,PHONE=mask"
+    let computed_columns = computed::computed_columns_from_env().map_err(SheetsError::Invalid)?; // COMPUTED_COLUMNS, e.g. "NET=[GROSS]-[REFUND]"
+    let header_rows = table::header_rows_from_env().map_err(SheetsError::Invalid)?.max(1); // HEADER_ROWS=2 for a group row over the field names
+    let schema = Schema::from_env().map_err(SheetsError::Invalid)?; // SHEET_SCHEMA, e.g. "AMOUNT:currency(eu)", for filters and computed columns
+
+    let values = api::v4::get_values(access_token, range).await?;
+
+    let mut filtered_data = Vec::new();
+    let mut count = 0;
+    if !values.is_empty() {
+        // println!(
+        //     "Filtered Rows where Column {} = '{}':",
+        //     column_index + 1,
+        //     filter_value
+        // );
+
+        // Print & Store Header Row
+        let header_rows = header_rows.min(values.len());
+        let raw_header = &if header_rows > 1 { table::merge_header_rows(&values[..header_rows]) } else { values[0].clone() };
+        let header_cells = &computed::extend_header(&computed_columns, raw_header);
+        let header = redactor.apply_header(header_cells);
+        if echo {
+            println!(" Header: {:?}", header);
+        }
+        // Resolved against the raw header, so computed columns can't be filtered on
+        let matcher = filter.compile_with(raw_header, &schema).map_err(SheetsError::Invalid)?;
+        for row in values.iter().skip(header_rows) {
+            let cells = row.as_slice();
+            if matcher.matches(cells) {
+                // Filter on the raw values, only redact what gets printed/saved
+                let cells = computed::extend_row(&computed_columns, raw_header, cells, &schema);
+                let output_row = redactor.apply_row(header_cells, &cells);
+                if echo {
+                    println!("{:?}", output_row);
+                }
+                filtered_data.push(output_row);
+                count += 1;
+            }
+        }
+        println!("Total Matching Rows: {}", count);
+        summary::rows_matched(count);
+        ordering::sort_rows(&header, &mut filtered_data, &output.sort).map_err(SheetsError::Invalid)?;
+
+        match output.format {
+            OutputFormat::Json => {
+                let mut json_output = json!({
+                    "header": header,
+                    "filtered_data": filtered_data,
+                    "count": count
+                });
+
+                if output.provenance {
+                    json_output["provenance"] = json!(provenance::Provenance::new(&sheet_id, range, &filter.to_string(), count));
+                }
+                output.write(json_output.to_string().as_bytes())?;
+            }
+            // CSV has nowhere to put provenance, so it's left out
+            OutputFormat::Csv => output.write(export::to_csv(&header, &filtered_data).as_bytes())?,
+        }
+        println!(" Data saved to '{}'", output.path.display());
+    } else {
+        println!("No data found!");
+    }
+
+    Ok(count)
+}
+
+// Function to append a row to Google Sheets
+pub async fn append_row_to_google_sheet(
+    access_token: &SecretString,
+    new_row: Vec<impl Into<CellValue>>,
+) -> Result<(), SheetsError> {
+    read_only::guard("append")?;
+    let new_row: Vec<CellValue> = new_row.into_iter().map(Into::into).collect();
+    limits::validate_rows(std::slice::from_ref(&new_row))?;
+    let sheet_id = config::sheet_id()?;
+    let range = A1Range::sheet("Sheet1").to_string(); // Adjust based on sheet name
+    policy::global()?.check_range(Operation::Append, &range)?;
+
+    let (values, value_input_option) = cell_value::rows_to_json(std::slice::from_ref(&new_row));
+    let url = format!(
+        "https://sheets.googleapis.com/v4/spreadsheets/{}/values/{}:append?valueInputOption={}",
+        sheet_id, encode_range(&range), value_input_option
+    );
+
+    let body = serde_json::json!({
+        "values": values // Data to be inserted
+    });
+
+    let response = api::v4::send(access_token, Method::POST, &url, Some(&body)).await?;
+
+    println!(" Row added: {:#?}", response);
+    if let Some(updated_range) = response["updates"]["updatedRange"].as_str() {
+        summary::rows_written(1);
+        watch::publish_write(updated_range, watch::ChangeKind::Appended, std::slice::from_ref(&new_row));
+    }
+
+    // VERIFY_WRITES: read the appended range back and compare
+    if verify::verify_writes_enabled() {
+        if let Some(updated_range) = response["updates"]["updatedRange"].as_str() {
+            verify::verify_range(access_token, updated_range, &[new_row]).await?;
+        }
+    }
+    Ok(())
+}
+
+// Function to append many rows, split into requests that stay under the payload limit.
+// Returns the number of rows appended.
+pub async fn append_rows_to_google_sheet(
+    access_token: &SecretString,
+    range: &str,
+    rows: Vec<Vec<impl Into<CellValue>>>,
+) -> Result<usize, SheetsError> {
+    append_rows_to_google_sheet_with(access_token, range, rows, ValueInputOption::Auto).await
+}
+
+// append_rows_to_google_sheet with an explicit valueInputOption, e.g.
+// UserEntered for rows of formulas and dates written as text
+pub async fn append_rows_to_google_sheet_with(
+    access_token: &SecretString,
+    range: &str,
+    rows: Vec<Vec<impl Into<CellValue>>>,
+    input: impl Into<WriteOptions>,
+) -> Result<usize, SheetsError> {
+    if rows.is_empty() {
+        return Ok(0);
+    }
+    let rows = cell_value::into_cells(rows);
+    let input = input.into();
+    read_only::guard("append")?;
+    policy::global()?.check_range(Operation::Append, range)?;
+    limits::validate_rows(&rows)?;
+
+    let sheet = range.split('!').next().unwrap_or(range).trim_matches('\'');
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let metadata = metadata::spreadsheet_metadata(access_token).await?;
+    limits::check_capacity(&metadata, sheet, rows.len(), width)?;
+
+    let sheet_id = config::sheet_id()?;
+    let mut appended = 0;
+    for chunk in limits::split_by_payload(rows, limits::MAX_REQUEST_BYTES)? {
+        let (values, value_input_option) = cell_value::rows_to_json_as(&chunk, input);
+        let url = format!(
+            "https://sheets.googleapis.com/v4/spreadsheets/{}/values/{}:append?valueInputOption={}",
+            sheet_id, encode_range(range), value_input_option
+        );
+        let response = match api::v4::send(access_token, Method::POST, &url, Some(&json!({ "values": values }))).await {
+            Ok(response) => response,
+            Err(e) => {
+                tracing::warn!("append to '{}' stopped after {} rows", range, appended);
+                return Err(e);
+            }
+        };
+        if let Some(updated_range) = response["updates"]["updatedRange"].as_str() {
+            if verify::verify_writes_enabled() {
+                verify::verify_range(access_token, updated_range, &chunk).await?;
+            }
+            watch::publish_write(updated_range, watch::ChangeKind::Appended, &chunk);
+        }
+        appended += chunk.len();
+        summary::rows_written(chunk.len());
+    }
+
+    println!(" Rows added: {}", appended);
+    Ok(appended)
+}
+
+// Function to update a specific row
+pub async fn update_row_in_google_sheet(
+    access_token: &SecretString,
+    row_index: usize,
+    values: Vec<impl Into<CellValue>>,
+) -> Result<(), SheetsError> {
+    update_row_in_google_sheet_with(access_token, row_index, values, ValueInputOption::Auto).await
+}
+
+pub async fn update_row_in_google_sheet_with(
+    access_token: &SecretString,
+    row_index: usize,
+    values: Vec<impl Into<CellValue>>,
+    input: impl Into<WriteOptions>,
+) -> Result<(), SheetsError> {
+    read_only::guard("update")?;
+    let values: Vec<CellValue> = values.into_iter().map(Into::into).collect();
+    limits::validate_rows(std::slice::from_ref(&values))?;
+    let sheet_id = config::sheet_id()?;
+    // Exactly as wide as the new values, so cells to the right are left alone
+    let last_column = values.len().saturating_sub(1);
+    let range = A1Range::sheet("Sheet1").cell(0, row_index).to(last_column, row_index).to_string();
+    policy::global()?.check_range(Operation::Update, &range)?;
+
+    let (json_values, value_input_option) = cell_value::rows_to_json_as(std::slice::from_ref(&values), input);
+    let url = format!(
+        "https://sheets.googleapis.com/v4/spreadsheets/{}/values/{}?valueInputOption={}",
+        sheet_id, encode_range(&range), value_input_option
+    );
+
+    let body = serde_json::json!({
+        "values": json_values
+    });
+
+    let result = api::v4::send(access_token, Method::PUT, &url, Some(&body)).await?;
+    println!(" Row {} updated", row_index);
+    summary::rows_written(1);
+    let updated_range = result["updatedRange"].as_str().unwrap_or(&range);
+    watch::publish_write(updated_range, watch::ChangeKind::Updated, std::slice::from_ref(&values));
+    // VERIFY_WRITES: read the updated range back and compare
+    if verify::verify_writes_enabled() {
+        verify::verify_range(access_token, updated_range, &[values]).await?;
+    }
+    Ok(())
+}
+
+// Function to delete a row from Google Sheets (the first tab, gid 0)
+pub async fn delete_row_from_google_sheet(
+    access_token: &SecretString,
+    row_index: usize,
+) -> Result<(), SheetsError> {
+    delete_rows_from_google_sheet(access_token, 0, row_index, 1).await
+}
+
+// Delete `count` rows starting at 1-based `row_index` from a tab given by
+// title or gid
+pub async fn delete_rows_from_google_sheet(
+    access_token: &SecretString,
+    sheet: impl Into<SheetRef>,
+    row_index: usize,
+    count: usize,
+) -> Result<(), SheetsError> {
+    read_only::guard("delete")?;
+    dimensions::check_span(row_index, count).map_err(SheetsError::Invalid)?;
+    let sheet = sheet.into();
+    metadata::retry_with_fresh_metadata(|| async {
+        let gid = metadata::resolve_sheet(access_token, &sheet).await?;
+        // batch_update checks the tab against the policy and the delete budget
+        api::v4::batch_update(access_token, vec![dimensions::delete_dimension_request(gid, Dimension::Rows, row_index, count)]).await?;
+        Ok(())
+    })
+    .await?;
+    println!(" Deleted {} row(s) from row {}", count, row_index);
+    Ok(())
+}
+
@@ -0,0 +1,112 @@
+use crate::cell_value::CellValue;
+use crate::metadata::SpreadsheetMetadata;
+use std::fmt;
+
+// Documented Sheets limits
+pub const MAX_CELL_CHARS: usize = 50_000;
+pub const MAX_SPREADSHEET_CELLS: u64 = 10_000_000;
+pub const MAX_COLUMNS: usize = 18_278; // A to ZZZ
+// Google recommends keeping request bodies around 2 MB
+pub const MAX_REQUEST_BYTES: usize = 2 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LimitError {
+    CellTooLong { row: usize, column: usize, chars: usize },
+    RowTooLarge { row: usize, bytes: usize },
+    SpreadsheetFull { current: u64, adding: u64 },
+}
+
+impl fmt::Display for LimitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LimitError::CellTooLong { row, column, chars } => write!(
+                f,
+                "row {} column {} has {} characters; Sheets allows at most {} per cell",
+                row + 1,
+                column + 1,
+                chars,
+                MAX_CELL_CHARS
+            ),
+            LimitError::RowTooLarge { row, bytes } => write!(
+                f,
+                "row {} alone is {} bytes, over the {} byte request limit",
+                row + 1,
+                bytes,
+                MAX_REQUEST_BYTES
+            ),
+            LimitError::SpreadsheetFull { current, adding } => write!(
+                f,
+                "spreadsheet already has {} cells; adding {} would exceed the {} cell limit",
+                current, adding, MAX_SPREADSHEET_CELLS
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LimitError {}
+
+// Reject any cell Sheets would silently truncate
+pub fn validate_rows(rows: &[Vec<CellValue>]) -> Result<(), LimitError> {
+    for (row, cells) in rows.iter().enumerate() {
+        for (column, cell) in cells.iter().enumerate() {
+            let chars = cell.text_len();
+            if chars > MAX_CELL_CHARS {
+                return Err(LimitError::CellTooLong { row, column, chars });
+            }
+        }
+    }
+    Ok(())
+}
+
+// Approximate JSON size of one row in a values payload
+pub fn row_payload_bytes(row: &[CellValue]) -> usize {
+    // brackets + quotes/commas per cell, plus escaping headroom (chars, close enough to bytes)
+    2 + row.iter().map(|cell| cell.text_len() + cell.text_len() / 8 + 4).sum::<usize>()
+}
+
+// Split rows into consecutive chunks whose payload stays under `max_bytes`
+pub fn split_by_payload(rows: Vec<Vec<CellValue>>, max_bytes: usize) -> Result<Vec<Vec<Vec<CellValue>>>, LimitError> {
+    let mut chunks = Vec::new();
+    let mut current = Vec::new();
+    let mut current_bytes = 0;
+    for (index, row) in rows.into_iter().enumerate() {
+        let bytes = row_payload_bytes(&row);
+        if bytes > max_bytes {
+            return Err(LimitError::RowTooLarge { row: index, bytes });
+        }
+        if current_bytes + bytes > max_bytes && !current.is_empty() {
+            chunks.push(std::mem::take(&mut current));
+            current_bytes = 0;
+        }
+        current_bytes += bytes;
+        current.push(row);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    Ok(chunks)
+}
+
+// Cells currently allocated across all tabs (grid size, not just filled cells)
+pub fn allocated_cells(metadata: &SpreadsheetMetadata) -> u64 {
+    metadata
+        .sheets
+        .iter()
+        .filter_map(|s| s.grid_properties.as_ref())
+        .map(|g| g.row_count * g.column_count)
+        .sum()
+}
+
+// Appending `rows` rows to `sheet` grows it by rows x its column count
+pub fn check_capacity(metadata: &SpreadsheetMetadata, sheet: &str, rows: usize, width: usize) -> Result<(), LimitError> {
+    let columns = metadata
+        .sheet(sheet)
+        .and_then(|s| s.grid_properties.as_ref())
+        .map_or(width as u64, |g| g.column_count.max(width as u64));
+    let current = allocated_cells(metadata);
+    let adding = rows as u64 * columns;
+    if current + adding > MAX_SPREADSHEET_CELLS {
+        return Err(LimitError::SpreadsheetFull { current, adding });
+    }
+    Ok(())
+}
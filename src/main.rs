@@ -1,263 +1,644 @@
-use dotenv::dotenv;
-use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
-use std::env;
-use std::fs::File;
-use std::io::Write;
-use std::time::{SystemTime, UNIX_EPOCH};
-
-#[derive(Serialize, Deserialize)]
-struct Claims {
-    iss: String,   // Service account email
-    scope: String, // Google Sheets API scope
-    aud: String,   // Token URL
-    exp: u64,      // Expiration time
-    iat: u64,      // Issued at time
-}
-
-// Function to get Google OAuth2 token
-async fn get_google_access_token() -> Result<String, Box<dyn std::error::Error>> {
-    dotenv().ok(); // Load .env variables
-
-    let client_email = env::var("SERVICE_ACCOUNT_EMAIL")?;
-    let private_key = env::var("PRIVATE_KEY")?.replace("\\n", "\n"); // Convert escaped \n to actual newlines
-
-    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
-    let claims = Claims {
-        iss: client_email,
-        scope: "https://www.googleapis.com/auth/spreadsheets".to_string(), // Full access needed to write
-        aud: "https://oauth2.googleapis.com/token".to_string(),
-        exp: now + 3600,
-        iat: now,
-    };
+use clap::{Args, Parser, Subcommand};
+use google_sheet::a1::A1Range;
+use google_sheet::aggregate::summarize_column;
+use google_sheet::confirm;
+use google_sheet::count::distinct_value_counts;
+use google_sheet::doctor::run_doctor;
+use google_sheet::drive::{export_spreadsheet_xlsx, DRIVE_READONLY_SCOPE};
+use google_sheet::export::OutputFormat;
+use google_sheet::import::{import_csv, ImportMode};
+use google_sheet::init::run_init;
+use google_sheet::metadata::{sheet_name_for, SheetRef};
+use google_sheet::numbers::Amount;
+use google_sheet::pii::scan_pii;
+use google_sheet::sample::{preview, sample};
+use google_sheet::schema::Schema;
+use google_sheet::scope::scope_urls;
+use google_sheet::sweep::{sweep_expired, ExpiryAction, Retention};
+use google_sheet::{har, journal, read_only, summary, table};
+use google_sheet::whoami::whoami;
+use google_sheet::{
+    access_token_for, config, export_filtered, get_google_access_token, Credentials, Filter, NullPolicy, OutputConfig, SheetsClient, ValueInputOption,
+    WriteOptions,
+};
+use std::path::PathBuf;
+
+// The global options go anywhere on the line; the rest after their command
+#[derive(Debug, Parser)]
+#[command(
+    name = "sheets",
+    about = "Read, write and maintain a Google Sheet",
+    after_help = "Without a command, reads RETURNS MAIN with ROW_FILTER or the built-in filter."
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[arg(short, long, global = true, value_name = "ID", help = "spreadsheet ID or URL (overrides SHEET_ID)")]
+    spreadsheet: Option<String>,
+    #[arg(long, global = true, value_name = "N", help = "header rows to merge into \"GROUP / FIELD\" names (overrides HEADER_ROWS)")]
+    header_rows: Option<usize>,
+    #[arg(long, global = true, help = "fail every write before it reaches the API")]
+    read_only: bool,
+    #[arg(long, global = true, help = "don't ask before mass deletes")]
+    yes: bool,
+    #[arg(long, global = true, value_name = "FILE", help = "record every API request and response to FILE (overrides HAR_PATH)")]
+    har: Option<PathBuf>,
+}
 
-    let jwt = encode(
-        &Header::new(Algorithm::RS256),
-        &claims,
-        &EncodingKey::from_rsa_pem(private_key.as_bytes())?,
-    )?;
-
-    let client = Client::new();
-    let response = client
-        .post("https://oauth2.googleapis.com/token")
-        .form(&[
-            ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
-            ("assertion", &jwt),
-        ])
-        .send()
-        .await?
-        .json::<Value>()
-        .await?;
-
-    Ok(response["access_token"].as_str().unwrap().to_string())
-}
-
-// Function to read Google Sheets data
-async fn read_google_sheet(
-    access_token: &str, column_index1: usize, filter_value1: &str, column_index2: usize, filter_value2: &str) -> Result<(), Box<dyn std::error::Error>> {
-    dotenv().ok();
-    let sheet_id = env::var("SHEET_ID")?;
-    let range = "RETURNS MAIN"; // Reads entire sheet
-
-    let url = format!(
-        "https://sheets.googleapis.com/v4/spreadsheets/{}/values/{}",
-        sheet_id, range
-    );
-
-    let client = Client::new();
-    let response = client
-        .get(&url)
-        .bearer_auth(access_token)
-        .send()
-        .await?
-        .json::<Value>()
-        .await?;
-
-    print!("{}", response);
-
-    let mut filtered_data = Vec::new();
-    let mut count = 0;
-    if let Some(values) = response["values"].as_array() {
-        // println!(
-        //     "Filtered Rows where Column {} = '{}':",
-        //     column_index + 1,
-        //     filter_value
-        // );
-
-        // Print & Store Header Row
-        let header = &values[0];
-        println!(" Header: {:?}", header);
-        for row in values.iter().skip(1) {
-            let match_col1 = row.get(column_index1).map_or(false, |cell| cell.as_str() == Some(filter_value1));
-            let match_col2 = row.get(column_index2).map_or(false, |cell| cell.as_str() == Some(filter_value2));
-
-            if match_col1 && match_col2 {
-                println!("{:?}", row);
-                filtered_data.push(row.clone());
-                count += 1;
-            }
+#[derive(Debug, Subcommand)]
+enum Command {
+    #[command(about = "print rows of --range matching --filter and save them to --output")]
+    Read(ReadArgs),
+    #[command(about = "save rows of --range matching --filter to --output without printing them")]
+    Export(ReadArgs),
+    #[command(about = "append VALUES as one row to --range (default: --sheet)")]
+    Append {
+        #[command(flatten)]
+        target: Target,
+        #[command(flatten)]
+        write: WriteArgs,
+        #[arg(required = true, allow_negative_numbers = true, help = "the row's cell values")]
+        values: Vec<String>,
+    },
+    #[command(about = "overwrite the row at --range, or --row N of --sheet, with VALUES")]
+    Update {
+        #[command(flatten)]
+        target: Target,
+        #[arg(long, value_name = "N", required_unless_present = "range", help = "1-based row number")]
+        row: Option<usize>,
+        #[command(flatten)]
+        write: WriteArgs,
+        #[arg(required = true, allow_negative_numbers = true, help = "the row's cell values")]
+        values: Vec<String>,
+    },
+    #[command(about = "blank out the values in --range, keeping the rows")]
+    Clear {
+        #[arg(short, long, value_parser = a1_range, help = "A1 range or tab name")]
+        range: String,
+    },
+    #[command(about = "load a CSV file into --range (see --mode)")]
+    Import {
+        path: PathBuf,
+        #[command(flatten)]
+        target: Target,
+        #[arg(long, value_name = "MODE", value_parser = import_mode, default_value = "append",
+            help = "append, overwrite from the range's first cell, or replace (clear the range first)")]
+        mode: ImportMode,
+    },
+    #[command(about = "delete --count rows from --row N of --sheet (or --gid)")]
+    Delete {
+        #[arg(long, value_name = "N", help = "1-based row number")]
+        row: usize,
+        #[arg(long, default_value_t = 1, help = "rows to delete")]
+        count: usize,
+        #[arg(long, value_name = "TAB", allow_hyphen_values = true, help = "tab to delete from (default: the first)")]
+        sheet: Option<String>,
+        #[arg(long, value_name = "N", conflicts_with = "sheet", help = "tab id, instead of --sheet")]
+        gid: Option<u64>,
+    },
+    #[command(about = "send the writes JOURNAL_PATH queued while Google was unreachable")]
+    ReplayJournal,
+    #[command(about = "delete (or archive) rows older than RETENTION_DAYS by RETENTION_DATE_COLUMN",
+        long_about = "delete (or archive) rows older than RETENTION_DAYS by RETENTION_DATE_COLUMN\n\n\
+            From cron, add --yes or set RETENTION_FORCE=1 so a large backlog isn't refused.")]
+    Sweep {
+        #[arg(long, help = "only report the rows it would remove")]
+        dry_run: bool,
+    },
+    #[command(about = "list the values of a column in --sheet, with row counts")]
+    Distinct {
+        #[arg(help = "the column's header text")]
+        column: String,
+        #[arg(long, value_name = "TAB", allow_hyphen_values = true, default_value = "RETURNS MAIN")]
+        sheet: String,
+    },
+    #[command(about = "count, sum, min, max and mean of columns in --range, in the locales SHEET_SCHEMA gives them")]
+    Summarize {
+        #[arg(short, long, value_parser = a1_range, help = "A1 range or tab name (default RETURNS MAIN)")]
+        range: Option<String>,
+        #[arg(required = true, help = "the columns' header text")]
+        columns: Vec<String>,
+    },
+    #[command(about = "print the header and first --count rows of --range")]
+    Preview(SampleArgs),
+    #[command(about = "print the header and --count random rows of --range (see --seed)")]
+    Sample {
+        #[command(flatten)]
+        rows: SampleArgs,
+        #[arg(long, help = "the same seed picks the same rows (default: random)")]
+        seed: Option<u64>,
+    },
+    #[command(about = "download the whole workbook as .xlsx")]
+    Snapshot {
+        #[arg(short, long, value_name = "FILE", default_value = "snapshot.xlsx")]
+        output: PathBuf,
+    },
+    #[command(about = "report columns that look like personal data")]
+    ScanPii {
+        #[arg(value_parser = a1_range, help = "A1 range or tab name (default: the SHEET_ID URL's tab, else RETURNS MAIN)")]
+        range: Option<String>,
+    },
+    #[command(about = "check config, credentials and access")]
+    Doctor,
+    #[command(about = "write a .env interactively")]
+    Init,
+    #[command(about = "show the token's principal, scopes and expiry")]
+    Whoami,
+}
+
+impl Command {
+    // For the run summary
+    fn name(&self) -> &'static str {
+        match self {
+            Command::Read(_) => "read",
+            Command::Export(_) => "export",
+            Command::Append { .. } => "append",
+            Command::Update { .. } => "update",
+            Command::Clear { .. } => "clear",
+            Command::Import { .. } => "import",
+            Command::Delete { .. } => "delete",
+            Command::ReplayJournal => "replay-journal",
+            Command::Sweep { .. } => "sweep",
+            Command::Distinct { .. } => "distinct",
+            Command::Summarize { .. } => "summarize",
+            Command::Preview(_) => "preview",
+            Command::Sample { .. } => "sample",
+            Command::Snapshot { .. } => "snapshot",
+            Command::ScanPii { .. } => "scan-pii",
+            Command::Doctor => "doctor",
+            Command::Init => "init",
+            Command::Whoami => "whoami",
         }
-        println!("Total Matching Rows: {}", count);
-        //  Save to JSON file
-        let json_output = json!({
-            "header": header,
-            "filtered_data": filtered_data,
-            "count": count
-        });
-
-        let mut file = File::create("output.json")?;
-        file.write_all(json_output.to_string().as_bytes())?;
-        println!(" Data saved to 'output.json'");
-    } else {
-        println!("No data found!");
-    }
-
-    Ok(())
-}
-
-// Function to append a row to Google Sheets
-async fn append_row_to_google_sheet(
-    access_token: &str,
-    new_row: Vec<String>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    dotenv().ok();
-    let sheet_id = env::var("SHEET_ID")?;
-    let range = "Sheet1"; // Adjust based on sheet name
-
-    let url = format!(
-        "https://sheets.googleapis.com/v4/spreadsheets/{}/values/{}:append?valueInputOption=RAW",
-        sheet_id, range
-    );
-
-    let client = Client::new();
-    let body = serde_json::json!({
-        "values": [new_row] // Data to be inserted
-    });
-
-    let response = client
-        .post(&url)
-        .bearer_auth(access_token)
-        .json(&body)
-        .send()
-        .await?
-        .json::<Value>()
-        .await?;
-
-    println!(" Row added: {:#?}", response);
-    Ok(())
-}
-
-// Function to update a specific row
-async fn update_row_in_google_sheet(
-    access_token: &str,
-    row_index: usize,
-    values: Vec<String>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    dotenv().ok();
-    let sheet_id = env::var("SHEET_ID")?;
-    let range = format!("Sheet1!A{}:Z{}", row_index, row_index); // Adjust based on column range
-
-    let url = format!(
-        "https://sheets.googleapis.com/v4/spreadsheets/{}/values/{}?valueInputOption=RAW",
-        sheet_id, range
-    );
-
-    let body = serde_json::json!({
-        "values": [values]
-    });
-
-    let client = Client::new();
-    let response = client
-        .put(&url)
-        .bearer_auth(access_token)
-        .json(&body)
-        .send()
-        .await?;
-
-    println!("Update row status: {}", response.status());
-    Ok(())
-}
-
-// Function to delete a row from Google Sheets
-async fn delete_row_from_google_sheet(
-    access_token: &str,
-    row_index: usize,
-) -> Result<(), Box<dyn std::error::Error>> {
-    dotenv().ok();
-    let sheet_id = env::var("SHEET_ID")?;
-
-    let delete_url = format!(
-        "https://sheets.googleapis.com/v4/spreadsheets/{}:batchUpdate",
-        sheet_id
-    );
-
-    let body = serde_json::json!({
-        "requests": [
-            {
-                "deleteDimension": {
-                    "range": {
-                        "sheetId": 0, // Sheet ID (0 usually refers to the first sheet)
-                        "dimension": "ROWS",
-                        "startIndex": row_index - 1,  // Google Sheets uses zero-based index
-                        "endIndex": row_index
-                    }
-                }
-            }
-        ]
-    });
+    }
+}
 
-    let client = Client::new();
-    let response = client
-        .post(&delete_url)
-        .bearer_auth(access_token)
-        .json(&body)
-        .send()
-        .await?;
+#[derive(Debug, Default, Args)]
+struct ReadArgs {
+    #[arg(short, long, value_parser = a1_range, help = "A1 range or tab name (default RETURNS MAIN)")]
+    range: Option<String>,
+    #[arg(short, long, value_name = "EXPR", allow_hyphen_values = true,
+        help = "e.g. \"STATUS = open AND AMOUNT > 100\" (overrides ROW_FILTER)")]
+    filter: Option<String>,
+    #[arg(short, long, value_name = "FILE", help = "where to save the rows (overrides OUTPUT_PATH)")]
+    output: Option<PathBuf>,
+    #[arg(long, value_parser = output_format, help = "json or csv (overrides OUTPUT_FORMAT; default from the file extension)")]
+    format: Option<OutputFormat>,
+}
+
+// Where append, update and import write
+#[derive(Debug, Args)]
+struct Target {
+    #[arg(short, long, value_parser = a1_range, help = "A1 range or tab name")]
+    range: Option<String>,
+    #[arg(long, value_name = "TAB", allow_hyphen_values = true, default_value = "Sheet1", help = "tab, when --range isn't given")]
+    sheet: String,
+}
 
-    println!("Delete row status: {}", response.status());
-    Ok(())
+impl Target {
+    fn range(&self) -> String {
+        self.range.clone().unwrap_or_else(|| A1Range::sheet(&self.sheet).to_string())
+    }
+}
+
+#[derive(Debug, Args)]
+struct WriteArgs {
+    #[arg(long, value_name = "MODE", value_parser = input_option, default_value = "auto",
+        help = "how values are parsed: auto, raw or user-entered")]
+    input: ValueInputOption,
+    #[arg(long, value_name = "MODE", value_parser = null_policy, default_value = "clear",
+        help = "what empty values do: clear, skip (leave the cell as it was) or empty (write a zero-length string)")]
+    nulls: NullPolicy,
+}
+
+impl WriteArgs {
+    fn options(&self) -> WriteOptions {
+        WriteOptions { input: self.input, nulls: self.nulls }
+    }
+}
+
+#[derive(Debug, Args)]
+struct SampleArgs {
+    #[arg(short, long, value_parser = a1_range, help = "A1 range or tab name (default RETURNS MAIN)")]
+    range: Option<String>,
+    #[arg(long, default_value_t = 10, help = "rows to print")]
+    count: usize,
+}
+
+// Normalized so tab names with spaces get quoted
+fn a1_range(text: &str) -> Result<String, String> {
+    Ok(A1Range::parse(text)?.to_string())
+}
+
+fn output_format(name: &str) -> Result<OutputFormat, String> {
+    OutputFormat::parse(name).ok_or_else(|| "expected json or csv".to_string())
+}
+
+fn input_option(mode: &str) -> Result<ValueInputOption, String> {
+    ValueInputOption::parse(mode).ok_or_else(|| "expected auto, raw or user-entered".to_string())
+}
+
+fn null_policy(mode: &str) -> Result<NullPolicy, String> {
+    NullPolicy::parse(mode).ok_or_else(|| "expected clear, skip or empty".to_string())
+}
+
+fn import_mode(mode: &str) -> Result<ImportMode, String> {
+    ImportMode::parse(mode).ok_or_else(|| "expected append, overwrite or replace".to_string())
 }
 
 #[tokio::main]
 async fn main() {
-    match get_google_access_token().await {
-        Ok(token) => {
-            println!(" Token retrieved!");
+    let cli = Cli::parse();
+    if cli.read_only {
+        read_only::set_read_only(true);
+    }
+    if let Some(path) = &cli.har {
+        har::record_to(path);
+    }
+    confirm::set_interactive(true);
+    if cli.yes {
+        confirm::force();
+    }
+    if let Some(spreadsheet) = &cli.spreadsheet {
+        config::set_spreadsheet(spreadsheet);
+    }
+    if let Some(rows) = cli.header_rows {
+        table::set_header_rows(rows);
+    }
+    summary::start();
+    let name = cli.command.as_ref().map_or("read", Command::name);
+    let mut exit_code = 0;
+    match &cli.command {
+        // A bare `sheets` keeps the original built-in filter
+        None => run_read(&ReadArgs::default(), true, builtin_filter()).await,
+        Some(Command::Doctor) => {
+            let report = run_doctor().await;
+            print!("{}", report);
+            if report.has_failures() {
+                summary::error();
+                exit_code = 1;
+            }
+        }
+        Some(Command::Init) => match run_init().await {
+            Ok(path) => println!(" Config written to '{}'", path.display()),
+            Err(e) => {
+                fail("Setup failed", e);
+                exit_code = 1;
+            }
+        },
+        Some(Command::Read(args)) => run_read(args, true, Filter::all()).await,
+        Some(Command::Export(args)) => run_read(args, false, Filter::all()).await,
+        Some(Command::Append { target, write, values }) => run_append(target, write, values).await,
+        Some(Command::Update { target, row, write, values }) => run_update(target, *row, write, values).await,
+        Some(Command::Clear { range }) => run_clear(range).await,
+        Some(Command::Import { path, target, mode }) => run_import(path, target, *mode).await,
+        Some(Command::Delete { row, count, sheet, gid }) => run_delete(*row, *count, sheet.as_deref(), *gid).await,
+        Some(Command::Snapshot { output }) => run_snapshot(output).await,
+        Some(Command::Distinct { column, sheet }) => run_distinct(column, sheet).await,
+        Some(Command::Summarize { range, columns }) => run_summarize(range.as_deref(), columns).await,
+        Some(Command::Preview(args)) => run_sample(args, None).await,
+        Some(Command::Sample { rows, seed }) => {
+            // Printed so a sample worth a second look can be drawn again
+            let seed = seed.unwrap_or_else(|| chrono::Utc::now().timestamp_micros() as u64);
+            println!(" Seed: {}", seed);
+            run_sample(rows, Some(seed)).await
+        }
+        Some(Command::ReplayJournal) => run_replay_journal().await,
+        Some(Command::Sweep { dry_run }) => run_sweep(*dry_run).await,
+        Some(Command::ScanPii { range }) => run_scan_pii(range.as_deref()).await,
+        Some(Command::Whoami) => run_whoami().await,
+    }
+    // Machine-readable totals for schedulers (RUN_SUMMARY=0 to silence)
+    summary::emit(name);
+    if exit_code == 0 && !summary::snapshot(name).ok {
+        exit_code = 1;
+    }
+    if exit_code != 0 {
+        std::process::exit(exit_code);
+    }
+}
+
+fn fail(context: &str, error: impl std::fmt::Display) {
+    summary::error();
+    eprintln!("{}: {}", context, error);
+}
+
+fn builtin_filter() -> Filter {
+    Filter::eq(1, "AryfS") // Column B (CHANNEL VLOOKUP)
+        .and(Filter::eq(9, "FALSE")) // Column J (Refunded)
+}
+
+// --filter, else ROW_FILTER, else `default`
+fn row_filter(args: &ReadArgs, default: Filter) -> Result<Filter, String> {
+    if let Some(spec) = &args.filter {
+        return Filter::parse(spec);
+    }
+    Ok(Filter::from_env()?.unwrap_or(default))
+}
+
+async fn run_read(args: &ReadArgs, echo: bool, default: Filter) {
+    let filter = match row_filter(args, default) {
+        Ok(filter) => filter,
+        Err(e) => return fail("Error in filter", e),
+    };
+    let mut output = match OutputConfig::from_env() {
+        Ok(output) => output, // OUTPUT_PATH / OUTPUT_KEEP / OUTPUT_PROVENANCE / OUTPUT_SORT / OUTPUT_FORMAT
+        Err(e) => return fail("Error in output settings", e),
+    };
+    if let Some(path) = &args.output {
+        output.set_path(path.clone());
+    }
+    if let Some(format) = args.format {
+        output.format = format;
+    }
+    let token = match get_google_access_token().await {
+        Ok(token) => token,
+        Err(e) => return fail("Error getting token", e),
+    };
+    println!(" Token retrieved!");
+    let range = args.range.clone().unwrap_or_else(|| A1Range::sheet("RETURNS MAIN").to_string()); // Reads entire sheet
+    if let Err(e) = export_filtered(&token, &range, &filter, &output, echo).await {
+        fail("Error reading sheet", e);
+    }
+}
 
-            let column_index1: usize = 1; // Column B (CHANNEL VLOOKUP)
-            let filter_value1 = "AryfS";
+fn client() -> Option<SheetsClient> {
+    SheetsClient::from_env().map_err(|e| fail("Error loading config", e)).ok()
+}
 
-            let column_index2: usize = 9; // Column J (Refunded)
-            let filter_value2 = "FALSE"; 
+async fn run_append(target: &Target, write: &WriteArgs, values: &[String]) {
+    let Some(client) = client() else { return };
+    match client.append_with(&target.range(), vec![values.to_vec()], write.options()).await {
+        Ok(updated) => println!(" Row added at {}", updated),
+        Err(e) => fail("Error appending row", e),
+    }
+}
 
-            // Read existing data
-            if let Err(e) = read_google_sheet(&token, column_index1, filter_value1, column_index2, filter_value2).await {
-                eprintln!("Error reading sheet: {}", e);
+async fn run_update(target: &Target, row: Option<usize>, write: &WriteArgs, values: &[String]) {
+    let range = match (&target.range, row) {
+        (Some(range), _) => range.clone(),
+        (None, Some(row)) => A1Range::sheet(&target.sheet).cell(0, row).to_string(),
+        (None, None) => return fail("Nothing to update", "pass --range or --row"),
+    };
+    let Some(client) = client() else { return };
+    match client.update_with(&range, vec![values.to_vec()], write.options()).await {
+        Ok(updated) => println!(" Updated {}", updated),
+        Err(e) => fail("Error updating row", e),
+    }
+}
+
+async fn run_clear(range: &str) {
+    let Some(client) = client() else { return };
+    match client.clear_range(range).await {
+        Ok(cleared) => println!(" Cleared {}", cleared),
+        Err(e) => fail("Error clearing range", e),
+    }
+}
+
+async fn run_import(path: &std::path::Path, target: &Target, mode: ImportMode) {
+    let token = match get_google_access_token().await {
+        Ok(token) => token,
+        Err(e) => return fail("Error getting token", e),
+    };
+    let range = target.range();
+    match import_csv(&token, path, &range, mode).await {
+        Ok(rows) => println!(" Imported {} row(s) into {}", rows, range),
+        Err(e) => fail("Error importing CSV", e),
+    }
+}
+
+async fn run_delete(row: usize, count: usize, sheet: Option<&str>, gid: Option<u64>) {
+    let Some(client) = client() else { return };
+    let sheet = match (gid, sheet) {
+        (Some(gid), _) => SheetRef::Gid(gid),
+        (None, Some(sheet)) => SheetRef::from(sheet),
+        (None, None) => SheetRef::Gid(0),
+    };
+    match client.delete(sheet, row, count).await {
+        Ok(()) => println!(" Deleted {} row(s) from row {}", count, row),
+        Err(e) => fail("Error deleting rows", e),
+    }
+}
+
+async fn run_replay_journal() {
+    let Some(path) = journal::path() else { return fail("Nothing to replay", "JOURNAL_PATH is not set") };
+    let token = match get_google_access_token().await {
+        Ok(token) => token,
+        Err(e) => return fail("Error getting token", e),
+    };
+    match journal::replay_journal(&token).await {
+        Ok(outcome) => {
+            println!(" Replayed {} write(s) from '{}'", outcome.replayed, path.display());
+            if outcome.rejected > 0 {
+                println!(" {} rejected by the API, see '{}.rejected'", outcome.rejected, path.display());
+            }
+            if outcome.remaining > 0 {
+                fail("Google still unreachable", format!("{} write(s) left in the journal", outcome.remaining));
+            }
+        }
+        Err(e) => fail("Error replaying journal", e),
+    }
+}
+
+async fn run_sweep(dry_run: bool) {
+    let retention = match Retention::from_env() {
+        Ok(retention) => retention,
+        Err(e) => return fail("Error in retention settings", e),
+    };
+    let Some(client) = client() else { return };
+    match sweep_expired(&client, &retention, dry_run).await {
+        Ok(outcome) => {
+            let verb = match (&retention.action, outcome.dry_run) {
+                (_, true) => "Would remove".to_string(),
+                (ExpiryAction::Delete, false) => "Deleted".to_string(),
+                (ExpiryAction::Archive(tab), false) => format!("Archived to '{}' and deleted", tab),
+            };
+            println!(" {} {} row(s) dated before {} from '{}'", verb, outcome.expired.len(), outcome.cutoff, retention.sheet);
+            if outcome.dry_run {
+                for row in &outcome.expired {
+                    println!("   row {} ({})", row.row_number, row.date);
+                }
+            }
+            if outcome.undated > 0 {
+                println!(" Kept {} row(s) without a readable date", outcome.undated);
+            }
+        }
+        Err(e) => fail("Error sweeping expired rows", e),
+    }
+}
+
+async fn run_distinct(column: &str, sheet: &str) {
+    let token = match get_google_access_token().await {
+        Ok(token) => token,
+        Err(e) => return fail("Error getting token", e),
+    };
+    match distinct_value_counts(&token, sheet, column).await {
+        Ok(counts) => {
+            for (value, count) in &counts {
+                println!("{:>8}  {}", count, value);
+            }
+            println!(" {} distinct value(s) in '{}'", counts.len(), column);
+        }
+        Err(e) => fail("Error reading column", e),
+    }
+}
+
+async fn run_summarize(range: Option<&str>, columns: &[String]) {
+    let schema = match Schema::from_env() {
+        Ok(schema) => schema,
+        Err(e) => return fail("Error in SHEET_SCHEMA", e),
+    };
+    let Some(client) = client() else { return };
+    let range = range.map_or_else(|| A1Range::sheet("RETURNS MAIN").to_string(), str::to_string);
+    let table = match client.read(&range).await {
+        Ok(table) => table,
+        Err(e) => return fail("Error reading range", e),
+    };
+    for column in columns {
+        match summarize_column(&table, column, &schema) {
+            Ok(summary) => {
+                let show = |amount: Option<Amount>| amount.map_or("-".to_string(), |a| a.to_string());
+                println!(
+                    " {}: {} number(s), sum {}, min {}, max {}, mean {}",
+                    summary.column, summary.count, summary.sum, show(summary.min), show(summary.max), show(summary.mean())
+                );
+                if summary.skipped > 0 {
+                    println!("   {} cell(s) weren't numbers", summary.skipped);
+                }
+            }
+            Err(e) => fail("Error summarizing column", e),
+        }
+    }
+}
+
+// `seed` draws random rows, without it the first ones
+async fn run_sample(args: &SampleArgs, seed: Option<u64>) {
+    let range = args.range.clone().unwrap_or_else(|| A1Range::sheet("RETURNS MAIN").to_string());
+    let count = args.count;
+    let token = match get_google_access_token().await {
+        Ok(token) => token,
+        Err(e) => return fail("Error getting token", e),
+    };
+    let result = match seed {
+        Some(seed) => sample(&token, &range, count, seed).await,
+        None => preview(&token, &range, count).await,
+    };
+    match result {
+        Ok(rows) => {
+            println!(" Header: {:?}", rows.header);
+            for (number, row) in &rows.rows {
+                println!("{:>7}: {:?}", number, row);
             }
+        }
+        Err(e) => fail("Error reading rows", e),
+    }
+}
 
-            // // Append a new row
-            // let new_data = vec![
-            //     "afwe".to_string(),
-            //     "wefwe@gmail.com".to_string(),
-            //     "192783568".to_string(),
-            // ];
-            // if let Err(e) = append_row_to_google_sheet(&token, new_data).await {
-            //     eprintln!("Error appending row: {}", e);
-            // }
-
-            // // Update row 2 (change values as needed)
-            // let updated_row = vec!["Jane Doe".to_string(), "janedoe@gmail.com".to_string(), "1234567890".to_string()];
-            // if let Err(e) = update_row_in_google_sheet(&token, 2, updated_row).await {
-            //     eprintln!("Error updating row: {}", e);
-            // }
-
-            // // Delete a row (Example: Delete Row 3)
-            // if let Err(e) = delete_row_from_google_sheet(&token, 3).await {
-            //     eprintln!("Error deleting row: {}", e);
-            // }
+async fn run_snapshot(path: &std::path::Path) {
+    // files/export is a Drive endpoint, so the token needs a Drive scope on top
+    // of the ones SHEETS_SCOPES gives the credentials
+    let credentials = match Credentials::from_env() {
+        Ok(credentials) => credentials,
+        Err(e) => return fail("Error loading credentials", e),
+    };
+    let mut scopes = scope_urls(&credentials.scopes);
+    if !scopes.contains(&DRIVE_READONLY_SCOPE) {
+        scopes.push(DRIVE_READONLY_SCOPE);
+    }
+    let token = match access_token_for(&credentials, &scopes).await {
+        Ok(token) => token,
+        Err(e) => return fail("Error getting token", e),
+    };
+    match export_spreadsheet_xlsx(&token, path).await {
+        Ok(bytes) => println!(" Workbook saved to '{}' ({} bytes)", path.display(), bytes),
+        Err(e) => fail("Error exporting workbook", e),
+    }
+}
+
+// Print a per-column PII report plus a suggested REDACT_COLUMNS value
+// Without a range, scans the tab from a `#gid=` SHEET_ID URL, else RETURNS MAIN
+async fn run_scan_pii(range: Option<&str>) {
+    let token = match get_google_access_token().await {
+        Ok(token) => token,
+        Err(e) => return fail("Error getting token", e),
+    };
+    let range = match range {
+        Some(range) => range.to_string(),
+        None => {
+            let from_url = match config::spreadsheet() {
+                Ok(spreadsheet) => sheet_name_for(&token, &spreadsheet).await,
+                Err(e) => Err(e.into()),
+            };
+            match from_url {
+                Ok(name) => name.unwrap_or_else(|| "RETURNS MAIN".to_string()),
+                Err(e) => return fail("Error resolving sheet tab", e),
+            }
         }
-        Err(e) => eprintln!("Error getting token: {}", e),
+    };
+    match scan_pii(&token, &range).await {
+        Ok(report) => println!("{}", report),
+        Err(e) => fail("Error scanning sheet", e),
+    }
+}
+
+// Show the principal, scopes and expiry of the token this config produces
+async fn run_whoami() {
+    let token = match get_google_access_token().await {
+        Ok(token) => token,
+        Err(e) => return fail("Error getting token", e),
+    };
+    match whoami(&token).await {
+        Ok(info) => print!("{}", info),
+        Err(e) => fail("Error checking token", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+        Cli::try_parse_from(std::iter::once("sheets").chain(args.iter().copied()))
+    }
+
+    #[test]
+    fn command_line_is_well_formed() {
+        use clap::CommandFactory;
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn flag_values_may_start_with_a_dash() {
+        let cli = parse(&["append", "--sheet", "-archive", "x"]).unwrap();
+        let Some(Command::Append { target, .. }) = cli.command else { panic!("not append") };
+        assert_eq!(target.sheet, "-archive");
+        let cli = parse(&["read", "--filter=-x"]).unwrap();
+        let Some(Command::Read(args)) = cli.command else { panic!("not read") };
+        assert_eq!(args.filter.as_deref(), Some("-x"));
+    }
+
+    #[test]
+    fn negative_numbers_are_values() {
+        let cli = parse(&["append", "-5", "-3.2", "x"]).unwrap();
+        let Some(Command::Append { values, .. }) = cli.command else { panic!("not append") };
+        assert_eq!(values, ["-5", "-3.2", "x"]);
+    }
+
+    #[test]
+    fn unknown_flags_are_rejected() {
+        assert!(parse(&["append", "-q"]).is_err());
+        assert!(parse(&["delete", "--row"]).is_err());
+        let cli = parse(&["append", "--", "-q"]).unwrap();
+        let Some(Command::Append { values, .. }) = cli.command else { panic!("not append") };
+        assert_eq!(values, ["-q"]);
+    }
+
+    #[test]
+    fn global_options_go_anywhere() {
+        let cli = parse(&["--read-only", "export", "-s", "abc", "--header-rows", "2"]).unwrap();
+        assert!(cli.read_only);
+        assert_eq!(cli.spreadsheet.as_deref(), Some("abc"));
+        assert_eq!(cli.header_rows, Some(2));
+        let cli = parse(&["--yes"]).unwrap();
+        assert!(cli.command.is_none() && cli.yes);
     }
 }
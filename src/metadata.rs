@@ -0,0 +1,252 @@
+use crate::{api, cache_file, config, SecretString, SheetsError, SpreadsheetId};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GridProperties {
+    #[serde(default)]
+    pub row_count: u64,
+    #[serde(default)]
+    pub column_count: u64,
+}
+
+// One tab of a spreadsheet; `sheet_id` is the gid shown in URLs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SheetProperties {
+    pub sheet_id: u64,
+    pub title: String,
+    #[serde(default)]
+    pub index: u32,
+    pub grid_properties: Option<GridProperties>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpreadsheetMetadata {
+    pub spreadsheet_id: String,
+    pub title: String,
+    pub sheets: Vec<SheetProperties>,
+}
+
+impl SpreadsheetMetadata {
+    pub fn resolve_gid(&self, name: &str) -> Option<u64> {
+        self.sheets.iter().find(|s| s.title == name).map(|s| s.sheet_id)
+    }
+
+    pub fn resolve_name(&self, gid: u64) -> Option<&str> {
+        self.sheets.iter().find(|s| s.sheet_id == gid).map(|s| s.title.as_str())
+    }
+
+    pub fn sheet(&self, name: &str) -> Option<&SheetProperties> {
+        self.sheets.iter().find(|s| s.title == name)
+    }
+}
+
+// A tab given either way: its gid or its title
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SheetRef {
+    Gid(u64),
+    Title(String),
+}
+
+impl From<u64> for SheetRef {
+    fn from(gid: u64) -> Self {
+        SheetRef::Gid(gid)
+    }
+}
+
+impl From<&str> for SheetRef {
+    fn from(title: &str) -> Self {
+        SheetRef::Title(title.to_string())
+    }
+}
+
+impl From<String> for SheetRef {
+    fn from(title: String) -> Self {
+        SheetRef::Title(title)
+    }
+}
+
+impl From<&String> for SheetRef {
+    fn from(title: &String) -> Self {
+        SheetRef::Title(title.clone())
+    }
+}
+
+struct Cached {
+    fetched: Instant,
+    metadata: Arc<SpreadsheetMetadata>,
+}
+
+// Process-wide cache keyed by spreadsheet ID
+fn cache() -> MutexGuard<'static, HashMap<String, Cached>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, Cached>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new())).lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// One fetch per spreadsheet at a time; tasks missing the cache together wait
+// for the first one's result instead of each calling the API
+fn fetch_lock(spreadsheet_id: &str) -> Arc<tokio::sync::Mutex<()>> {
+    static LOCKS: OnceLock<Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>> = OnceLock::new();
+    let mut locks = LOCKS.get_or_init(|| Mutex::new(HashMap::new())).lock().unwrap_or_else(|e| e.into_inner());
+    locks.entry(spreadsheet_id.to_string()).or_default().clone()
+}
+
+static TTL: Mutex<Option<Duration>> = Mutex::new(None);
+
+// How long cached metadata is trusted, in memory and in the cache file
+pub fn set_metadata_ttl(ttl: Duration) {
+    *TTL.lock().unwrap_or_else(|e| e.into_inner()) = Some(ttl);
+}
+
+// set_metadata_ttl, else METADATA_TTL_SECS, else 5 minutes
+pub fn metadata_ttl() -> Duration {
+    if let Some(ttl) = *TTL.lock().unwrap_or_else(|e| e.into_inner()) {
+        return ttl;
+    }
+    env::var("METADATA_TTL_SECS").ok().and_then(|secs| secs.trim().parse().ok()).map_or(DEFAULT_TTL, Duration::from_secs)
+}
+
+const DEFAULT_TTL: Duration = Duration::from_secs(300);
+
+fn cached(spreadsheet_id: &str) -> Option<Arc<SpreadsheetMetadata>> {
+    let cache = cache();
+    let entry = cache.get(spreadsheet_id)?;
+    (entry.fetched.elapsed() < metadata_ttl()).then(|| entry.metadata.clone())
+}
+
+// Function to fetch tab titles and ids (spreadsheets.get), bypassing the cache
+pub async fn fetch_metadata(
+    access_token: &SecretString,
+    spreadsheet_id: &str,
+) -> Result<SpreadsheetMetadata, SheetsError> {
+    let url = format!(
+        "https://sheets.googleapis.com/v4/spreadsheets/{}?fields=properties.title,sheets.properties(sheetId,title,index,gridProperties)",
+        spreadsheet_id
+    );
+
+    let response = api::v4::send(access_token, Method::GET, &url, None).await?;
+
+    let sheets = response["sheets"]
+        .as_array()
+        .map(|sheets| {
+            sheets
+                .iter()
+                .filter_map(|s| serde_json::from_value(s["properties"].clone()).ok())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(SpreadsheetMetadata {
+        spreadsheet_id: spreadsheet_id.to_string(),
+        title: response["properties"]["title"].as_str().unwrap_or_default().to_string(),
+        sheets,
+    })
+}
+
+// Cached metadata for the configured spreadsheet (SHEET_ID)
+pub async fn spreadsheet_metadata(
+    access_token: &SecretString,
+) -> Result<Arc<SpreadsheetMetadata>, SheetsError> {
+    cached_metadata(access_token, &config::sheet_id()?).await
+}
+
+pub async fn cached_metadata(
+    access_token: &SecretString,
+    spreadsheet_id: &str,
+) -> Result<Arc<SpreadsheetMetadata>, SheetsError> {
+    if let Some(metadata) = cached(spreadsheet_id) {
+        return Ok(metadata);
+    }
+    let lock = fetch_lock(spreadsheet_id);
+    let _fetching = lock.lock().await;
+    // Someone else may have fetched it while we waited
+    if let Some(metadata) = cached(spreadsheet_id) {
+        return Ok(metadata);
+    }
+    // Then the on-disk cache shared between CLI runs
+    let metadata = match cache_file::load_metadata(spreadsheet_id) {
+        Some(metadata) => Arc::new(metadata),
+        None => {
+            let metadata = fetch_metadata(access_token, spreadsheet_id).await?;
+            cache_file::store_metadata(&metadata);
+            Arc::new(metadata)
+        }
+    };
+    cache().insert(spreadsheet_id.to_string(), Cached { fetched: Instant::now(), metadata: metadata.clone() });
+    Ok(metadata)
+}
+
+// Drop cached metadata, e.g. after creating or renaming tabs
+pub fn invalidate_metadata() {
+    cache().clear();
+    cache_file::clear_metadata();
+}
+
+// Run `operation`, and if it fails because a tab it was given doesn't exist
+// (renamed or deleted since the metadata was cached) run it once more
+// against freshly fetched metadata
+pub async fn retry_with_fresh_metadata<T, F, Fut>(mut operation: F) -> Result<T, SheetsError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, SheetsError>>,
+{
+    match operation().await {
+        Err(e) if e.is_unknown_sheet() => {
+            tracing::debug!("retrying with fresh metadata after: {}", e);
+            invalidate_metadata();
+            operation().await
+        }
+        result => result,
+    }
+}
+
+pub async fn resolve_gid(access_token: &SecretString, name: &str) -> Result<u64, SheetsError> {
+    spreadsheet_metadata(access_token)
+        .await?
+        .resolve_gid(name)
+        .ok_or_else(|| SheetsError::NotFound(format!("no tab named '{}'", name)))
+}
+
+// gid of a tab given by title or gid. A title missing from the cached
+// metadata is looked up once more, in case the tab was created since.
+pub async fn resolve_sheet(access_token: &SecretString, sheet: &SheetRef) -> Result<u64, SheetsError> {
+    let title = match sheet {
+        SheetRef::Gid(gid) => return Ok(*gid),
+        SheetRef::Title(title) => title,
+    };
+    if let Some(gid) = spreadsheet_metadata(access_token).await?.resolve_gid(title) {
+        return Ok(gid);
+    }
+    invalidate_metadata();
+    resolve_gid(access_token, title).await
+}
+
+pub async fn resolve_name(access_token: &SecretString, gid: u64) -> Result<String, SheetsError> {
+    spreadsheet_metadata(access_token)
+        .await?
+        .resolve_name(gid)
+        .map(str::to_string)
+        .ok_or_else(|| SheetsError::NotFound(format!("no tab with gid {}", gid)))
+}
+
+// Tab name selected by a `#gid=` URL, if the ID carried one
+pub async fn sheet_name_for(
+    access_token: &SecretString,
+    spreadsheet: &SpreadsheetId,
+) -> Result<Option<String>, SheetsError> {
+    let Some(gid) = spreadsheet.gid() else {
+        return Ok(None);
+    };
+    let metadata = cached_metadata(access_token, spreadsheet.as_str()).await?;
+    match metadata.resolve_name(gid) {
+        Some(name) => Ok(Some(name.to_string())),
+        None => Err(SheetsError::NotFound(format!("no tab with gid {}", gid))),
+    }
+}
@@ -0,0 +1,186 @@
+use crate::coerce::{cell_f64, cell_text};
+use crate::output::write_atomic;
+use crate::table::{interpret, ReadOptions};
+use crate::{fetch_values, SecretString, SheetsError};
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::path::Path;
+
+// Columns with more distinct values than this are treated as free text and
+// not checked for new values
+const MAX_TRACKED_VALUES: usize = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnStats {
+    pub name: String,
+    pub non_empty: usize,
+    pub values: Option<BTreeSet<String>>, // None once MAX_TRACKED_VALUES is exceeded
+    pub mean: Option<f64>,                // Cells that are plain numbers only
+}
+
+// One run's snapshot, saved as the baseline for the next run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableStats {
+    pub taken_at: DateTime<Utc>,
+    pub row_count: usize,
+    pub columns: Vec<ColumnStats>,
+}
+
+impl TableStats {
+    pub fn column(&self, name: &str) -> Option<&ColumnStats> {
+        self.columns.iter().find(|c| c.name == name)
+    }
+}
+
+// `header` plus data rows
+pub fn compute_stats(header: &[Value], rows: &[Vec<Value>]) -> TableStats {
+    let columns = header
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let mut values = Some(BTreeSet::new());
+            let (mut non_empty, mut sum, mut numbers) = (0, 0.0, 0);
+            for cell in rows.iter().filter_map(|row| row.get(i)) {
+                let text = cell_text(cell);
+                if text.trim().is_empty() {
+                    continue;
+                }
+                non_empty += 1;
+                if let Some(n) = cell_f64(cell) {
+                    sum += n;
+                    numbers += 1;
+                }
+                if let Some(set) = &mut values {
+                    set.insert(text.trim().to_string());
+                    if set.len() > MAX_TRACKED_VALUES {
+                        values = None;
+                    }
+                }
+            }
+            ColumnStats {
+                name: cell_text(name),
+                non_empty,
+                values,
+                mean: (numbers > 0 && numbers * 2 >= non_empty).then(|| sum / numbers as f64),
+            }
+        })
+        .collect();
+    TableStats { taken_at: Utc::now(), row_count: rows.len(), columns }
+}
+
+#[derive(Debug, Clone)]
+pub struct Thresholds {
+    pub max_row_drop: f64,              // 0.2 = alert when rows fall by more than 20%
+    pub max_mean_shift: f64,            // 0.5 = alert when a numeric column's mean moves by more than 50%
+    pub new_value_columns: Vec<String>, // Alert on values not seen last run, e.g. CHANNEL
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Thresholds { max_row_drop: 0.2, max_mean_shift: 0.5, new_value_columns: Vec::new() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Alert {
+    RowCountDrop { before: usize, after: usize },
+    ColumnMissing { column: String },
+    NewValues { column: String, values: Vec<String> },
+    MeanShift { column: String, before: f64, after: f64 },
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Alert::RowCountDrop { before, after } => write!(f, "row count dropped from {} to {}", before, after),
+            Alert::ColumnMissing { column } => write!(f, "column '{}' disappeared", column),
+            Alert::NewValues { column, values } => write!(f, "new values in '{}': {}", column, values.join(", ")),
+            Alert::MeanShift { column, before, after } => {
+                write!(f, "mean of '{}' moved from {:.2} to {:.2}", column, before, after)
+            }
+        }
+    }
+}
+
+pub fn compare(baseline: &TableStats, current: &TableStats, thresholds: &Thresholds) -> Vec<Alert> {
+    let mut alerts = Vec::new();
+    if baseline.row_count > 0 {
+        let drop = 1.0 - current.row_count as f64 / baseline.row_count as f64;
+        if drop > thresholds.max_row_drop {
+            alerts.push(Alert::RowCountDrop { before: baseline.row_count, after: current.row_count });
+        }
+    }
+    for before in &baseline.columns {
+        let Some(after) = current.column(&before.name) else {
+            alerts.push(Alert::ColumnMissing { column: before.name.clone() });
+            continue;
+        };
+        if let (Some(b), Some(a)) = (before.mean, after.mean) {
+            if b != 0.0 && ((a - b) / b).abs() > thresholds.max_mean_shift {
+                alerts.push(Alert::MeanShift { column: before.name.clone(), before: b, after: a });
+            }
+        }
+        if thresholds.new_value_columns.iter().any(|c| c.eq_ignore_ascii_case(&before.name)) {
+            if let (Some(seen), Some(now)) = (&before.values, &after.values) {
+                let new: Vec<String> = now.difference(seen).cloned().collect();
+                if !new.is_empty() {
+                    alerts.push(Alert::NewValues { column: before.name.clone(), values: new });
+                }
+            }
+        }
+    }
+    alerts
+}
+
+// Where alerts go
+#[derive(Debug, Clone)]
+pub enum AlertSink {
+    Stderr,
+    // Posts {"text": "..."}, which Slack and Google Chat incoming webhooks accept
+    Webhook(String),
+}
+
+impl AlertSink {
+    pub async fn send(&self, source: &str, alerts: &[Alert]) -> Result<(), SheetsError> {
+        if alerts.is_empty() {
+            return Ok(());
+        }
+        let lines: Vec<String> = alerts.iter().map(|a| format!("- {}", a)).collect();
+        let text = format!("Data alerts for {}:\n{}", source, lines.join("\n"));
+        match self {
+            AlertSink::Stderr => eprintln!("{}", text),
+            AlertSink::Webhook(url) => {
+                Client::new().post(url).json(&json!({ "text": text })).send().await?.error_for_status()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Read `range`, compare with the stats saved at `baseline_path` by the last
+// run, send any alerts and save the new stats. The first run only saves.
+pub async fn run_monitor(
+    access_token: &SecretString,
+    range: &str,
+    baseline_path: &Path,
+    thresholds: &Thresholds,
+    sinks: &[AlertSink],
+) -> Result<Vec<Alert>, SheetsError> {
+    let result = interpret(range, fetch_values(access_token, range).await?, &ReadOptions::default())?;
+    let current = compute_stats(result.header(), result.rows());
+
+    let alerts = match std::fs::read(baseline_path) {
+        Ok(bytes) => compare(&serde_json::from_slice(&bytes)?, &current, thresholds),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+    for sink in sinks {
+        sink.send(range, &alerts).await?;
+    }
+    write_atomic(baseline_path, &serde_json::to_vec_pretty(&current)?, 0)?;
+    Ok(alerts)
+}
@@ -0,0 +1,105 @@
+use crate::metadata::{self, SpreadsheetMetadata};
+use crate::rollover::quote_sheet;
+use crate::{fetch_values, SecretString, SheetsError};
+use serde_json::Value;
+
+// Rows from several same-schema tabs, read as one table
+#[derive(Debug, Clone, Default)]
+pub struct UnionTable {
+    pub header: Vec<Value>,
+    pub rows: Vec<Vec<Value>>,
+    pub tabs: Vec<(String, usize)>, // Tab name and how many data rows it contributed
+}
+
+// Tab names matching a glob like "RETURNS *" (`*` any run, `?` one character),
+// in spreadsheet order
+pub fn match_tabs(metadata: &SpreadsheetMetadata, pattern: &str) -> Vec<String> {
+    let mut sheets: Vec<_> = metadata.sheets.iter().collect();
+    sheets.sort_by_key(|s| s.index);
+    sheets
+        .into_iter()
+        .filter(|s| glob_match(pattern, &s.title))
+        .map(|s| s.title.clone())
+        .collect()
+}
+
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((star_pi, star_ti)) = star {
+            pi = star_pi + 1;
+            ti = star_ti + 1;
+            star = Some((star_pi, star_ti + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|c| *c == '*')
+}
+
+// Read each tab (names, or a single glob pattern) and concatenate the data rows.
+// Every tab must have the same header as the first one.
+pub async fn read_union(
+    access_token: &SecretString,
+    tabs: &[&str],
+) -> Result<UnionTable, SheetsError> {
+    let names: Vec<String> = if tabs.iter().any(|t| t.contains(['*', '?'])) {
+        let metadata = metadata::spreadsheet_metadata(access_token).await?;
+        let mut names = Vec::new();
+        for pattern in tabs {
+            for name in match_tabs(&metadata, pattern) {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+        names
+    } else {
+        tabs.iter().map(|t| t.to_string()).collect()
+    };
+    if names.is_empty() {
+        return Err(SheetsError::NotFound(format!("no tabs match {:?}", tabs)));
+    }
+
+    let mut table = UnionTable::default();
+    for (i, name) in names.iter().enumerate() {
+        let mut values = fetch_values(access_token, &quote_sheet(name)).await?.into_iter();
+        let header = values.next().unwrap_or_default();
+        if i == 0 {
+            table.header = header;
+        } else if !same_header(&table.header, &header) {
+            return Err(SheetsError::Invalid(format!(
+                "tab '{}' has a different header than '{}': {:?} vs {:?}",
+                name, names[0], header, table.header
+            )));
+        }
+        let rows: Vec<Vec<Value>> = values.collect();
+        table.tabs.push((name.clone(), rows.len()));
+        table.rows.extend(rows);
+    }
+    Ok(table)
+}
+
+// Headers match when the names agree ignoring surrounding whitespace and case
+fn same_header(a: &[Value], b: &[Value]) -> bool {
+    let names = |h: &[Value]| -> Vec<String> {
+        let mut names: Vec<String> = h
+            .iter()
+            .map(|v| v.as_str().unwrap_or_default().trim().to_ascii_lowercase())
+            .collect();
+        while names.last().is_some_and(String::is_empty) {
+            names.pop();
+        }
+        names
+    };
+    names(a) == names(b)
+}
@@ -0,0 +1,165 @@
+use crate::a1::column_letter;
+use crate::limits::MAX_REQUEST_BYTES;
+use crate::rollover::quote_sheet;
+use crate::{api, fetch_values, SecretString, SheetsError};
+use serde_json::{json, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseRule {
+    Upper,
+    Lower,
+}
+
+// How to clean a column. Applied in order: trim/collapse, synonyms, case.
+#[derive(Debug, Clone, Default)]
+pub struct NormalizeRules {
+    pub trim: bool,
+    pub collapse_whitespace: bool, // "DEBENHAMS   UK" -> "DEBENHAMS UK"
+    pub case: Option<CaseRule>,
+    // (variant, canonical). Variants match ignoring case and surrounding spaces.
+    pub synonyms: Vec<(String, String)>,
+}
+
+impl NormalizeRules {
+    pub fn apply(&self, value: &str) -> String {
+        let mut out = if self.collapse_whitespace {
+            value.split_whitespace().collect::<Vec<_>>().join(" ")
+        } else if self.trim {
+            value.trim().to_string()
+        } else {
+            value.to_string()
+        };
+        if let Some((_, canonical)) = self
+            .synonyms
+            .iter()
+            .find(|(variant, _)| variant.trim().eq_ignore_ascii_case(out.trim()))
+        {
+            out = canonical.clone();
+        }
+        match self.case {
+            Some(CaseRule::Upper) => out.to_uppercase(),
+            Some(CaseRule::Lower) => out.to_lowercase(),
+            None => out,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellChange {
+    pub row_number: usize, // 1-based sheet row
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NormalizeOutcome {
+    pub changes: Vec<CellChange>,
+    pub requests: usize, // batchUpdate calls made
+}
+
+// Rewrite column `column` of `sheet` (header row left alone) in place. Only
+// cells whose value changes are written, in as few values:batchUpdate calls as
+// the payload limit allows. With `dry_run` nothing is written.
+pub async fn normalize(
+    access_token: &SecretString,
+    sheet: &str,
+    column: usize,
+    rules: &NormalizeRules,
+    dry_run: bool,
+) -> Result<NormalizeOutcome, SheetsError> {
+    let letter = column_letter(column);
+    let range = format!("{}!{}2:{}", quote_sheet(sheet), letter, letter);
+    let values = fetch_values(access_token, &range).await?;
+
+    let mut outcome = NormalizeOutcome::default();
+    for (i, row) in values.iter().enumerate() {
+        let Some(Value::String(before)) = row.first() else { continue };
+        let after = rules.apply(before);
+        if after != *before {
+            outcome.changes.push(CellChange { row_number: i + 2, before: before.clone(), after });
+        }
+    }
+    if dry_run || outcome.changes.is_empty() {
+        return Ok(outcome);
+    }
+
+    // Neighbouring changed rows go in one range
+    let mut data = Vec::new();
+    let mut run: Vec<&CellChange> = Vec::new();
+    for change in &outcome.changes {
+        if run.last().is_some_and(|last| last.row_number + 1 != change.row_number) {
+            data.push(range_update(sheet, &letter, &run));
+            run.clear();
+        }
+        run.push(change);
+    }
+    data.push(range_update(sheet, &letter, &run));
+
+    let mut batch = Vec::new();
+    let mut batch_bytes = 0;
+    for entry in data {
+        let bytes = entry.to_string().len();
+        if batch_bytes + bytes > MAX_REQUEST_BYTES && !batch.is_empty() {
+            batch_update(access_token, std::mem::take(&mut batch)).await?;
+            outcome.requests += 1;
+            batch_bytes = 0;
+        }
+        batch_bytes += bytes;
+        batch.push(entry);
+    }
+    batch_update(access_token, batch).await?;
+    outcome.requests += 1;
+    println!(" Normalized {} cell(s) in {} request(s)", outcome.changes.len(), outcome.requests);
+    Ok(outcome)
+}
+
+fn range_update(sheet: &str, letter: &str, run: &[&CellChange]) -> Value {
+    let first = run[0].row_number;
+    let last = run[run.len() - 1].row_number;
+    let values: Vec<Vec<&str>> = run.iter().map(|c| vec![c.after.as_str()]).collect();
+    json!({
+        "range": format!("{}!{}{}:{}{}", quote_sheet(sheet), letter, first, letter, last),
+        "values": values,
+    })
+}
+
+async fn batch_update(access_token: &SecretString, data: Vec<Value>) -> Result<(), SheetsError> {
+    // RAW so cleaned text isn't re-interpreted as numbers or dates
+    api::v4::values_batch_update(access_token, "RAW", data).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whitespace_rules() {
+        let rules = NormalizeRules { trim: true, ..Default::default() };
+        assert_eq!(rules.apply("  DEBENHAMS   UK "), "DEBENHAMS   UK");
+        let rules = NormalizeRules { collapse_whitespace: true, ..Default::default() };
+        assert_eq!(rules.apply("  DEBENHAMS \t  UK "), "DEBENHAMS UK");
+        assert_eq!(NormalizeRules::default().apply(" as is "), " as is ");
+    }
+
+    #[test]
+    fn synonyms_then_case() {
+        let rules = NormalizeRules {
+            trim: true,
+            case: Some(CaseRule::Upper),
+            synonyms: vec![("debenhams uk".to_string(), "Debenhams".to_string())],
+            ..Default::default()
+        };
+        assert_eq!(rules.apply(" Debenhams UK "), "DEBENHAMS");
+        assert_eq!(rules.apply("asos"), "ASOS");
+        let lower = NormalizeRules { case: Some(CaseRule::Lower), ..rules };
+        assert_eq!(lower.apply("DEBENHAMS UK"), "debenhams");
+    }
+
+    #[test]
+    fn synonyms_need_the_whole_value() {
+        let rules = NormalizeRules { synonyms: vec![("UK".to_string(), "United Kingdom".to_string())], ..Default::default() };
+        assert_eq!(rules.apply(" uk"), "United Kingdom");
+        assert_eq!(rules.apply("UK Ltd"), "UK Ltd");
+    }
+}
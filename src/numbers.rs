@@ -0,0 +1,190 @@
+#[cfg(feature = "decimal")]
+use rust_decimal::Decimal;
+#[cfg(feature = "decimal")]
+use std::str::FromStr;
+
+// Which character separates the fractional part
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumberLocale {
+    // Guess per cell: the last of '.'/',' wins when both appear
+    #[default]
+    Auto,
+    // 1,234.56
+    Dot,
+    // 1.234,56
+    Comma,
+}
+
+impl NumberLocale {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "" | "auto" => Some(NumberLocale::Auto),
+            "dot" | "en" | "uk" | "us" => Some(NumberLocale::Dot),
+            "comma" | "eu" | "de" | "fr" => Some(NumberLocale::Comma),
+            _ => None,
+        }
+    }
+}
+
+const CURRENCY_SYMBOLS: &[char] = &['£', '$', '€', '¥', '₹', '¢'];
+const CURRENCY_CODES: &[&str] = &["GBP", "USD", "EUR", "JPY", "INR", "CHF", "AUD", "CAD"];
+
+// Reduce "£1,234.56", "1.234,56 €", "(45.00)", "-£3" to a plain "-1234.56"
+// string that f64/Decimal can parse. None if it isn't a number.
+pub fn normalize_number(text: &str, locale: NumberLocale) -> Option<String> {
+    let mut s = text.trim().replace(['\u{a0}', '\u{202f}', ' ', '\''], "");
+    let mut negative = false;
+    if let Some(inner) = s.strip_prefix('(').and_then(|s| s.strip_suffix(')')) {
+        negative = true;
+        s = inner.to_string();
+    }
+    for code in CURRENCY_CODES {
+        if let Some(rest) = s.strip_prefix(code) {
+            s = rest.to_string();
+        } else if let Some(rest) = s.strip_suffix(code) {
+            s = rest.to_string();
+        }
+    }
+    // Sign may sit on either side of the symbol: -£3, £-3, 3-
+    let mut stripped: String = s.chars().filter(|c| !CURRENCY_SYMBOLS.contains(c)).collect();
+    if let Some(rest) = stripped.strip_prefix('-') {
+        negative = !negative;
+        stripped = rest.to_string();
+    } else if let Some(rest) = stripped.strip_suffix('-') {
+        negative = !negative;
+        stripped = rest.to_string();
+    } else if let Some(rest) = stripped.strip_prefix('+') {
+        stripped = rest.to_string();
+    }
+    if stripped.is_empty() || !stripped.chars().all(|c| c.is_ascii_digit() || c == '.' || c == ',') {
+        return None;
+    }
+
+    let decimal = match locale {
+        NumberLocale::Dot => '.',
+        NumberLocale::Comma => ',',
+        NumberLocale::Auto => guess_decimal_separator(&stripped),
+    };
+    let group = if decimal == '.' { ',' } else { '.' };
+    if stripped.matches(decimal).count() > 1 {
+        return None;
+    }
+    let plain: String = stripped
+        .chars()
+        .filter(|c| *c != group)
+        .map(|c| if c == decimal { '.' } else { c })
+        .collect();
+    if plain == "." || plain.is_empty() {
+        return None;
+    }
+    Some(if negative { format!("-{}", plain) } else { plain })
+}
+
+fn guess_decimal_separator(s: &str) -> char {
+    match (s.rfind('.'), s.rfind(',')) {
+        (Some(dot), Some(comma)) => if dot > comma { '.' } else { ',' },
+        // A lone comma followed by exactly 3 digits is a thousands separator (1,234)
+        (None, Some(comma)) if s.matches(',').count() == 1 && s.len() - comma - 1 != 3 => ',',
+        // Repeated dots can only be grouping (1.234.567)
+        (Some(_), None) if s.matches('.').count() > 1 => ',',
+        _ => '.',
+    }
+}
+
+pub fn parse_number(text: &str, locale: NumberLocale) -> Option<f64> {
+    normalize_number(text, locale)?.parse().ok()
+}
+
+#[cfg(feature = "decimal")]
+pub fn parse_decimal(text: &str, locale: NumberLocale) -> Option<Decimal> {
+    Decimal::from_str(&normalize_number(text, locale)?).ok()
+}
+
+// Money and other exact quantities: Decimal with the `decimal` feature, f64 otherwise.
+// Sums and comparisons over currency columns go through this type.
+#[cfg(feature = "decimal")]
+pub type Amount = Decimal;
+#[cfg(not(feature = "decimal"))]
+pub type Amount = f64;
+
+#[cfg(feature = "decimal")]
+pub fn parse_amount(text: &str, locale: NumberLocale) -> Option<Amount> {
+    parse_decimal(text, locale)
+}
+
+#[cfg(not(feature = "decimal"))]
+pub fn parse_amount(text: &str, locale: NumberLocale) -> Option<Amount> {
+    parse_number(text, locale)
+}
+
+pub fn amount_is_zero(amount: Amount) -> bool {
+    amount == Amount::default()
+}
+
+pub fn amount_to_f64(amount: Amount) -> f64 {
+    amount.to_string().parse().unwrap_or(f64::NAN)
+}
+
+#[cfg(feature = "decimal")]
+pub fn amount_from_f64(value: f64) -> Option<Amount> {
+    Decimal::from_str(&value.to_string()).ok()
+}
+
+#[cfg(not(feature = "decimal"))]
+pub fn amount_from_f64(value: f64) -> Option<Amount> {
+    value.is_finite().then_some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auto(text: &str) -> Option<String> {
+        normalize_number(text, NumberLocale::Auto)
+    }
+
+    #[test]
+    fn currency_and_signs() {
+        assert_eq!(auto("£1,234.56").as_deref(), Some("1234.56"));
+        assert_eq!(auto("1.234,56 €").as_deref(), Some("1234.56"));
+        assert_eq!(auto("(45.00)").as_deref(), Some("-45.00"));
+        assert_eq!(auto("-£3").as_deref(), Some("-3"));
+        assert_eq!(auto("£-3").as_deref(), Some("-3"));
+        assert_eq!(auto("3-").as_deref(), Some("-3"));
+        assert_eq!(auto("USD 12.50").as_deref(), Some("12.50"));
+        assert_eq!(auto("+7").as_deref(), Some("7"));
+    }
+
+    #[test]
+    fn auto_guesses_the_separator() {
+        assert_eq!(auto("1,234").as_deref(), Some("1234"));
+        assert_eq!(auto("1,5").as_deref(), Some("1.5"));
+        assert_eq!(auto("1.234.567").as_deref(), Some("1234567"));
+        assert_eq!(auto("1 234 567,89").as_deref(), Some("1234567.89"));
+    }
+
+    #[test]
+    fn fixed_locales() {
+        assert_eq!(normalize_number("1,234", NumberLocale::Comma).as_deref(), Some("1.234"));
+        assert_eq!(normalize_number("1.234", NumberLocale::Comma).as_deref(), Some("1234"));
+        assert_eq!(normalize_number("1,234.5", NumberLocale::Dot).as_deref(), Some("1234.5"));
+        assert_eq!(normalize_number("1.2.3", NumberLocale::Dot), None);
+    }
+
+    #[test]
+    fn not_numbers() {
+        for text in ["", "£", ".", "-", "12abc", "N/A", "1e5"] {
+            assert_eq!(auto(text), None, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn parses_amounts() {
+        assert_eq!(parse_number("€ 2,50", NumberLocale::Auto), Some(2.5));
+        assert_eq!(NumberLocale::parse("DE"), Some(NumberLocale::Comma));
+        assert_eq!(NumberLocale::parse("klingon"), None);
+        let amount = parse_amount("(1,000.10)", NumberLocale::Auto).unwrap();
+        assert_eq!(amount_to_f64(amount), -1000.1);
+        assert!(amount_is_zero(parse_amount("0.00", NumberLocale::Auto).unwrap()));
+    }
+}
@@ -0,0 +1,69 @@
+use crate::coerce::{cell_f64, cell_text};
+use serde_json::Value;
+use std::cmp::Ordering;
+
+// Exports keep the sheet's row order unless a sort is given, and JSON object
+// keys come out sorted (serde_json without preserve_order), so two runs over
+// the same data produce byte-identical files.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortKey {
+    pub column: String,
+    pub descending: bool,
+}
+
+// "RETURN DATE:desc, ORDER ID" -> sort by date newest first, then order ID
+pub fn parse_sort(spec: &str) -> Result<Vec<SortKey>, String> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let (column, direction) = match part.rsplit_once(':') {
+                Some((column, direction)) => (column.trim(), direction.trim().to_ascii_lowercase()),
+                None => (part, "asc".to_string()),
+            };
+            let descending = match direction.as_str() {
+                "asc" => false,
+                "desc" => true,
+                other => return Err(format!("sort direction must be asc or desc, got '{}'", other)),
+            };
+            Ok(SortKey { column: column.to_string(), descending })
+        })
+        .collect()
+}
+
+// Numbers compare numerically and before text; text compares as is
+pub fn compare_cells(a: Option<&Value>, b: Option<&Value>) -> Ordering {
+    let (a, b) = (a.unwrap_or(&Value::Null), b.unwrap_or(&Value::Null));
+    match (cell_f64(a), cell_f64(b)) {
+        (Some(x), Some(y)) => x.total_cmp(&y),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => cell_text(a).cmp(&cell_text(b)),
+    }
+}
+
+// Stable, so rows that tie keep their sheet order
+pub fn sort_rows(header: &[Value], rows: &mut [Vec<Value>], keys: &[SortKey]) -> Result<(), String> {
+    let indices: Vec<(usize, bool)> = keys
+        .iter()
+        .map(|key| {
+            header
+                .iter()
+                .position(|h| h.as_str().is_some_and(|h| h.trim().eq_ignore_ascii_case(key.column.trim())))
+                .map(|i| (i, key.descending))
+                .ok_or_else(|| format!("can't sort by '{}': no such column", key.column))
+        })
+        .collect::<Result<_, _>>()?;
+    rows.sort_by(|a, b| {
+        indices
+            .iter()
+            .map(|(i, descending)| {
+                let ordering = compare_cells(a.get(*i), b.get(*i));
+                if *descending { ordering.reverse() } else { ordering }
+            })
+            .find(|o| o.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+    Ok(())
+}
@@ -0,0 +1,153 @@
+use crate::export::OutputFormat;
+use crate::ordering::{parse_sort, SortKey};
+use std::env;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+// Where exports are written and how many previous versions are kept
+#[derive(Debug, Clone)]
+pub struct OutputConfig {
+    pub path: PathBuf,
+    pub keep_previous: usize, // 0 = just overwrite; N = keep output.json.1 .. output.json.N
+    pub provenance: bool,     // Embed a provenance block (source, filter, version, time)
+    pub sort: Vec<SortKey>,   // Empty = sheet order
+    pub format: OutputFormat,
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        OutputConfig {
+            path: PathBuf::from("output.json"),
+            keep_previous: 0,
+            provenance: false,
+            sort: Vec::new(),
+            format: OutputFormat::Json,
+        }
+    }
+}
+
+impl OutputConfig {
+    // OUTPUT_PATH (default output.json), OUTPUT_KEEP (default 0), OUTPUT_PROVENANCE (1/true),
+    // OUTPUT_SORT ("COLUMN[:desc], ...") and OUTPUT_FORMAT (json/csv, default from
+    // the path's extension)
+    pub fn from_env() -> Result<Self, String> {
+        let mut config = OutputConfig::default();
+        if let Some(path) = env::var_os("OUTPUT_PATH") {
+            config.path = PathBuf::from(path);
+        }
+        if let Ok(keep) = env::var("OUTPUT_KEEP") {
+            config.keep_previous = keep
+                .trim()
+                .parse()
+                .map_err(|_| format!("OUTPUT_KEEP must be a whole number, got '{}'", keep))?;
+        }
+        if let Ok(flag) = env::var("OUTPUT_PROVENANCE") {
+            config.provenance = matches!(flag.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes");
+        }
+        if let Ok(spec) = env::var("OUTPUT_SORT") {
+            config.sort = parse_sort(&spec)?;
+        }
+        config.format = match env::var("OUTPUT_FORMAT") {
+            Ok(name) => OutputFormat::parse(&name).ok_or_else(|| format!("OUTPUT_FORMAT must be json or csv, got '{}'", name))?,
+            Err(_) => OutputFormat::for_path(&config.path),
+        };
+        Ok(config)
+    }
+
+    // Save to `path` instead, e.g. from --output. The format follows its
+    // extension unless OUTPUT_FORMAT names one.
+    pub fn set_path(&mut self, path: PathBuf) {
+        if env::var_os("OUTPUT_FORMAT").is_none() {
+            self.format = OutputFormat::for_path(&path);
+        }
+        self.path = path;
+    }
+
+    pub fn write(&self, contents: &[u8]) -> io::Result<()> {
+        write_atomic(&self.path, contents, self.keep_previous)
+    }
+}
+
+// Write to a temp file next to `path`, fsync it, then rename over the target,
+// so readers see either the old file or the complete new one
+pub fn write_atomic(path: &Path, contents: &[u8], keep_previous: usize) -> io::Result<()> {
+    write_atomic_mode(path, contents, keep_previous, false)
+}
+
+// Same, but readable only by the current user (0600 on Unix) from the moment
+// the temp file is created. For files holding keys or tokens.
+pub fn write_private(path: &Path, contents: &[u8]) -> io::Result<()> {
+    write_atomic_mode(path, contents, 0, true)
+}
+
+fn create_file(path: &Path, private: bool) -> io::Result<File> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    if private {
+        use std::os::unix::fs::OpenOptionsExt;
+        let _ = fs::remove_file(path); // A leftover temp file would keep its old mode
+        options.mode(0o600);
+    }
+    #[cfg(not(unix))]
+    let _ = private;
+    options.open(path)
+}
+
+fn write_atomic_mode(path: &Path, contents: &[u8], keep_previous: usize, private: bool) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    fs::create_dir_all(&dir)?;
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "output path has no file name"))?;
+    let mut tmp_name = OsString::from(".");
+    tmp_name.push(file_name);
+    tmp_name.push(format!(".tmp-{}", std::process::id()));
+    let tmp_path = dir.join(tmp_name);
+
+    let result = (|| {
+        let mut file = create_file(&tmp_path, private)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        drop(file);
+        if keep_previous > 0 {
+            rotate(path, keep_previous)?;
+        }
+        fs::rename(&tmp_path, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+fn numbered(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(format!(".{}", n));
+    PathBuf::from(name)
+}
+
+// output.json -> output.json.1 -> output.json.2 ..., dropping the oldest
+fn rotate(path: &Path, keep: usize) -> io::Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let oldest = numbered(path, keep);
+    if oldest.exists() {
+        fs::remove_file(&oldest)?;
+    }
+    for n in (1..keep).rev() {
+        let from = numbered(path, n);
+        if from.exists() {
+            fs::rename(&from, numbered(path, n + 1))?;
+        }
+    }
+    // Copy rather than move so `path` never disappears before the new file lands
+    fs::copy(path, numbered(path, 1)).map(|_| ())
+}
@@ -0,0 +1,132 @@
+use crate::a1::column_letter;
+use crate::filter::Filter;
+use crate::metadata::spreadsheet_metadata;
+use crate::rollover::{quote_sheet, tab_family};
+use crate::schema::Schema;
+use crate::spill::SpillBuffer;
+use crate::{api, fetch_values, SecretString, SheetsError};
+use futures::future;
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+use serde_json::Value;
+
+pub const DEFAULT_WINDOW_ROWS: usize = 10_000;
+pub const DEFAULT_PREFETCH: usize = 2;
+
+// Reads a tab in fixed-size row windows below the header. Up to `prefetch`
+// windows are requested concurrently; they are still yielded in sheet order,
+// and no more are started until the consumer takes one, so a slow consumer
+// holds at most `prefetch` windows in memory. A tab that rolled over (see
+// rollover) is read as one table: "Log", then "Log (2)", "Log (3)", each
+// below its own header row.
+#[derive(Debug, Clone)]
+pub struct PagedReader {
+    pub sheet: String,
+    pub window_rows: usize,
+    pub prefetch: usize,            // 1 = strictly sequential
+    pub last_column: Option<usize>, // Read A..=last_column; None = every column
+    pub max_memory: Option<usize>,  // Bytes read_all keeps in memory before spilling to disk
+}
+
+impl PagedReader {
+    pub fn new(sheet: &str) -> Self {
+        PagedReader {
+            sheet: sheet.to_string(),
+            window_rows: DEFAULT_WINDOW_ROWS,
+            prefetch: DEFAULT_PREFETCH,
+            last_column: None,
+            max_memory: None,
+        }
+    }
+
+    pub fn window_rows(mut self, rows: usize) -> Self {
+        self.window_rows = rows.max(1);
+        self
+    }
+
+    pub fn prefetch(mut self, depth: usize) -> Self {
+        self.prefetch = depth.max(1);
+        self
+    }
+
+    pub fn max_memory(mut self, bytes: usize) -> Self {
+        self.max_memory = Some(bytes);
+        self
+    }
+
+    fn window_range(&self, sheet: &str, start: usize, end: usize, width: usize) -> String {
+        let last = column_letter(self.last_column.unwrap_or(width.saturating_sub(1)));
+        format!("{}!A{}:{}{}", quote_sheet(sheet), start, last, end)
+    }
+
+    pub async fn header(&self, access_token: &SecretString) -> Result<Vec<Value>, SheetsError> {
+        let range = format!("{}!1:1", quote_sheet(&self.sheet));
+        Ok(fetch_values(access_token, &range).await?.into_iter().next().unwrap_or_default())
+    }
+
+    // Windows of data rows (header excluded), in order, across the tab's
+    // rollover family. Grid sizes decide how many windows there are, so
+    // trailing blank rows cost requests.
+    pub async fn windows<'a>(
+        &'a self,
+        access_token: &'a SecretString,
+    ) -> Result<impl Stream<Item = Result<Vec<Vec<Value>>, SheetsError>> + 'a, SheetsError> {
+        let metadata = spreadsheet_metadata(access_token).await?;
+        let family = tab_family(&metadata, &self.sheet);
+        if family.is_empty() {
+            return Err(SheetsError::NotFound(format!("no tab named '{}'", self.sheet)));
+        }
+        let mut windows = Vec::new();
+        for tab in family {
+            let grid = metadata.sheet(&tab).and_then(|sheet| sheet.grid_properties.clone());
+            let (row_count, width) = grid.map_or((0, 1), |g| (g.row_count as usize, g.column_count as usize));
+            for start in (2..=row_count).step_by(self.window_rows) {
+                let end = (start + self.window_rows - 1).min(row_count);
+                windows.push(self.window_range(&tab, start, end, width));
+            }
+        }
+
+        let windows = stream::iter(windows).map(move |range| {
+            // Through api::v4 so throttled windows are retried rather than failing the scan
+            async move { api::v4::get_values(access_token, &range).await }
+        });
+        Ok(windows.buffered(self.prefetch))
+    }
+
+    // The windows flattened into single data rows, for consumers that filter
+    // or aggregate as they go
+    pub async fn rows<'a>(
+        &'a self,
+        access_token: &'a SecretString,
+    ) -> Result<impl Stream<Item = Result<Vec<Value>, SheetsError>> + 'a, SheetsError> {
+        let windows = self.windows(access_token).await?;
+        Ok(windows.map_ok(|rows| stream::iter(rows.into_iter().map(Ok))).try_flatten())
+    }
+
+    // Data rows matching `filter`, whose column names resolve against the
+    // header row and whose numbers read in SHEET_SCHEMA's locales; the rest
+    // are dropped window by window
+    pub async fn filtered<'a>(
+        &'a self,
+        access_token: &'a SecretString,
+        filter: &'a Filter,
+    ) -> Result<impl Stream<Item = Result<Vec<Value>, SheetsError>> + 'a, SheetsError> {
+        let header = self.header(access_token).await?;
+        let schema = Schema::from_env().map_err(SheetsError::Invalid)?;
+        let compiled = filter.compile_with(&header, &schema).map_err(SheetsError::Invalid)?;
+        let rows = self.rows(access_token).await?;
+        Ok(rows.try_filter(move |row| future::ready(compiled.matches(row))))
+    }
+
+    // Every data row, held in memory up to `max_memory` and in a temp file beyond it
+    pub async fn read_all(&self, access_token: &SecretString) -> Result<SpillBuffer, SheetsError> {
+        let mut buffer = SpillBuffer::new(self.max_memory.unwrap_or(usize::MAX));
+        let windows = self.windows(access_token).await?;
+        futures::pin_mut!(windows);
+        while let Some(window) = windows.next().await {
+            for row in window? {
+                buffer.push(row)?;
+            }
+        }
+        Ok(buffer)
+    }
+}
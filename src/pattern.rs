@@ -0,0 +1,275 @@
+use std::fmt;
+
+// A small backtracking regex for row filters, so matching doesn't pull in a
+// regex engine. Supports literals, `.`, `^`, `$`, `[a-z]`/`[^...]` classes,
+// `\d \w \s` (and their negations), groups with `|`, the quantifiers
+// `* + ? {n} {n,} {n,m}` (a trailing lazy `?` is accepted and changes nothing
+// for a yes/no match) and a leading `(?i)` for case-insensitive matching.
+// `is_match` looks for the pattern anywhere in the text, like Regex::is_match.
+
+#[derive(Debug, Clone)]
+enum Node {
+    Char(char),
+    Any,
+    Class { ranges: Vec<(char, char)>, negated: bool },
+    Start,
+    End,
+    Group(Vec<Vec<Node>>),
+    Repeat { node: Box<Node>, min: usize, max: Option<usize> },
+}
+
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    source: String,
+    alternatives: Vec<Vec<Node>>,
+    ignore_case: bool,
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl Pattern {
+    pub fn new(source: &str) -> Result<Self, String> {
+        let (ignore_case, body) = match source.strip_prefix("(?i)") {
+            Some(rest) => (true, rest),
+            None => (false, source),
+        };
+        let mut parser = Parser { chars: body.chars().collect(), pos: 0, ignore_case };
+        let alternatives = parser.alternatives()?;
+        if parser.pos < parser.chars.len() {
+            return Err(format!("invalid pattern '{}': unmatched ')'", source));
+        }
+        Ok(Pattern { source: source.to_string(), alternatives, ignore_case })
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        let text: Vec<char> =
+            if self.ignore_case { text.chars().flat_map(char::to_lowercase).collect() } else { text.chars().collect() };
+        let group = [Node::Group(self.alternatives.clone())];
+        (0..=text.len()).any(|start| match_seq(&group, &text, start, &mut |_| true))
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    ignore_case: bool,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += 1;
+        c
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("invalid pattern '{}': {}", self.chars.iter().collect::<String>(), message)
+    }
+
+    fn literal(&self, c: char) -> char {
+        if self.ignore_case { c.to_lowercase().next().unwrap_or(c) } else { c }
+    }
+
+    fn alternatives(&mut self) -> Result<Vec<Vec<Node>>, String> {
+        let mut alternatives = vec![self.sequence()?];
+        while self.peek() == Some('|') {
+            self.pos += 1;
+            alternatives.push(self.sequence()?);
+        }
+        Ok(alternatives)
+    }
+
+    fn sequence(&mut self) -> Result<Vec<Node>, String> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.atom()?;
+            nodes.push(self.quantified(atom)?);
+        }
+        Ok(nodes)
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        match self.next() {
+            Some('.') => Ok(Node::Any),
+            Some('^') => Ok(Node::Start),
+            Some('$') => Ok(Node::End),
+            Some('(') => {
+                // Non-capturing groups are the same thing here
+                if self.chars[self.pos..].starts_with(&['?', ':']) {
+                    self.pos += 2;
+                }
+                let alternatives = self.alternatives()?;
+                match self.next() {
+                    Some(')') => Ok(Node::Group(alternatives)),
+                    _ => Err(self.error("unclosed '('")),
+                }
+            }
+            Some('[') => self.class(),
+            Some('\\') => self.escape(),
+            Some(c @ ('*' | '+' | '?' | '{')) => Err(self.error(&format!("'{}' has nothing to repeat", c))),
+            Some(c) => Ok(Node::Char(self.literal(c))),
+            None => Err(self.error("unexpected end")),
+        }
+    }
+
+    fn escape(&mut self) -> Result<Node, String> {
+        let class = |ranges: &[(char, char)], negated| Node::Class { ranges: ranges.to_vec(), negated };
+        const DIGIT: &[(char, char)] = &[('0', '9')];
+        const WORD: &[(char, char)] = &[('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')];
+        const SPACE: &[(char, char)] = &[(' ', ' '), ('\t', '\r')];
+        match self.next() {
+            Some('d') => Ok(class(DIGIT, false)),
+            Some('D') => Ok(class(DIGIT, true)),
+            Some('w') => Ok(class(WORD, false)),
+            Some('W') => Ok(class(WORD, true)),
+            Some('s') => Ok(class(SPACE, false)),
+            Some('S') => Ok(class(SPACE, true)),
+            Some('t') => Ok(Node::Char('\t')),
+            Some('n') => Ok(Node::Char('\n')),
+            Some(c) => Ok(Node::Char(self.literal(c))),
+            None => Err(self.error("trailing '\\'")),
+        }
+    }
+
+    fn class(&mut self) -> Result<Node, String> {
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.pos += 1;
+        }
+        let mut ranges = Vec::new();
+        let mut first = true;
+        loop {
+            let c = match self.next() {
+                None => return Err(self.error("unclosed '['")),
+                Some(']') if !first => break,
+                Some('\\') => match self.next() {
+                    Some('d') => {
+                        ranges.push(('0', '9'));
+                        continue;
+                    }
+                    Some('t') => '\t',
+                    Some('n') => '\n',
+                    Some(c) => c,
+                    None => return Err(self.error("unclosed '['")),
+                },
+                Some(c) => c,
+            };
+            first = false;
+            let end = if self.peek() == Some('-') && self.chars.get(self.pos + 1).is_some_and(|&c| c != ']') {
+                self.pos += 1;
+                self.next().unwrap_or(c)
+            } else {
+                c
+            };
+            if end < c {
+                return Err(self.error(&format!("range {}-{} is out of order", c, end)));
+            }
+            ranges.push((c, end));
+        }
+        // Case-insensitive classes get both cases of every letter range
+        if self.ignore_case {
+            let lowered: Vec<(char, char)> = ranges
+                .iter()
+                .filter(|(a, b)| a.is_ascii_uppercase() && b.is_ascii_uppercase())
+                .map(|(a, b)| (a.to_ascii_lowercase(), b.to_ascii_lowercase()))
+                .collect();
+            ranges.extend(lowered);
+        }
+        Ok(Node::Class { ranges, negated })
+    }
+
+    fn quantified(&mut self, atom: Node) -> Result<Node, String> {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => return self.counted(atom),
+            _ => return Ok(atom),
+        };
+        self.pos += 1;
+        if self.peek() == Some('?') {
+            self.pos += 1;
+        }
+        Ok(Node::Repeat { node: Box::new(atom), min, max })
+    }
+
+    fn counted(&mut self, atom: Node) -> Result<Node, String> {
+        let close = self.chars[self.pos..]
+            .iter()
+            .position(|&c| c == '}')
+            .ok_or_else(|| self.error("unclosed '{'"))?;
+        let spec: String = self.chars[self.pos + 1..self.pos + close].iter().collect();
+        let number = |s: &str| s.trim().parse::<usize>().map_err(|_| self.error(&format!("bad repeat count '{{{}}}'", spec)));
+        let (min, max) = match spec.split_once(',') {
+            Some((min, "")) => (number(min)?, None),
+            Some((min, max)) => (number(min)?, Some(number(max)?)),
+            None => (number(&spec)?, Some(number(&spec)?)),
+        };
+        if max.is_some_and(|max| max < min) {
+            return Err(self.error(&format!("bad repeat count '{{{}}}'", spec)));
+        }
+        self.pos += close + 1;
+        if self.peek() == Some('?') {
+            self.pos += 1;
+        }
+        Ok(Node::Repeat { node: Box::new(atom), min, max })
+    }
+}
+
+// Continuation-passing backtracking: `k` is called with every position the
+// rest of the pattern could start from until one of them succeeds
+fn match_seq(nodes: &[Node], text: &[char], pos: usize, k: &mut dyn FnMut(usize) -> bool) -> bool {
+    let Some((node, rest)) = nodes.split_first() else { return k(pos) };
+    match node {
+        Node::Repeat { node, min, max } => match_repeat(node, *min, *max, 0, rest, text, pos, k),
+        _ => match_one(node, text, pos, &mut |next| match_seq(rest, text, next, k)),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn match_repeat(
+    node: &Node,
+    min: usize,
+    max: Option<usize>,
+    count: usize,
+    rest: &[Node],
+    text: &[char],
+    pos: usize,
+    k: &mut dyn FnMut(usize) -> bool,
+) -> bool {
+    // Greedy: one more repetition first. An empty repetition only counts
+    // towards the minimum, otherwise `(a*)*` would loop forever.
+    if max.is_none_or(|max| count < max)
+        && match_one(node, text, pos, &mut |next| {
+            (next != pos || count < min) && match_repeat(node, min, max, count + 1, rest, text, next, k)
+        })
+    {
+        return true;
+    }
+    count >= min && match_seq(rest, text, pos, k)
+}
+
+fn match_one(node: &Node, text: &[char], pos: usize, k: &mut dyn FnMut(usize) -> bool) -> bool {
+    match node {
+        Node::Char(c) => text.get(pos) == Some(c) && k(pos + 1),
+        Node::Any => pos < text.len() && text[pos] != '\n' && k(pos + 1),
+        Node::Class { ranges, negated } => {
+            text.get(pos).is_some_and(|c| ranges.iter().any(|(a, b)| (a..=b).contains(&c)) != *negated) && k(pos + 1)
+        }
+        Node::Start => pos == 0 && k(pos),
+        Node::End => pos == text.len() && k(pos),
+        Node::Group(alternatives) => alternatives.iter().any(|alternative| match_seq(alternative, text, pos, k)),
+        Node::Repeat { .. } => match_seq(std::slice::from_ref(node), text, pos, k),
+    }
+}
@@ -0,0 +1,278 @@
+use crate::redaction::{RedactAction, RedactionRule, Redactor};
+use crate::{fetch_values, SecretString, SheetsError};
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+
+// A column is flagged when at least this share of its non-empty cells match
+const DETECTION_THRESHOLD: f64 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PiiKind {
+    Email,
+    Phone,
+    Address,
+}
+
+impl PiiKind {
+    // Emails and phones stay partially readable, addresses are dropped outright
+    pub fn suggested_action(self) -> RedactAction {
+        match self {
+            PiiKind::Email | PiiKind::Phone => RedactAction::Mask,
+            PiiKind::Address => RedactAction::Drop,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ColumnPii {
+    pub index: usize,
+    pub header: String,
+    pub non_empty: usize,
+    pub emails: usize,
+    pub phones: usize,
+    pub addresses: usize,
+    pub detected: Option<PiiKind>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PiiReport {
+    pub range: String,
+    pub rows_scanned: usize,
+    pub columns: Vec<ColumnPii>,
+}
+
+impl PiiReport {
+    pub fn flagged(&self) -> impl Iterator<Item = &ColumnPii> {
+        self.columns.iter().filter(|c| c.detected.is_some())
+    }
+
+    // Redaction config covering every flagged column, ready for REDACT_COLUMNS
+    pub fn suggested_redaction(&self) -> Redactor {
+        Redactor::new(
+            self.flagged()
+                .map(|c| RedactionRule {
+                    column: c.header.clone(),
+                    action: c.detected.map(PiiKind::suggested_action).unwrap_or(RedactAction::Mask),
+                })
+                .collect(),
+        )
+    }
+}
+
+impl fmt::Display for PiiReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "PII scan of '{}' ({} rows)", self.range, self.rows_scanned)?;
+        for c in &self.columns {
+            let verdict = match c.detected {
+                Some(kind) => format!("{:?}", kind).to_uppercase(),
+                None => "-".to_string(),
+            };
+            writeln!(
+                f,
+                "  [{}] {:<24} {:<8} emails={} phones={} addresses={} (of {})",
+                c.index, c.header, verdict, c.emails, c.phones, c.addresses, c.non_empty
+            )?;
+        }
+        let suggestion = self.suggested_redaction();
+        if suggestion.is_empty() {
+            write!(f, "No PII columns detected")
+        } else {
+            write!(f, "Suggested: REDACT_COLUMNS=\"{}\"", suggestion.to_spec())
+        }
+    }
+}
+
+// Fetch `range` and classify each column
+pub async fn scan_pii(access_token: &SecretString, range: &str) -> Result<PiiReport, SheetsError> {
+    let values = fetch_values(access_token, range).await?;
+    Ok(scan_values(range, &values))
+}
+
+// First row is treated as the header
+pub fn scan_values(range: &str, values: &[Vec<Value>]) -> PiiReport {
+    let header = values.first().map(Vec::as_slice).unwrap_or(&[]);
+    let rows = values.get(1..).unwrap_or(&[]);
+    let width = values.iter().map(Vec::len).max().unwrap_or(0);
+
+    let columns = (0..width)
+        .map(|index| {
+            let mut column = ColumnPii {
+                index,
+                header: header
+                    .get(index)
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+                    .unwrap_or_else(|| format!("column {}", index + 1)),
+                non_empty: 0,
+                emails: 0,
+                phones: 0,
+                addresses: 0,
+                detected: None,
+            };
+            for text in rows.iter().filter_map(|row| row.get(index)).map(cell_text) {
+                let text = text.trim();
+                if text.is_empty() {
+                    continue;
+                }
+                column.non_empty += 1;
+                if looks_like_email(text) {
+                    column.emails += 1;
+                } else if looks_like_phone(text) {
+                    column.phones += 1;
+                } else if looks_like_address(text) {
+                    column.addresses += 1;
+                }
+            }
+            column.detected = classify(&column);
+            column
+        })
+        .collect();
+
+    PiiReport {
+        range: range.to_string(),
+        rows_scanned: rows.len(),
+        columns,
+    }
+}
+
+fn classify(column: &ColumnPii) -> Option<PiiKind> {
+    if column.non_empty == 0 {
+        return None;
+    }
+    [
+        (PiiKind::Email, column.emails),
+        (PiiKind::Phone, column.phones),
+        (PiiKind::Address, column.addresses),
+    ]
+    .into_iter()
+    .filter(|(_, hits)| *hits as f64 / column.non_empty as f64 >= DETECTION_THRESHOLD)
+    .max_by_key(|(_, hits)| *hits)
+    .map(|(kind, _)| kind)
+}
+
+fn cell_text(cell: &Value) -> String {
+    match cell {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+pub fn looks_like_email(text: &str) -> bool {
+    let Some((local, domain)) = text.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && !text.contains(char::is_whitespace)
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+}
+
+// 7-15 digits with a leading '+' or split into groups, so a bare ID or an
+// amount doesn't count; '.' isn't a separator for the same reason
+pub fn looks_like_phone(text: &str) -> bool {
+    let allowed = |c: char| c.is_ascii_digit() || " -()".contains(c);
+    let number = text.strip_prefix('+').unwrap_or(text);
+    let digits = number.chars().filter(char::is_ascii_digit).count();
+    let groups = number.split(|c: char| !c.is_ascii_digit()).filter(|g| !g.is_empty()).count();
+    number.chars().all(allowed)
+        && (7..=15).contains(&digits)
+        && (number.len() < text.len() || groups >= 2)
+        && !looks_like_date(text)
+}
+
+// "2024-05-01" or "01-05-2024", optionally followed by a time
+fn looks_like_date(text: &str) -> bool {
+    let groups: Vec<&str> = text.split(['-', ' ']).filter(|g| !g.is_empty()).take(3).collect();
+    let part = |g: &str, len: std::ops::RangeInclusive<usize>| len.contains(&g.len()) && g.chars().all(|c| c.is_ascii_digit());
+    match groups[..] {
+        [y, m, d] if part(y, 4..=4) => part(m, 1..=2) && part(d, 1..=2),
+        [d, m, y] => part(d, 1..=2) && part(m, 1..=2) && part(y, 4..=4),
+        _ => false,
+    }
+}
+
+const STREET_WORDS: &[&str] = &[
+    "street", "st", "road", "rd", "avenue", "ave", "lane", "ln", "drive", "dr", "close", "way",
+    "court", "ct", "place", "pl", "crescent", "terrace", "boulevard", "blvd", "square", "sq",
+];
+
+pub fn looks_like_address(text: &str) -> bool {
+    let words: Vec<String> = text
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|w| !w.is_empty())
+        .map(|w| w.trim_end_matches('.').to_ascii_lowercase())
+        .collect();
+    let has_number = words.iter().any(|w| w.chars().next().is_some_and(|c| c.is_ascii_digit()));
+    let has_street = words.iter().any(|w| STREET_WORDS.contains(&w.as_str()));
+    (has_number && has_street) || words.windows(2).any(|pair| is_uk_postcode(&pair[0], &pair[1]))
+}
+
+// Outward code like "sw1a"/"m1" followed by inward code like "1aa"
+fn is_uk_postcode(outward: &str, inward: &str) -> bool {
+    let out: Vec<char> = outward.chars().collect();
+    let inw: Vec<char> = inward.chars().collect();
+    (2..=4).contains(&out.len())
+        && out[0].is_ascii_alphabetic()
+        && out.iter().any(char::is_ascii_digit)
+        && out.iter().all(char::is_ascii_alphanumeric)
+        && inw.len() == 3
+        && inw[0].is_ascii_digit()
+        && inw[1].is_ascii_alphabetic()
+        && inw[2].is_ascii_alphabetic()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn emails() {
+        assert!(looks_like_email("jo@example.com"));
+        assert!(!looks_like_email("jo@localhost"));
+        assert!(!looks_like_email("@example.com"));
+        assert!(!looks_like_email("jo @example.com"));
+        assert!(!looks_like_email("jo@example."));
+    }
+
+    #[test]
+    fn phones() {
+        assert!(looks_like_phone("+447911123456"));
+        assert!(looks_like_phone("+44 7911 123456"));
+        assert!(looks_like_phone("(020) 7946-0018"));
+        assert!(looks_like_phone("555-123-4567"));
+        assert!(!looks_like_phone("2024-05-01"));
+        assert!(!looks_like_phone("01-05-2024"));
+        assert!(!looks_like_phone("2024-05-01 10:30"));
+        assert!(!looks_like_phone("1234567"));
+        assert!(!looks_like_phone("123456789012"));
+        assert!(!looks_like_phone("1234567.89"));
+        assert!(!looks_like_phone("12-34"));
+        assert!(!looks_like_phone("44+7911123456"));
+    }
+
+    #[test]
+    fn addresses() {
+        assert!(looks_like_address("221B Baker Street"));
+        assert!(looks_like_address("10 Downing St., London"));
+        assert!(looks_like_address("London SW1A 2AA"));
+        assert!(!looks_like_address("Baker Street"));
+        assert!(!looks_like_address("Order 1234"));
+    }
+
+    #[test]
+    fn flags_columns_over_the_threshold() {
+        let text = |cells: &[&str]| cells.iter().map(|c| Value::from(*c)).collect::<Vec<_>>();
+        let values = vec![
+            text(&["Email", "Phone", "Date", "Order"]),
+            text(&["a@example.com", "+44 7911 123456", "2024-05-01", "10042001"]),
+            text(&["b@example.com", "555-123-4567", "2024-05-02", "10042002"]),
+        ];
+        let report = scan_values("Sheet1", &values);
+        let detected: Vec<_> = report.columns.iter().map(|c| c.detected).collect();
+        assert_eq!(detected, [Some(PiiKind::Email), Some(PiiKind::Phone), None, None]);
+        assert_eq!(report.suggested_redaction().to_spec(), "Email=mask,Phone=mask");
+    }
+}
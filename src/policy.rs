@@ -0,0 +1,290 @@
+use crate::a1::range_sheet;
+use crate::config::{self, ConfigError};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::env;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Read,
+    Append,
+    Update,
+    Delete,
+    // Everything else batchUpdate can do: tabs, formatting, charts, ...
+    Structure,
+}
+
+impl Operation {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "read" => Some(Operation::Read),
+            "append" => Some(Operation::Append),
+            "update" | "write" => Some(Operation::Update),
+            "delete" => Some(Operation::Delete),
+            "structure" | "format" => Some(Operation::Structure),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Operation::Read => "read",
+            Operation::Append => "append",
+            Operation::Update => "update",
+            Operation::Delete => "delete",
+            Operation::Structure => "structure",
+        }
+    }
+
+    // What a batchUpdate request such as {"deleteDimension": ..} amounts to
+    fn of_request(kind: &str) -> Self {
+        match kind {
+            "appendCells" | "appendDimension" => Operation::Append,
+            "deleteDimension" | "deleteRange" | "deleteSheet" | "deleteDuplicates" => Operation::Delete,
+            "updateCells" | "repeatCell" | "findReplace" | "pasteData" | "copyPaste" | "cutPaste" | "autoFill" | "textToColumns" | "trimWhitespace"
+            | "randomizeRange" => {
+                Operation::Update
+            }
+            _ => Operation::Structure,
+        }
+    }
+}
+
+// A request the policy doesn't allow; it was never sent
+#[derive(Debug, Clone)]
+pub struct PolicyViolation {
+    pub reason: String,
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "blocked by policy: {}", self.reason)
+    }
+}
+
+impl std::error::Error for PolicyViolation {}
+
+fn violation(reason: String) -> PolicyViolation {
+    PolicyViolation { reason }
+}
+
+// Client-side guardrails checked before a request goes out. Unset parts allow
+// everything. Deleted rows are counted over the policy's lifetime (one CLI run).
+#[derive(Debug, Default)]
+pub struct Policy {
+    operations: Option<Vec<Operation>>,
+    sheets: Option<Vec<String>>, // Tab names; a trailing * matches a prefix
+    max_deleted_rows: Option<usize>,
+    deleted: AtomicUsize,
+}
+
+impl Policy {
+    pub fn unrestricted() -> Self {
+        Policy::default()
+    }
+
+    pub fn allow_operations(mut self, operations: &[Operation]) -> Self {
+        self.operations = Some(operations.to_vec());
+        self
+    }
+
+    pub fn allow_sheets(mut self, sheets: &[&str]) -> Self {
+        self.sheets = Some(sheets.iter().map(|s| s.trim().to_string()).collect());
+        self
+    }
+
+    pub fn max_deleted_rows(mut self, rows: usize) -> Self {
+        self.max_deleted_rows = Some(rows);
+        self
+    }
+
+    // POLICY_OPERATIONS="read,append", POLICY_SHEETS="RETURNS MAIN,Archive *",
+    // POLICY_MAX_DELETE_ROWS=100 (plus the env file)
+    pub fn from_env() -> Result<Self, ConfigError> {
+        config::load_dotenv()?;
+        let mut policy = Policy::default();
+        if let Ok(spec) = env::var("POLICY_OPERATIONS") {
+            let operations = split_list(&spec)
+                .map(|name| {
+                    Operation::parse(name).ok_or_else(|| ConfigError::Invalid {
+                        var: "POLICY_OPERATIONS".to_string(),
+                        reason: format!("unknown operation '{}' (read, append, update, delete, structure)", name),
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            policy.operations = Some(operations);
+        }
+        if let Ok(spec) = env::var("POLICY_SHEETS") {
+            policy.sheets = Some(split_list(&spec).map(str::to_string).collect());
+        }
+        if let Ok(limit) = env::var("POLICY_MAX_DELETE_ROWS") {
+            policy.max_deleted_rows = Some(limit.trim().parse().map_err(|_| ConfigError::Invalid {
+                var: "POLICY_MAX_DELETE_ROWS".to_string(),
+                reason: format!("'{}' is not a row count", limit),
+            })?);
+        }
+        Ok(policy)
+    }
+
+    pub fn restricts_sheets(&self) -> bool {
+        self.sheets.is_some()
+    }
+
+    pub fn allows(&self, operation: Operation) -> bool {
+        self.operations.as_ref().is_none_or(|ops| ops.contains(&operation))
+    }
+
+    pub fn check_operation(&self, operation: Operation) -> Result<(), PolicyViolation> {
+        if !self.allows(operation) {
+            return Err(violation(format!("{} is not an allowed operation", operation.name())));
+        }
+        Ok(())
+    }
+
+    pub fn check(&self, operation: Operation, sheet: Option<&str>) -> Result<(), PolicyViolation> {
+        self.check_operation(operation)?;
+        let Some(allowed) = &self.sheets else { return Ok(()) };
+        let Some(sheet) = sheet else {
+            return Err(violation(format!("{} without a tab name while tabs are restricted", operation.name())));
+        };
+        if allowed.iter().any(|pattern| sheet_matches(pattern, sheet)) {
+            Ok(())
+        } else {
+            Err(violation(format!("tab '{}' is not in the allowed tabs", sheet)))
+        }
+    }
+
+    // A1 range; a range without '!' is taken as a bare tab name, so "A1:B2"
+    // is refused when tabs are restricted
+    pub fn check_range(&self, operation: Operation, range: &str) -> Result<(), PolicyViolation> {
+        let sheet = range_sheet(range).unwrap_or_else(|| range.trim().trim_matches('\'').to_string());
+        self.check(operation, Some(&sheet))
+    }
+
+    // Count `rows` against the delete budget, refusing (and not counting) past it
+    pub fn reserve_deletes(&self, rows: usize) -> Result<(), PolicyViolation> {
+        let Some(max) = self.max_deleted_rows else {
+            self.deleted.fetch_add(rows, Ordering::Relaxed);
+            return Ok(());
+        };
+        self.deleted
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |done| (done + rows <= max).then_some(done + rows))
+            .map(|_| ())
+            .map_err(|done| violation(format!("deleting {} more rows would exceed the limit of {} ({} deleted so far)", rows, max, done)))
+    }
+
+    pub fn deleted_rows(&self) -> usize {
+        self.deleted.load(Ordering::Relaxed)
+    }
+
+    // batchUpdate requests: each request's kind decides the operation, every
+    // sheetId it mentions must be an allowed tab (`titles` maps gid -> title),
+    // and deleted rows come out of the budget
+    pub fn check_requests(&self, requests: &[Value], titles: &HashMap<u64, String>) -> Result<(), PolicyViolation> {
+        let mut rows = 0;
+        for request in requests {
+            let Some((kind, body)) = request.as_object().and_then(|o| o.iter().next()) else { continue };
+            let operation = Operation::of_request(kind);
+            let mut gids = BTreeSet::new();
+            collect_sheet_ids(body, &mut gids);
+            if kind == "addSheet" {
+                // The new tab doesn't exist yet, so there's only its title to go on
+                self.check(operation, body["properties"]["title"].as_str())?;
+                continue;
+            }
+            // A copy or rename must also land on an allowed tab name
+            let new_title = match kind.as_str() {
+                "duplicateSheet" => body["newSheetName"].as_str(),
+                "updateSheetProperties" => body["properties"]["title"].as_str(),
+                _ => None,
+            };
+            if let Some(title) = new_title {
+                self.check(operation, Some(title))?;
+            }
+            if gids.is_empty() {
+                self.check(operation, None)?;
+            }
+            for gid in gids {
+                let title = titles.get(&gid).map(String::as_str);
+                if title.is_none() && self.restricts_sheets() {
+                    return Err(violation(format!("{} refers to unknown tab id {}", kind, gid)));
+                }
+                self.check(operation, title)?;
+            }
+            rows += request_deleted_rows(kind, body);
+        }
+        if rows > 0 {
+            self.reserve_deletes(rows)?;
+        }
+        Ok(())
+    }
+}
+
+// Process-wide policy from the environment, for the free functions and the CLI
+pub fn global() -> Result<&'static Policy, ConfigError> {
+    static POLICY: OnceLock<Result<Policy, ConfigError>> = OnceLock::new();
+    POLICY.get_or_init(Policy::from_env).as_ref().map_err(Clone::clone)
+}
+
+// gid -> title from a spreadsheets.get response with sheets.properties
+pub(crate) fn titles_from(spreadsheet: &Value) -> HashMap<u64, String> {
+    spreadsheet["sheets"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|s| Some((s["properties"]["sheetId"].as_u64()?, s["properties"]["title"].as_str()?.to_string())))
+        .collect()
+}
+
+fn split_list(spec: &str) -> impl Iterator<Item = &str> {
+    spec.split(',').map(str::trim).filter(|s| !s.is_empty())
+}
+
+// Tab names are unique regardless of case, so compare them that way
+fn sheet_matches(pattern: &str, sheet: &str) -> bool {
+    let (pattern, sheet) = (pattern.to_lowercase(), sheet.to_lowercase());
+    match pattern.strip_suffix('*') {
+        Some(prefix) => sheet.starts_with(prefix),
+        None => pattern == sheet,
+    }
+}
+
+fn collect_sheet_ids(value: &Value, out: &mut BTreeSet<u64>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                match (key.as_str(), value.as_u64()) {
+                    ("sheetId" | "sourceSheetId", Some(gid)) => {
+                        out.insert(gid);
+                    }
+                    _ => collect_sheet_ids(value, out),
+                }
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|item| collect_sheet_ids(item, out)),
+        _ => {}
+    }
+}
+
+// Rows removed by deleteDimension/deleteRange requests in a batchUpdate
+pub(crate) fn deleted_rows_in(requests: &[Value]) -> usize {
+    requests
+        .iter()
+        .filter_map(|r| r.as_object()?.iter().next())
+        .map(|(kind, body)| request_deleted_rows(kind, body))
+        .sum()
+}
+
+fn request_deleted_rows(kind: &str, body: &Value) -> usize {
+    let span = |range: &Value, start: &str, end: &str| {
+        range[end].as_u64().zip(range[start].as_u64().or(Some(0))).map_or(0, |(end, start)| end.saturating_sub(start) as usize)
+    };
+    match kind {
+        "deleteDimension" if body["range"]["dimension"] == "ROWS" => span(&body["range"], "startIndex", "endIndex"),
+        "deleteRange" if body["shiftDimension"] == "ROWS" => span(&body["range"], "startRowIndex", "endRowIndex"),
+        _ => 0,
+    }
+}
@@ -0,0 +1,106 @@
+use crate::a1::{column_index, A1Range};
+use crate::cell_value::{into_cells, CellValue};
+use crate::grid::{append_cells_request, auto_fill_request, CellData, GridRange};
+use crate::{api, limits, metadata, summary, SecretString, SheetsError};
+use std::env;
+
+// Appending raw data under a table whose computed columns are formulas
+// leaves those columns blank in the new rows. These helpers append and then
+// autoFill the formula columns from the last existing row over the new rows,
+// in one batchUpdate, so the sheet is never seen half-filled.
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum FormulaColumns {
+    // Every column whose cell in the last existing row is a formula
+    #[default]
+    Detect,
+    // 0-based column indices
+    Columns(Vec<usize>),
+}
+
+impl FormulaColumns {
+    // FORMULA_COLUMNS: "detect", or column letters like "F,G"; None when unset
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let Ok(spec) = env::var("FORMULA_COLUMNS") else { return Ok(None) };
+        if spec.trim().eq_ignore_ascii_case("detect") {
+            return Ok(Some(FormulaColumns::Detect));
+        }
+        let columns = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|letters| !letters.is_empty())
+            .map(|letters| column_index(letters).ok_or_else(|| format!("FORMULA_COLUMNS: '{}' is not a column letter", letters)))
+            .collect::<Result<_, _>>()?;
+        Ok(Some(FormulaColumns::Columns(columns)))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PropagateOutcome {
+    pub appended: usize,
+    // Formula columns extended over the new rows
+    pub filled_columns: Vec<usize>,
+}
+
+// Contiguous runs of `columns` as end-exclusive ranges: [1, 2, 5] -> 1..3, 5..6
+fn column_runs(columns: &[usize]) -> Vec<std::ops::Range<usize>> {
+    let mut sorted = columns.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+    let mut runs: Vec<std::ops::Range<usize>> = Vec::new();
+    for column in sorted {
+        match runs.last_mut() {
+            Some(run) if run.end == column => run.end += 1,
+            _ => runs.push(column..column + 1),
+        }
+    }
+    runs
+}
+
+// Append `rows` to tab `sheet` and extend its formula columns over them.
+// Whatever `rows` hold in those columns is replaced by the filled formula.
+// With only a header (or nothing) above, there is no formula to copy and the
+// rows are appended as they are.
+pub async fn append_rows_with_formulas(
+    access_token: &SecretString,
+    sheet: &str,
+    rows: Vec<Vec<impl Into<CellValue>>>,
+    formula_columns: FormulaColumns,
+) -> Result<PropagateOutcome, SheetsError> {
+    let rows = into_cells(rows);
+    if rows.is_empty() {
+        return Ok(PropagateOutcome::default());
+    }
+    limits::validate_rows(&rows)?;
+    let gid = metadata::spreadsheet_metadata(access_token)
+        .await?
+        .resolve_gid(sheet)
+        .ok_or_else(|| SheetsError::NotFound(format!("no tab named '{}'", sheet)))?;
+
+    // appendCells lands right after the last row with data, which is also
+    // where the values read ends
+    let existing = api::v4::get_formulas(access_token, &A1Range::sheet(sheet).to_string()).await?;
+    let template = existing.len().checked_sub(1).filter(|&last| last > 0);
+    let columns = match (&formula_columns, template) {
+        (_, None) => Vec::new(),
+        (FormulaColumns::Columns(columns), Some(_)) => columns.clone(),
+        (FormulaColumns::Detect, Some(last)) => existing[last]
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| cell.as_str().is_some_and(|s| s.starts_with('=')))
+            .map(|(column, _)| column)
+            .collect(),
+    };
+
+    let cells: Vec<Vec<CellData>> = rows.iter().map(|row| row.iter().map(CellData::from_cell).collect()).collect();
+    let mut requests = vec![append_cells_request(gid, &cells)];
+    if let Some(last) = template {
+        for run in column_runs(&columns) {
+            requests.push(auto_fill_request(GridRange::new(gid, last..last + 1, run), rows.len()));
+        }
+    }
+    api::v4::batch_update(access_token, requests).await?;
+    summary::rows_written(rows.len());
+    println!(" Rows added: {} (formulas extended in {} column(s))", rows.len(), columns.len());
+    Ok(PropagateOutcome { appended: rows.len(), filled_columns: column_runs(&columns).into_iter().flatten().collect() })
+}
@@ -0,0 +1,39 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Serialize;
+
+// Where an export came from, so a file found later can be traced back and
+// regenerated
+#[derive(Debug, Clone, Serialize)]
+pub struct Provenance {
+    pub spreadsheet_id: String,
+    pub range: String,
+    pub filter: String, // Human-readable filter definition, "" for none
+    pub crate_version: &'static str,
+    pub generated_at: DateTime<Utc>,
+    pub row_count: usize,
+}
+
+impl Provenance {
+    pub fn new(spreadsheet_id: &str, range: &str, filter: &str, row_count: usize) -> Self {
+        Provenance {
+            spreadsheet_id: spreadsheet_id.to_string(),
+            range: range.to_string(),
+            filter: filter.to_string(),
+            crate_version: env!("CARGO_PKG_VERSION"),
+            generated_at: Utc::now(),
+            row_count,
+        }
+    }
+
+    // "key: value" lines for CSV comments or a notes section on a materialized tab
+    pub fn lines(&self) -> Vec<String> {
+        vec![
+            format!("spreadsheet: {}", self.spreadsheet_id),
+            format!("range: {}", self.range),
+            format!("filter: {}", if self.filter.is_empty() { "none" } else { &self.filter }),
+            format!("generated by: google-sheet {}", self.crate_version),
+            format!("generated at: {}", self.generated_at.to_rfc3339_opts(SecondsFormat::Secs, true)),
+            format!("rows: {}", self.row_count),
+        ]
+    }
+}
@@ -0,0 +1,176 @@
+use crate::api::v4;
+use crate::drive::{list_managed_spreadsheets, tag_spreadsheet, FILES_URL, MANAGED_BY_KEY, MANAGED_BY_VALUE};
+use crate::SecretString;
+use crate::SheetsError;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use reqwest::Method;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+pub const DEFAULT_MAX_IN_FLIGHT: usize = 4;
+
+const SPREADSHEET_MIME: &str = "application/vnd.google-apps.spreadsheet";
+// Tag set once a spreadsheet is shared, so a rerun knows its setup finished
+const PROVISIONED_KEY: &str = "provisioned";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Share {
+    pub email: String,
+    #[serde(default = "default_role")]
+    pub role: String, // reader, commenter or writer
+}
+
+fn default_role() -> String {
+    "writer".to_string()
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ManifestEntry {
+    pub key: String,           // e.g. the store code; tagged as <tag_key>=<key>
+    pub title: Option<String>, // Overrides the manifest's title
+    #[serde(default)]
+    pub share_with: Vec<Share>, // On top of the manifest's
+}
+
+// Spreadsheets to provision, one per entry (store, customer, ...):
+//
+//   { "tag_key": "store", "title": "Returns - {key}", "template": "1AbC...",
+//     "share_with": [{ "email": "ops@example.com", "role": "reader" }],
+//     "entries": [{ "key": "LDN01", "share_with": [{ "email": "ldn@example.com" }] }] }
+#[derive(Debug, Clone, Deserialize)]
+pub struct Manifest {
+    pub tag_key: String,
+    pub title: String, // "{key}" is replaced by the entry's key
+    // Spreadsheet ID to copy; without one, a blank spreadsheet with `tabs`
+    pub template: Option<String>,
+    #[serde(default)]
+    pub tabs: Vec<String>,
+    pub folder: Option<String>, // Drive folder ID to create them in
+    #[serde(default)]
+    pub share_with: Vec<Share>,
+    pub entries: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    pub fn from_json_file(path: impl AsRef<Path>) -> Result<Self, SheetsError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|e| std::io::Error::new(e.kind(), format!("cannot read manifest '{}': {}", path.display(), e)))?;
+        serde_json::from_str(&text).map_err(|e| SheetsError::Invalid(format!("manifest '{}': {}", path.display(), e)))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CreateOutcome {
+    pub spreadsheets: BTreeMap<String, String>, // Key -> spreadsheet ID, new or from an earlier run
+    pub created: usize,
+    pub failed: BTreeMap<String, String>, // Key -> error
+}
+
+pub async fn create_many(access_token: &SecretString, manifest: &Manifest) -> Result<CreateOutcome, SheetsError> {
+    create_many_with(access_token, manifest, DEFAULT_MAX_IN_FLIGHT).await
+}
+
+// Create, tag and share a spreadsheet per manifest entry, `max_in_flight` at
+// a time. Spreadsheets are tagged <tag_key>=<key> as they're created, so
+// running the same manifest again after a failure reuses what exists and only
+// finishes the rest. A failing entry doesn't stop the others; check `failed`.
+// The token needs DRIVE_SCOPE (copying a template it didn't create) as well
+// as SHEETS_SCOPE.
+pub async fn create_many_with(
+    access_token: &SecretString,
+    manifest: &Manifest,
+    max_in_flight: usize,
+) -> Result<CreateOutcome, SheetsError> {
+    let mut keys = HashSet::new();
+    if let Some(entry) = manifest.entries.iter().find(|entry| entry.key.trim().is_empty() || !keys.insert(entry.key.as_str())) {
+        return Err(SheetsError::Invalid(format!("manifest key '{}' is empty or repeated", entry.key)));
+    }
+
+    // Key -> (ID, finished) from earlier runs
+    let mut existing: BTreeMap<String, (String, bool)> = BTreeMap::new();
+    let files: Vec<_> = list_managed_spreadsheets(access_token, &[]).try_collect().await?;
+    for file in files {
+        let Some(key) = file.app_properties.get(&manifest.tag_key) else { continue };
+        let finished = file.app_properties.get(PROVISIONED_KEY).is_some_and(|v| v == "true");
+        if let Some((other, _)) = existing.insert(key.clone(), (file.id.clone(), finished)) {
+            return Err(SheetsError::Conflict(format!("both {} and {} are tagged {}={}", other, file.id, manifest.tag_key, key)));
+        }
+    }
+
+    let existing = &existing;
+    let results: Vec<_> = stream::iter(&manifest.entries)
+        .map(|entry| async move {
+            let result = match existing.get(&entry.key) {
+                Some((id, true)) => Ok((id.clone(), false)),
+                Some((id, false)) => finish(access_token, manifest, entry, id).await.map(|()| (id.clone(), false)),
+                None => match create(access_token, manifest, entry).await {
+                    Ok(id) => finish(access_token, manifest, entry, &id).await.map(|()| (id, true)),
+                    Err(e) => Err(e),
+                },
+            };
+            (entry.key.clone(), result.map_err(|e| e.to_string()))
+        })
+        .buffer_unordered(max_in_flight.max(1))
+        .collect()
+        .await;
+
+    let mut outcome = CreateOutcome::default();
+    for (key, result) in results {
+        match result {
+            Ok((id, created)) => {
+                outcome.created += usize::from(created);
+                outcome.spreadsheets.insert(key, id);
+            }
+            Err(e) => {
+                outcome.failed.insert(key, e);
+            }
+        }
+    }
+    Ok(outcome)
+}
+
+// A copy of the template, or a blank spreadsheet with the manifest's tabs,
+// tagged in the same request
+async fn create(access_token: &SecretString, manifest: &Manifest, entry: &ManifestEntry) -> Result<String, SheetsError> {
+    let title = entry.title.clone().unwrap_or_else(|| manifest.title.replace("{key}", &entry.key));
+    let mut body = json!({
+        "name": title,
+        "appProperties": { MANAGED_BY_KEY: MANAGED_BY_VALUE, manifest.tag_key.as_str(): entry.key },
+    });
+    if let Some(folder) = &manifest.folder {
+        body["parents"] = json!([folder]);
+    }
+    let url = match &manifest.template {
+        Some(template) => format!("{}/{}/copy?fields=id&supportsAllDrives=true", FILES_URL, template),
+        None => {
+            body["mimeType"] = SPREADSHEET_MIME.into();
+            format!("{}?fields=id&supportsAllDrives=true", FILES_URL)
+        }
+    };
+    let response = v4::send(access_token, Method::POST, &url, Some(&body)).await?;
+    let id = response["id"].as_str().ok_or_else(|| SheetsError::Parse("Drive returned no file id".to_string()))?.to_string();
+
+    if manifest.template.is_none() && !manifest.tabs.is_empty() {
+        // A new spreadsheet has one tab, gid 0: rename it, add the rest
+        let mut requests = vec![json!({
+            "updateSheetProperties": { "properties": { "sheetId": 0, "title": manifest.tabs[0] }, "fields": "title" }
+        })];
+        requests.extend(manifest.tabs[1..].iter().map(|tab| json!({ "addSheet": { "properties": { "title": tab } } })));
+        let url = format!("https://sheets.googleapis.com/v4/spreadsheets/{}:batchUpdate", id);
+        v4::send(access_token, Method::POST, &url, Some(&json!({ "requests": requests }))).await?;
+    }
+    Ok(id)
+}
+
+// Share, then mark done; both are safe to repeat
+async fn finish(access_token: &SecretString, manifest: &Manifest, entry: &ManifestEntry, id: &str) -> Result<(), SheetsError> {
+    for share in manifest.share_with.iter().chain(&entry.share_with) {
+        let url = format!("{}/{}/permissions?sendNotificationEmail=false&supportsAllDrives=true", FILES_URL, id);
+        let body: Value = json!({ "type": "user", "role": share.role, "emailAddress": share.email });
+        v4::send(access_token, Method::POST, &url, Some(&body)).await?;
+    }
+    tag_spreadsheet(access_token, id, &[(PROVISIONED_KEY, "true")]).await?;
+    Ok(())
+}
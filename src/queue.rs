@@ -0,0 +1,54 @@
+use crate::a1::column_letter;
+use crate::cas::{compare_and_set, CasOutcome};
+use crate::rollover::quote_sheet;
+use crate::{fetch_values, SecretString, SheetsError};
+use serde_json::Value;
+
+#[derive(Debug, Clone)]
+pub struct ClaimedRow {
+    pub row_number: usize, // 1-based sheet row
+    pub row: Vec<Value>,   // Row as it was before the assignee was written
+}
+
+// Claim the first data row of `sheet` that matches `filter` and has an empty
+// assignee cell by writing `assignee` into it. Rows another operator grabs
+// first are skipped. Returns None when nothing is left to claim.
+//
+// Delivery is at least once: claims go through cas::compare_and_set, which
+// is best effort, so two workers racing for the same row can both get it.
+// Jobs must be idempotent (safe to run twice), or check the assignee cell
+// again before any step that mustn't be repeated.
+pub async fn claim_next<F>(
+    access_token: &SecretString,
+    sheet: &str,
+    filter: F,
+    assignee_column: usize,
+    assignee: &str,
+) -> Result<Option<ClaimedRow>, SheetsError>
+where
+    F: Fn(&[Value]) -> bool,
+{
+    if assignee.trim().is_empty() {
+        return Err(SheetsError::Invalid("assignee must not be empty".to_string()));
+    }
+    let values = fetch_values(access_token, &quote_sheet(sheet)).await?;
+    let column = column_letter(assignee_column);
+
+    for (i, row) in values.iter().enumerate().skip(1) {
+        let unclaimed = row
+            .get(assignee_column)
+            .is_none_or(|cell| cell.as_str().is_some_and(|s| s.trim().is_empty()));
+        if !unclaimed || !filter(row) {
+            continue;
+        }
+        let row_number = i + 1;
+        let cell = format!("{}!{}{}", quote_sheet(sheet), column, row_number);
+        match compare_and_set(access_token, &cell, "", assignee).await? {
+            CasOutcome::Swapped => {
+                return Ok(Some(ClaimedRow { row_number, row: row.clone() }));
+            }
+            CasOutcome::Mismatch { .. } | CasOutcome::LostRace { .. } => continue,
+        }
+    }
+    Ok(None)
+}
@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+// Client-side throttle for Sheets API calls, shared by every request the
+// process makes (free functions and SheetsClient clones alike), so parallel
+// tasks queue here instead of tripping the per-minute quota and spending
+// their retries on 429s. One token bucket per quota project: the project in
+// a request's x-goog-user-project header, else DEFAULT_PROJECT. Off unless a
+// limit is set.
+
+pub const DEFAULT_PROJECT: &str = "default";
+
+const SHEETS_HOST: &str = "sheets.googleapis.com";
+
+struct Bucket {
+    per_second: f64,
+    capacity: f64,
+    tokens: f64, // Below zero when callers are already queued for later tokens
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(requests_per_minute: u32) -> Self {
+        let capacity = f64::from(requests_per_minute.max(1));
+        Bucket { per_second: capacity / 60.0, capacity, tokens: capacity, updated: Instant::now() }
+    }
+
+    // Take a token, returning how long to wait before it's really ours
+    fn reserve(&mut self) -> Duration {
+        let now = Instant::now();
+        let refill = now.duration_since(self.updated).as_secs_f64() * self.per_second;
+        self.tokens = (self.tokens + refill).min(self.capacity) - 1.0;
+        self.updated = now;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.per_second)
+        }
+    }
+}
+
+#[derive(Default)]
+struct Limits {
+    configured: HashMap<String, Option<u32>>, // None = explicitly unlimited
+    buckets: HashMap<String, Bucket>,
+}
+
+fn limits() -> std::sync::MutexGuard<'static, Limits> {
+    static LIMITS: OnceLock<Mutex<Limits>> = OnceLock::new();
+    LIMITS.get_or_init(Mutex::default).lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// Allow `project` this many requests a minute, in bursts of up to as many
+pub fn set_limit(project: &str, requests_per_minute: u32) {
+    let mut limits = limits();
+    limits.configured.insert(project.to_string(), Some(requests_per_minute));
+    limits.buckets.insert(project.to_string(), Bucket::new(requests_per_minute));
+}
+
+// No throttling for `project`, whatever RATE_LIMIT_RPM says
+pub fn remove_limit(project: &str) {
+    let mut limits = limits();
+    limits.configured.insert(project.to_string(), None);
+    limits.buckets.remove(project);
+}
+
+// set_limit for the project, else RATE_LIMIT_RPM (which then applies to each
+// project separately), else none
+pub fn limit_for(project: &str) -> Option<u32> {
+    if let Some(limit) = limits().configured.get(project) {
+        return *limit;
+    }
+    env::var("RATE_LIMIT_RPM").ok().and_then(|rpm| rpm.trim().parse().ok()).filter(|rpm| *rpm > 0)
+}
+
+// Wait for a slot before sending `request`; requests to other hosts pass
+pub(crate) async fn acquire(request: &reqwest::Request) {
+    if request.url().host_str() != Some(SHEETS_HOST) {
+        return;
+    }
+    let project = request.headers().get("x-goog-user-project").and_then(|v| v.to_str().ok()).unwrap_or(DEFAULT_PROJECT);
+    let Some(limit) = limit_for(project) else { return };
+    let wait = limits().buckets.entry(project.to_string()).or_insert_with(|| Bucket::new(limit)).reserve();
+    if !wait.is_zero() {
+        tracing::debug!("rate limit for '{}': waiting {:?}", project, wait);
+        tokio::time::sleep(wait).await;
+    }
+}
@@ -0,0 +1,37 @@
+use std::env;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+
+// A write attempted while read-only mode is on; nothing was sent
+#[derive(Debug, Clone)]
+pub struct ReadOnlyViolation {
+    pub operation: String,
+}
+
+impl fmt::Display for ReadOnlyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} refused: read-only mode is on", self.operation)
+    }
+}
+
+impl std::error::Error for ReadOnlyViolation {}
+
+// Process-wide switch used by the CLI's --read-only. SheetsClient::read_only
+// covers a single client instead.
+pub fn set_read_only(on: bool) {
+    READ_ONLY.store(on, Ordering::Relaxed);
+}
+
+// On after set_read_only(true), or with SHEETS_READ_ONLY=1
+pub fn is_read_only() -> bool {
+    READ_ONLY.load(Ordering::Relaxed) || matches!(env::var("SHEETS_READ_ONLY").as_deref(), Ok("1" | "true"))
+}
+
+pub fn guard(operation: &str) -> Result<(), ReadOnlyViolation> {
+    if is_read_only() {
+        return Err(ReadOnlyViolation { operation: operation.to_string() });
+    }
+    Ok(())
+}
@@ -0,0 +1,307 @@
+use crate::a1::range_sheet;
+use crate::bool_column::BoolColumn;
+use crate::cell_value::{CellValue, NullPolicy};
+use crate::coerce::{cell_i64, cell_text, coerce};
+use crate::numbers::{parse_number, NumberLocale};
+use crate::rollover::quote_sheet;
+use crate::row::column_key;
+use crate::table::{interpret, ReadOptions, ReadResult};
+use crate::{append_rows_to_google_sheet_with, fetch_values, SecretString, SheetsError};
+use serde::de::value::{Error as DeError, MapDeserializer};
+use serde::de::{DeserializeOwned, Error as _, IntoDeserializer, Visitor};
+use serde::{forward_to_deserialize_any, Deserializer, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+// Rows as user structs. Fields are matched to columns by the header's
+// column_key ("CHANNEL VLOOKUP" -> channel_vlookup), so #[serde(rename)]
+// takes that form too. Columns the struct doesn't name are ignored; fields
+// with no column need Option or #[serde(default)].
+
+#[derive(Debug, Clone)]
+pub struct RecordError {
+    pub row: usize, // 1-based sheet row, header is row 1
+    pub message: String,
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "row {}: {}", self.row, self.message)
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+// Deserialize each row; blank cells read as None/"" and "12", "£1,234.50",
+// "TRUE" fill numeric and bool fields
+pub fn rows_as<T: DeserializeOwned>(header: &[Value], rows: &[Vec<Value>]) -> Result<Vec<T>, RecordError> {
+    let keys: Vec<String> = header.iter().map(|h| column_key(&cell_text(h))).collect();
+    rows.iter()
+        .enumerate()
+        .map(|(index, row)| {
+            let fields = keys
+                .iter()
+                .enumerate()
+                .filter(|(_, key)| !key.is_empty())
+                .map(|(col, key)| (key.clone(), CellDeserializer(row.get(col).cloned().unwrap_or(Value::Null))));
+            T::deserialize(MapDeserializer::<_, DeError>::new(fields))
+                .map_err(|e| RecordError { row: index + 2, message: e.to_string() })
+        })
+        .collect()
+}
+
+// How fields are lined up with the sheet's header when writing. Values always
+// follow the header's current column order, so data stays in the right
+// columns when someone rearranges them; columns no field names are left blank.
+#[derive(Debug, Clone, Default)]
+pub struct Alignment {
+    // Columns that must be in the header, by header text or field name
+    // ("CHANNEL VLOOKUP" or channel_vlookup); the write fails without them
+    pub required: Vec<String>,
+    // Drop fields that have no column instead of failing
+    pub ignore_unknown: bool,
+    // What None fields and unnamed columns write
+    pub nulls: NullPolicy,
+}
+
+impl Alignment {
+    pub fn require(columns: &[&str]) -> Self {
+        Alignment { required: columns.iter().map(|c| c.to_string()).collect(), ..Default::default() }
+    }
+
+    pub fn ignore_unknown(mut self) -> Self {
+        self.ignore_unknown = true;
+        self
+    }
+
+    pub fn nulls(mut self, nulls: NullPolicy) -> Self {
+        self.nulls = nulls;
+        self
+    }
+
+    fn check_required(&self, keys: &[String]) -> Result<(), String> {
+        let missing: Vec<&str> = self.required.iter().filter(|c| !keys.contains(&column_key(c))).map(String::as_str).collect();
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("the header has no column for required {}", missing.join(", ")))
+        }
+    }
+}
+
+// The inverse: one row per item in header order. A field with a value but no
+// matching column is an error rather than silently dropped.
+pub fn struct_rows<T: Serialize>(header: &[Value], items: &[T]) -> Result<Vec<Vec<CellValue>>, String> {
+    struct_rows_with(header, items, &Alignment::default())
+}
+
+pub fn struct_rows_with<T: Serialize>(header: &[Value], items: &[T], alignment: &Alignment) -> Result<Vec<Vec<CellValue>>, String> {
+    let keys: Vec<String> = header.iter().map(|h| column_key(&cell_text(h))).collect();
+    alignment.check_required(&keys)?;
+    items
+        .iter()
+        .map(|item| {
+            let Value::Object(fields) = serde_json::to_value(item).map_err(|e| e.to_string())? else {
+                return Err("append_struct needs a struct or map".to_string());
+            };
+            let unknown = fields.iter().find(|(field, value)| !value.is_null() && !keys.contains(&column_key(field)));
+            if let (Some((unknown, _)), false) = (unknown, alignment.ignore_unknown) {
+                return Err(format!("no column for field '{}'", unknown));
+            }
+            let row = keys
+                .iter()
+                .map(|key| {
+                    fields
+                        .iter()
+                        .find(|(field, _)| column_key(field) == *key && !key.is_empty())
+                        .map_or(CellValue::Empty, |(_, value)| CellValue::from_json(value))
+                })
+                .collect();
+            Ok(row)
+        })
+        .collect()
+}
+
+// Rows laid out under `columns` rearranged into the order of the sheet's
+// `header`, matched by column_key, with blanks for header columns `columns`
+// doesn't have
+pub fn align_rows(header: &[Value], columns: &[&str], rows: Vec<Vec<CellValue>>, alignment: &Alignment) -> Result<Vec<Vec<CellValue>>, String> {
+    let keys: Vec<String> = header.iter().map(|h| column_key(&cell_text(h))).collect();
+    alignment.check_required(&keys)?;
+    let mut targets = Vec::with_capacity(columns.len());
+    for column in columns {
+        let target = keys.iter().position(|key| !key.is_empty() && *key == column_key(column));
+        if target.is_none() && !alignment.ignore_unknown {
+            return Err(format!("no column for '{}'", column));
+        }
+        targets.push(target);
+    }
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let mut aligned = vec![CellValue::Empty; keys.len()];
+            for (cell, target) in row.into_iter().zip(&targets) {
+                if let Some(target) = target {
+                    aligned[*target] = cell;
+                }
+            }
+            aligned
+        })
+        .collect())
+}
+
+// Read `range` (header in its first row) into structs
+pub async fn read_as<T: DeserializeOwned>(access_token: &SecretString, range: &str) -> Result<Vec<T>, SheetsError> {
+    read_as_with(access_token, range, &ReadOptions::default()).await
+}
+
+// read_as with table options, e.g. ReadOptions::default().header_rows(2) to
+// map "RETURNS / DATE" onto a returns_date field
+pub async fn read_as_with<T: DeserializeOwned>(
+    access_token: &SecretString,
+    range: &str,
+    options: &ReadOptions,
+) -> Result<Vec<T>, SheetsError> {
+    let values = fetch_values(access_token, range).await?;
+    let options = ReadOptions { require_header: true, ..*options };
+    match interpret(range, values, &options)? {
+        ReadResult::Empty => Ok(Vec::new()),
+        ReadResult::Table { header, rows } => Ok(rows_as(&header, &rows)?),
+    }
+}
+
+// Append items under the header of the tab `range` names; returns rows appended
+pub async fn append_struct<T: Serialize>(access_token: &SecretString, range: &str, items: &[T]) -> Result<usize, SheetsError> {
+    append_struct_with(access_token, range, items, &Alignment::default()).await
+}
+
+pub async fn append_struct_with<T: Serialize>(
+    access_token: &SecretString,
+    range: &str,
+    items: &[T],
+    alignment: &Alignment,
+) -> Result<usize, SheetsError> {
+    let sheet = range_sheet(range).unwrap_or_else(|| range.trim_matches('\'').to_string());
+    let header = fetch_values(access_token, &format!("{}!1:1", quote_sheet(&sheet))).await?.into_iter().next().unwrap_or_default();
+    if header.is_empty() {
+        return Err(SheetsError::Invalid(format!("'{}' has no header row to map fields onto", sheet)));
+    }
+    let rows = struct_rows_with(&header, items, alignment).map_err(SheetsError::Invalid)?;
+    append_rows_to_google_sheet_with(access_token, range, rows, alignment.nulls).await
+}
+
+// One cell as a serde Deserializer: lenient about how the value was rendered
+struct CellDeserializer(Value);
+
+impl CellDeserializer {
+    fn is_blank(&self) -> bool {
+        match &self.0 {
+            Value::Null => true,
+            Value::String(s) => s.trim().is_empty(),
+            _ => false,
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> DeError {
+        DeError::custom(format!("expected {}, found {:?}", expected, cell_text(&self.0)))
+    }
+
+    fn number(&self) -> Option<f64> {
+        match &self.0 {
+            Value::Number(n) => n.as_f64(),
+            Value::String(s) => parse_number(s, NumberLocale::Auto),
+            _ => None,
+        }
+    }
+}
+
+impl<'de> IntoDeserializer<'de, DeError> for CellDeserializer {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+macro_rules! deserialize_int {
+    ($($method:ident)*) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+            match cell_i64(&self.0) {
+                Some(i) => visitor.visit_i64(i),
+                None => Err(self.unexpected("a whole number")),
+            }
+        }
+    )*};
+}
+
+impl<'de> Deserializer<'de> for CellDeserializer {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match coerce(&self.0) {
+            Value::Null => visitor.visit_unit(),
+            Value::Bool(b) => visitor.visit_bool(b),
+            Value::Number(n) => match (n.as_i64(), n.as_f64()) {
+                (Some(i), _) => visitor.visit_i64(i),
+                (None, Some(f)) => visitor.visit_f64(f),
+                _ => visitor.visit_string(n.to_string()),
+            },
+            Value::String(s) => visitor.visit_string(s),
+            other => visitor.visit_string(other.to_string()),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match BoolColumn::default().parse(&self.0) {
+            Some(b) => visitor.visit_bool(b),
+            None => Err(self.unexpected("TRUE or FALSE")),
+        }
+    }
+
+    deserialize_int!(deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64 deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64);
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        self.deserialize_f64(visitor)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self.number() {
+            Some(f) => visitor.visit_f64(f),
+            None => Err(self.unexpected("a number")),
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_string(cell_text(&self.0))
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_string(cell_text(&self.0))
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        if self.is_blank() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        // Unit variants by name, e.g. a STATUS column holding "Refunded"
+        visitor.visit_enum(cell_text(&self.0).into_deserializer())
+    }
+
+    forward_to_deserialize_any! {
+        char bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
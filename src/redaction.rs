@@ -0,0 +1,143 @@
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::env;
+
+// What to do with a sensitive column before it leaves the process
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedactAction {
+    Hash, // sha256 hex, stable across runs so rows can still be joined
+    Mask, // keep a few characters, e.g. j***@gmail.com or ******1234
+    Drop, // remove the column entirely
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedactionRule {
+    pub column: String, // Header name, compared case-insensitively
+    pub action: RedactAction,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    rules: Vec<RedactionRule>,
+}
+
+impl Redactor {
+    pub fn new(rules: Vec<RedactionRule>) -> Self {
+        Redactor { rules }
+    }
+
+    // Parse a spec like "EMAIL=hash, PHONE=mask, NOTES=drop"
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut rules = Vec::new();
+        for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (column, action) = part
+                .rsplit_once('=')
+                .ok_or_else(|| format!("redaction rule '{}' must look like COLUMN=action", part))?;
+            let action = match action.trim().to_ascii_lowercase().as_str() {
+                "hash" => RedactAction::Hash,
+                "mask" => RedactAction::Mask,
+                "drop" => RedactAction::Drop,
+                other => return Err(format!("unknown redaction action '{}' for column '{}'", other, column.trim())),
+            };
+            rules.push(RedactionRule { column: column.trim().to_string(), action });
+        }
+        Ok(Redactor { rules })
+    }
+
+    // Rules come from the REDACT_COLUMNS variable; unset means no redaction
+    pub fn from_env() -> Result<Self, String> {
+        match env::var("REDACT_COLUMNS") {
+            Ok(spec) => Redactor::parse(&spec),
+            Err(_) => Ok(Redactor::default()),
+        }
+    }
+
+    // Inverse of `parse`
+    pub fn to_spec(&self) -> String {
+        self.rules
+            .iter()
+            .map(|rule| {
+                let action = match rule.action {
+                    RedactAction::Hash => "hash",
+                    RedactAction::Mask => "mask",
+                    RedactAction::Drop => "drop",
+                };
+                format!("{}={}", rule.column, action)
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    pub fn rules(&self) -> &[RedactionRule] {
+        &self.rules
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    // Action for each header position (None = pass through)
+    fn actions_for(&self, header: &[Value]) -> Vec<Option<RedactAction>> {
+        header
+            .iter()
+            .map(|name| {
+                let name = name.as_str().unwrap_or_default().trim();
+                self.rules
+                    .iter()
+                    .find(|rule| rule.column.eq_ignore_ascii_case(name))
+                    .map(|rule| rule.action)
+            })
+            .collect()
+    }
+
+    pub fn apply_header(&self, header: &[Value]) -> Vec<Value> {
+        let actions = self.actions_for(header);
+        header
+            .iter()
+            .zip(actions)
+            .filter(|(_, action)| *action != Some(RedactAction::Drop))
+            .map(|(cell, _)| cell.clone())
+            .collect()
+    }
+
+    pub fn apply_row(&self, header: &[Value], row: &[Value]) -> Vec<Value> {
+        let actions = self.actions_for(header);
+        row.iter()
+            .enumerate()
+            .filter_map(|(i, cell)| match actions.get(i).copied().flatten() {
+                None => Some(cell.clone()),
+                Some(RedactAction::Drop) => None,
+                Some(RedactAction::Hash) => Some(Value::String(hash_value(&cell_text(cell)))),
+                Some(RedactAction::Mask) => Some(Value::String(mask_value(&cell_text(cell)))),
+            })
+            .collect()
+    }
+}
+
+fn cell_text(cell: &Value) -> String {
+    match cell {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+pub fn hash_value(value: &str) -> String {
+    let digest = Sha256::digest(value.as_bytes());
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn mask_value(value: &str) -> String {
+    if value.is_empty() {
+        return String::new();
+    }
+    if let Some((local, domain)) = value.split_once('@') {
+        let first: String = local.chars().take(1).collect();
+        return format!("{}***@{}", first, domain);
+    }
+    let chars: Vec<char> = value.chars().collect();
+    if chars.len() <= 4 {
+        return "*".repeat(chars.len());
+    }
+    let visible: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}{}", "*".repeat(chars.len() - 4), visible)
+}
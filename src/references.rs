@@ -0,0 +1,112 @@
+use crate::a1::{range_sheet, range_start};
+use crate::metadata::spreadsheet_metadata;
+use crate::{api, fetch_values, SecretString, SheetsError};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::fmt;
+
+// Light red, the same shade Sheets uses for its "bad" conditional format
+const ORPHAN_COLOR: (f64, f64, f64) = (0.957, 0.8, 0.8);
+
+#[derive(Debug, Clone)]
+pub struct OrphanRow {
+    pub row_number: usize, // 1-based sheet row
+    pub key: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ReferenceReport {
+    pub checked: usize,
+    pub orphans: Vec<OrphanRow>,
+}
+
+impl ReferenceReport {
+    pub fn is_clean(&self) -> bool {
+        self.orphans.is_empty()
+    }
+}
+
+impl fmt::Display for ReferenceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} row(s) checked, {} orphaned", self.checked, self.orphans.len())?;
+        for orphan in &self.orphans {
+            writeln!(f, "  row {}: '{}' not found", orphan.row_number, orphan.key)?;
+        }
+        Ok(())
+    }
+}
+
+fn cell_text(cell: Option<&Value>) -> String {
+    match cell {
+        Some(Value::String(s)) => s.trim().to_string(),
+        Some(Value::Null) | None => String::new(),
+        Some(other) => other.to_string(),
+    }
+}
+
+// Report rows of `child_range` whose `child_col` key isn't in `parent_col` of
+// `parent_range` (e.g. returns pointing at order IDs that don't exist). Both
+// ranges start with a header row; columns are indices within the range.
+// Empty child keys are ignored. With `highlight`, orphaned rows get a red
+// background in the child tab.
+pub async fn check_references(
+    access_token: &SecretString,
+    child_range: &str,
+    child_col: usize,
+    parent_range: &str,
+    parent_col: usize,
+    highlight: bool,
+) -> Result<ReferenceReport, SheetsError> {
+    let parent = fetch_values(access_token, parent_range).await?;
+    let parent_keys: HashSet<String> = parent
+        .iter()
+        .skip(1)
+        .map(|row| cell_text(row.get(parent_col)))
+        .filter(|key| !key.is_empty())
+        .collect();
+
+    let child = fetch_values(access_token, child_range).await?;
+    let (_, first_row) = range_start(child_range);
+    let mut report = ReferenceReport::default();
+    for (i, row) in child.iter().enumerate().skip(1) {
+        let key = cell_text(row.get(child_col));
+        if key.is_empty() {
+            continue;
+        }
+        report.checked += 1;
+        if !parent_keys.contains(&key) {
+            report.orphans.push(OrphanRow { row_number: first_row + i, key });
+        }
+    }
+
+    if highlight && !report.orphans.is_empty() {
+        highlight_rows(access_token, child_range, &report.orphans).await?;
+    }
+    Ok(report)
+}
+
+async fn highlight_rows(
+    access_token: &SecretString,
+    child_range: &str,
+    orphans: &[OrphanRow],
+) -> Result<(), SheetsError> {
+    let sheet = range_sheet(child_range).unwrap_or_else(|| child_range.to_string());
+    let metadata = spreadsheet_metadata(access_token).await?;
+    let gid = metadata
+        .resolve_gid(&sheet)
+        .ok_or_else(|| SheetsError::NotFound(format!("no tab named '{}' to highlight", sheet)))?;
+    let (red, green, blue) = ORPHAN_COLOR;
+    let requests: Vec<Value> = orphans
+        .iter()
+        .map(|orphan| {
+            json!({ "repeatCell": {
+                "range": { "sheetId": gid, "startRowIndex": orphan.row_number - 1, "endRowIndex": orphan.row_number },
+                "cell": { "userEnteredFormat": { "backgroundColor": { "red": red, "green": green, "blue": blue } } },
+                "fields": "userEnteredFormat.backgroundColor",
+            }})
+        })
+        .collect();
+
+    api::v4::batch_update(access_token, requests).await?;
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use std::fmt;
+
+// How reads render cells. The API default is FORMATTED_VALUE: what the UI
+// shows, as strings ("£1,234.50", "01/02/2024"). UNFORMATTED_VALUE gives
+// raw numbers and booleans, FORMULA gives "=SUM(A1:A3)" for formula cells.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ValueRender {
+    #[default]
+    Formatted,
+    Unformatted,
+    Formula,
+}
+
+impl ValueRender {
+    pub fn api_name(self) -> &'static str {
+        match self {
+            ValueRender::Formatted => "FORMATTED_VALUE",
+            ValueRender::Unformatted => "UNFORMATTED_VALUE",
+            ValueRender::Formula => "FORMULA",
+        }
+    }
+}
+
+// Dates and times when they aren't formatted: the serial number Sheets
+// stores (days since 1899-12-30, the API default) or the formatted string
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DateTimeRender {
+    #[default]
+    SerialNumber,
+    FormattedString,
+}
+
+impl DateTimeRender {
+    pub fn api_name(self) -> &'static str {
+        match self {
+            DateTimeRender::SerialNumber => "SERIAL_NUMBER",
+            DateTimeRender::FormattedString => "FORMATTED_STRING",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RenderOptions {
+    pub value: ValueRender,
+    // Ignored by the API with Formatted values
+    pub date_time: DateTimeRender,
+}
+
+impl RenderOptions {
+    pub fn unformatted() -> Self {
+        RenderOptions { value: ValueRender::Unformatted, ..Default::default() }
+    }
+
+    pub fn formulas() -> Self {
+        RenderOptions { value: ValueRender::Formula, ..Default::default() }
+    }
+
+    pub fn with_date_time(mut self, date_time: DateTimeRender) -> Self {
+        self.date_time = date_time;
+        self
+    }
+
+    // Query parameters that differ from the API defaults
+    pub fn params(&self) -> Vec<(&'static str, &'static str)> {
+        let mut params = Vec::new();
+        if self.value != ValueRender::default() {
+            params.push(("valueRenderOption", self.value.api_name()));
+        }
+        if self.date_time != DateTimeRender::default() {
+            params.push(("dateTimeRenderOption", self.date_time.api_name()));
+        }
+        params
+    }
+}
+
+// "?valueRenderOption=...&..." or "" for the defaults, to append to a URL
+impl fmt::Display for RenderOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (name, value)) in self.params().into_iter().enumerate() {
+            write!(f, "{}{}={}", if i == 0 { '?' } else { '&' }, name, value)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,262 @@
+use crate::aggregate::ColumnSummary;
+use crate::grid::{update_cells_request, CellData, CellFormat, Color, GridRange, NumberFormat, TextFormat};
+use crate::metadata::{self, spreadsheet_metadata};
+use crate::numbers::amount_to_f64;
+use crate::provenance::Provenance;
+use crate::{api, SecretString, SheetsError};
+use serde_json::{json, Value};
+
+// Rows a chart takes up below the content before the next section starts
+const CHART_ROWS: usize = 20;
+const HEADER_GREY: f64 = 0.9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChartKind {
+    Column,
+    Bar,
+    Line,
+}
+
+impl ChartKind {
+    fn api_name(self) -> &'static str {
+        match self {
+            ChartKind::Column => "COLUMN",
+            ChartKind::Bar => "BAR",
+            ChartKind::Line => "LINE",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ReportTable {
+    pub title: Option<String>,
+    pub header: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+    // Sheets number format pattern for numeric cells, e.g. "£#,##0.00"
+    pub number_format: Option<String>,
+}
+
+impl ReportTable {
+    // One row per summary: column, count, sum, min, max
+    pub fn from_summaries(title: &str, summaries: &[ColumnSummary], number_format: Option<&str>) -> Self {
+        let number = |a| json!(amount_to_f64(a));
+        ReportTable {
+            title: Some(title.to_string()),
+            header: ["COLUMN", "COUNT", "SUM", "MIN", "MAX"].map(String::from).to_vec(),
+            rows: summaries
+                .iter()
+                .map(|s| {
+                    vec![
+                        json!(s.column),
+                        json!(s.count),
+                        number(s.sum),
+                        s.min.map(number).unwrap_or(Value::Null),
+                        s.max.map(number).unwrap_or(Value::Null),
+                    ]
+                })
+                .collect(),
+            number_format: number_format.map(str::to_string),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Section {
+    // Bold, merged across the report width
+    Title(String),
+    Table(ReportTable),
+    // Chart of an earlier table: first column is the axis, the rest are series
+    Chart { title: String, kind: ChartKind, table: usize },
+    Notes(Vec<String>),
+}
+
+// A summary tab declared as a list of sections, rendered top to bottom with a
+// blank row between sections
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub tab: String,
+    pub sections: Vec<Section>,
+}
+
+impl Report {
+    pub fn new(tab: &str) -> Self {
+        Report { tab: tab.to_string(), sections: Vec::new() }
+    }
+
+    pub fn title(mut self, text: &str) -> Self {
+        self.sections.push(Section::Title(text.to_string()));
+        self
+    }
+
+    pub fn table(mut self, table: ReportTable) -> Self {
+        self.sections.push(Section::Table(table));
+        self
+    }
+
+    // `table` counts Table sections only: 0 is the first table in the report
+    pub fn chart(mut self, title: &str, kind: ChartKind, table: usize) -> Self {
+        self.sections.push(Section::Chart { title: title.to_string(), kind, table });
+        self
+    }
+
+    pub fn notes(mut self, lines: &[&str]) -> Self {
+        self.sections.push(Section::Notes(lines.iter().map(|l| l.to_string()).collect()));
+        self
+    }
+
+    // Notes section recording where the report's data came from
+    pub fn provenance(mut self, provenance: &Provenance) -> Self {
+        self.sections.push(Section::Notes(provenance.lines()));
+        self
+    }
+
+    fn width(&self) -> usize {
+        self.sections
+            .iter()
+            .filter_map(|s| match s {
+                Section::Table(t) => Some(t.header.len()),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(1)
+            .max(1)
+    }
+
+    // The batchUpdate requests that draw the report onto an empty tab `gid`
+    pub fn build_requests(&self, gid: u64) -> Result<Vec<Value>, String> {
+        let width = self.width();
+        let mut requests = Vec::new();
+        let mut row = 0usize;
+        // (first header row, row count including header, column count) per table
+        let mut tables: Vec<(usize, usize, usize)> = Vec::new();
+
+        for section in &self.sections {
+            match section {
+                Section::Title(text) => {
+                    let cell = CellData::text(text).with_format(text_style(true, false, Some(14)));
+                    requests.push(write_rows(gid, row, vec![vec![cell]]));
+                    requests.push(json!({ "mergeCells": {
+                        "range": grid_range(gid, row, row + 1, 0, width),
+                        "mergeType": "MERGE_ALL",
+                    }}));
+                    row += 1;
+                }
+                Section::Table(table) => {
+                    if let Some(title) = &table.title {
+                        let cell = CellData::text(title).with_format(text_style(true, false, None));
+                        requests.push(write_rows(gid, row, vec![vec![cell]]));
+                        row += 1;
+                    }
+                    // Values and formats go in the same updateCells, no second formatting pass
+                    let cols = table.header.len().max(1);
+                    let header_format = CellFormat {
+                        background_color: Some(Color { red: HEADER_GREY, green: HEADER_GREY, blue: HEADER_GREY }),
+                        ..text_style(true, false, None)
+                    };
+                    let mut cells = vec![table
+                        .header
+                        .iter()
+                        .map(|h| CellData::text(h).with_format(header_format.clone()))
+                        .collect::<Vec<_>>()];
+                    cells.extend(table.rows.iter().map(|r| {
+                        r.iter()
+                            .map(|value| {
+                                let cell = CellData::from_json(value);
+                                match (&table.number_format, value) {
+                                    (Some(pattern), Value::Number(_)) => cell.with_format(CellFormat {
+                                        number_format: Some(NumberFormat { kind: "NUMBER".to_string(), pattern: pattern.clone() }),
+                                        ..Default::default()
+                                    }),
+                                    _ => cell,
+                                }
+                            })
+                            .collect()
+                    }));
+                    let count = cells.len();
+                    requests.push(write_rows(gid, row, cells));
+                    tables.push((row, count, cols));
+                    row += count;
+                }
+                Section::Chart { title, kind, table } => {
+                    let &(start, count, cols) = tables
+                        .get(*table)
+                        .ok_or_else(|| format!("chart '{}' refers to table {} which isn't declared before it", title, table))?;
+                    let source = |col: usize| json!({ "sourceRange": { "sources": [grid_range(gid, start, start + count, col, col + 1)] } });
+                    let series: Vec<Value> = (1..cols)
+                        .map(|col| json!({ "series": source(col), "targetAxis": "LEFT_AXIS" }))
+                        .collect();
+                    requests.push(json!({ "addChart": { "chart": {
+                        "spec": {
+                            "title": title,
+                            "basicChart": {
+                                "chartType": kind.api_name(),
+                                "legendPosition": "BOTTOM_LEGEND",
+                                "headerCount": 1,
+                                "domains": [{ "domain": source(0) }],
+                                "series": series,
+                            },
+                        },
+                        "position": { "overlayPosition": {
+                            "anchorCell": { "sheetId": gid, "rowIndex": row, "columnIndex": 0 },
+                        }},
+                    }}}));
+                    row += CHART_ROWS;
+                }
+                Section::Notes(lines) => {
+                    let italic = text_style(false, true, None);
+                    let cells = lines.iter().map(|l| vec![CellData::text(l).with_format(italic.clone())]).collect();
+                    requests.push(write_rows(gid, row, cells));
+                    row += lines.len();
+                }
+            }
+            row += 1; // Blank row between sections
+        }
+        requests.push(json!({ "autoResizeDimensions": { "dimensions": {
+            "sheetId": gid, "dimension": "COLUMNS", "startIndex": 0, "endIndex": width,
+        }}}));
+        Ok(requests)
+    }
+}
+
+fn grid_range(gid: u64, start_row: usize, end_row: usize, start_col: usize, end_col: usize) -> Value {
+    json!({
+        "sheetId": gid,
+        "startRowIndex": start_row,
+        "endRowIndex": end_row,
+        "startColumnIndex": start_col,
+        "endColumnIndex": end_col,
+    })
+}
+
+fn text_style(bold: bool, italic: bool, font_size: Option<u32>) -> CellFormat {
+    CellFormat {
+        text_format: Some(TextFormat { bold: Some(bold), italic: Some(italic), font_size }),
+        ..Default::default()
+    }
+}
+
+fn write_rows(gid: u64, start_row: usize, rows: Vec<Vec<CellData>>) -> Value {
+    let range = GridRange { sheet_id: gid, start_row_index: Some(start_row), start_column_index: Some(0), ..Default::default() };
+    update_cells_request(range, &rows)
+}
+
+// Replace `report.tab` with a freshly rendered report in a single batchUpdate.
+// An existing tab of that name is deleted first (taking its old charts with it),
+// so the report tab gets a new gid on every run.
+pub async fn render_report(access_token: &SecretString, report: &Report) -> Result<u64, SheetsError> {
+    let metadata = spreadsheet_metadata(access_token).await?;
+    let gid = metadata.sheets.iter().map(|s| s.sheet_id).max().unwrap_or(0) + 1;
+
+    let mut requests = Vec::new();
+    if let Some(existing) = metadata.resolve_gid(&report.tab) {
+        requests.push(json!({ "deleteSheet": { "sheetId": existing } }));
+    }
+    requests.push(json!({ "addSheet": { "properties": { "sheetId": gid, "title": report.tab } } }));
+    requests.extend(report.build_requests(gid).map_err(SheetsError::Invalid)?);
+
+    let result = api::v4::batch_update(access_token, requests).await;
+    metadata::invalidate_metadata();
+    result?;
+    println!(" Rendered report '{}'", report.tab);
+    Ok(gid)
+}
@@ -0,0 +1,147 @@
+use serde_json::Value;
+use std::collections::HashMap;
+
+// Tables here are rows of cells with the header as the first row, the same
+// shape fetch_values returns and the write functions take.
+
+fn header_index(header: &[Value], name: &str) -> Result<usize, String> {
+    header
+        .iter()
+        .position(|h| h.as_str().is_some_and(|h| h.trim().eq_ignore_ascii_case(name.trim())))
+        .ok_or_else(|| format!("no column named '{}'", name))
+}
+
+fn cell_key(cell: Option<&Value>) -> String {
+    match cell {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Null) | None => String::new(),
+        Some(other) => other.to_string(),
+    }
+}
+
+// Long -> wide. One output row per distinct combination of `index_cols`, one
+// output column per distinct value of `columns_col` (in first-seen order),
+// filled from `value_col`. Two rows for the same cell are an error.
+pub fn pivot(
+    table: &[Vec<Value>],
+    index_cols: &[&str],
+    columns_col: &str,
+    value_col: &str,
+) -> Result<Vec<Vec<Value>>, String> {
+    let header = table.first().ok_or("table is empty")?;
+    let index: Vec<usize> = index_cols.iter().map(|c| header_index(header, c)).collect::<Result<_, _>>()?;
+    let key_col = header_index(header, columns_col)?;
+    let val_col = header_index(header, value_col)?;
+
+    let mut keys: Vec<String> = Vec::new();
+    let mut key_pos: HashMap<String, usize> = HashMap::new();
+    let mut groups: Vec<(Vec<Value>, HashMap<usize, Value>)> = Vec::new();
+    let mut group_pos: HashMap<Vec<String>, usize> = HashMap::new();
+
+    for row in &table[1..] {
+        let id_values: Vec<Value> = index
+            .iter()
+            .map(|i| row.get(*i).cloned().unwrap_or(Value::String(String::new())))
+            .collect();
+        let id_key: Vec<String> = index.iter().map(|i| cell_key(row.get(*i))).collect();
+        let key = cell_key(row.get(key_col));
+
+        let k = *key_pos.entry(key.clone()).or_insert_with(|| {
+            keys.push(key.clone());
+            keys.len() - 1
+        });
+        let g = *group_pos.entry(id_key.clone()).or_insert_with(|| {
+            groups.push((id_values, HashMap::new()));
+            groups.len() - 1
+        });
+        let value = row.get(val_col).cloned().unwrap_or(Value::String(String::new()));
+        if groups[g].1.insert(k, value).is_some() {
+            return Err(format!("more than one '{}' value for {:?} / '{}'", value_col, id_key, key));
+        }
+    }
+
+    let mut out = Vec::with_capacity(groups.len() + 1);
+    let mut out_header: Vec<Value> = index.iter().map(|i| header[*i].clone()).collect();
+    out_header.extend(keys.iter().map(|k| Value::String(k.clone())));
+    out.push(out_header);
+    for (mut id_values, mut cells) in groups {
+        for k in 0..keys.len() {
+            id_values.push(cells.remove(&k).unwrap_or(Value::String(String::new())));
+        }
+        out.push(id_values);
+    }
+    Ok(out)
+}
+
+// Wide -> long. Every column not in `id_cols` becomes one output row per input
+// row: id columns..., `var_name` (the column header), `value_name` (the cell).
+pub fn unpivot(
+    table: &[Vec<Value>],
+    id_cols: &[&str],
+    var_name: &str,
+    value_name: &str,
+) -> Result<Vec<Vec<Value>>, String> {
+    let header = table.first().ok_or("table is empty")?;
+    let ids: Vec<usize> = id_cols.iter().map(|c| header_index(header, c)).collect::<Result<_, _>>()?;
+    let values: Vec<usize> = (0..header.len()).filter(|i| !ids.contains(i)).collect();
+
+    let mut out_header: Vec<Value> = ids.iter().map(|i| header[*i].clone()).collect();
+    out_header.push(Value::String(var_name.to_string()));
+    out_header.push(Value::String(value_name.to_string()));
+
+    let mut out = vec![out_header];
+    for row in &table[1..] {
+        for v in &values {
+            let mut long: Vec<Value> = ids
+                .iter()
+                .map(|i| row.get(*i).cloned().unwrap_or(Value::String(String::new())))
+                .collect();
+            long.push(header[*v].clone());
+            long.push(row.get(*v).cloned().unwrap_or(Value::String(String::new())));
+            out.push(long);
+        }
+    }
+    Ok(out)
+}
+
+// Replace `column` with one column per `new_headers`, splitting each cell on
+// `delimiter`: "SKU|SIZE" -> "SKU", "SIZE". Pieces are trimmed; a cell with
+// fewer pieces gets "" for the rest, one with more keeps the remainder
+// (delimiters included) in the last new column. For when textToColumns
+// doesn't fit, e.g. the columns to the right aren't free.
+pub fn split_column(
+    table: &[Vec<Value>],
+    column: &str,
+    delimiter: &str,
+    new_headers: &[&str],
+) -> Result<Vec<Vec<Value>>, String> {
+    let header = table.first().ok_or("table is empty")?;
+    let col = header_index(header, column)?;
+    if delimiter.is_empty() || new_headers.is_empty() {
+        return Err("split_column needs a delimiter and at least one new header".to_string());
+    }
+    let splice = |row: &[Value], pieces: Vec<Value>| {
+        let mut out: Vec<Value> = row.iter().take(col).cloned().collect();
+        out.resize(col, Value::String(String::new()));
+        out.extend(pieces);
+        out.extend(row.iter().skip(col + 1).cloned());
+        out
+    };
+    let mut out = vec![splice(header, new_headers.iter().map(|h| Value::String(h.to_string())).collect())];
+    for row in &table[1..] {
+        let text = cell_key(row.get(col));
+        let mut pieces: Vec<Value> =
+            text.splitn(new_headers.len(), delimiter).map(|piece| Value::String(piece.trim().to_string())).collect();
+        pieces.resize(new_headers.len(), Value::String(String::new()));
+        out.push(splice(row, pieces));
+    }
+    Ok(out)
+}
+
+// Convert a reshaped table to the Vec<String> rows the write functions take
+pub fn to_string_rows(table: &[Vec<Value>]) -> Vec<Vec<String>> {
+    table
+        .iter()
+        .map(|row| row.iter().map(|cell| cell_key(Some(cell))).collect())
+        .collect()
+}
@@ -0,0 +1,89 @@
+use crate::api::v4;
+use crate::drive::FILES_URL;
+use crate::SecretString;
+use crate::SheetsError;
+use chrono::{DateTime, Utc};
+use reqwest::{Client, Method};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+pub const XLSX_MIME: &str = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
+pub const CSV_MIME: &str = "text/csv"; // First tab only
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RevisionUser {
+    pub display_name: Option<String>,
+    pub email_address: Option<String>,
+}
+
+// One saved version of a spreadsheet. Sheets merges edits made close
+// together, so this isn't one revision per write.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Revision {
+    pub id: String,
+    pub modified_time: Option<DateTime<Utc>>,
+    pub last_modifying_user: Option<RevisionUser>,
+    #[serde(default)]
+    pub export_links: BTreeMap<String, String>, // MIME type -> download URL
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RevisionList {
+    #[serde(default)]
+    revisions: Vec<Revision>,
+    next_page_token: Option<String>,
+}
+
+// Oldest first, as Drive returns them. Needs DRIVE_METADATA_SCOPE or wider.
+pub async fn list_revisions(access_token: &SecretString, spreadsheet_id: &str) -> Result<Vec<Revision>, SheetsError> {
+    let mut revisions = Vec::new();
+    let mut page_token: Option<String> = None;
+    loop {
+        let mut params = vec![
+            ("fields", "nextPageToken,revisions(id,modifiedTime,lastModifyingUser(displayName,emailAddress),exportLinks)".to_string()),
+            ("pageSize", "200".to_string()),
+        ];
+        if let Some(token) = &page_token {
+            params.push(("pageToken", token.clone()));
+        }
+        let url = reqwest::Url::parse_with_params(&format!("{}/{}/revisions", FILES_URL, spreadsheet_id), &params).map_err(|e| SheetsError::Invalid(e.to_string()))?;
+        let response = crate::api::v4::send(access_token, Method::GET, url.as_str(), None).await?;
+        let page: RevisionList = serde_json::from_value(response)?;
+        revisions.extend(page.revisions);
+        match page.next_page_token {
+            Some(token) => page_token = Some(token),
+            None => return Ok(revisions),
+        }
+    }
+}
+
+// Last revision saved before `at`, e.g. the moment a bad job started
+pub fn revision_before(revisions: &[Revision], at: DateTime<Utc>) -> Option<&Revision> {
+    revisions
+        .iter()
+        .filter(|r| r.modified_time.is_some_and(|t| t < at))
+        .max_by_key(|r| r.modified_time)
+}
+
+// Download a revision's content as `mime` (XLSX_MIME keeps every tab).
+// Drive can't roll a Google Sheet back through the API; restore by hand from
+// File > Version history, or re-import the exported copy.
+pub async fn export_revision(
+    access_token: &SecretString,
+    revision: &Revision,
+    mime: &str,
+) -> Result<Vec<u8>, SheetsError> {
+    let link = revision
+        .export_links
+        .get(mime)
+        .ok_or_else(|| SheetsError::NotFound(format!("revision {} can't be exported as {}", revision.id, mime)))?;
+    let bytes = v4::dispatch(Client::new().get(link).bearer_auth(access_token.expose_secret()))
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    Ok(bytes.to_vec())
+}
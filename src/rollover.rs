@@ -0,0 +1,135 @@
+use crate::a1::A1Range;
+use crate::metadata::{self, SpreadsheetMetadata};
+use crate::{api, append_rows_to_google_sheet, config, fetch_values, SecretString, SheetsError};
+use reqwest::Method;
+use serde_json::{json, Value};
+
+// Large tabs get slow long before the 10M-cell spreadsheet limit, so bulk
+// imports can cap each tab and continue in "Sheet1 (2)", "Sheet1 (3)", ...
+// Note the spreadsheet-wide cell limit still applies across the whole family.
+#[derive(Debug, Clone, Copy)]
+pub struct RolloverPolicy {
+    pub max_rows_per_tab: usize, // Including the header row
+}
+
+// Tab name for member `n` of a family (1 = the base tab itself)
+pub fn family_tab_name(base: &str, n: usize) -> String {
+    if n <= 1 {
+        base.to_string()
+    } else {
+        format!("{} ({})", base, n)
+    }
+}
+
+// Member number of `title` in the family of `base`, if it belongs to it
+fn family_member(base: &str, title: &str) -> Option<usize> {
+    if title == base {
+        return Some(1);
+    }
+    let rest = title.strip_prefix(base)?.strip_prefix(" (")?.strip_suffix(')')?;
+    rest.parse().ok().filter(|n| *n >= 2)
+}
+
+// Existing tabs of the family in order: base, base (2), base (3), ...
+pub fn tab_family(metadata: &SpreadsheetMetadata, base: &str) -> Vec<String> {
+    let mut members: Vec<(usize, String)> = metadata
+        .sheets
+        .iter()
+        .filter_map(|s| family_member(base, &s.title).map(|n| (n, s.title.clone())))
+        .collect();
+    members.sort();
+    members.into_iter().map(|(_, title)| title).collect()
+}
+
+// Quote a tab name for use in an A1 range
+pub fn quote_sheet(name: &str) -> String {
+    format!("'{}'", name.replace('\'', "''"))
+}
+
+// Append `rows` to the last tab of the family, opening new tabs with the same
+// header once a tab reaches the policy's row cap. Returns (tab, rows written).
+pub async fn append_rows_with_rollover(
+    access_token: &SecretString,
+    base_sheet: &str,
+    rows: Vec<Vec<String>>,
+    policy: RolloverPolicy,
+) -> Result<Vec<(String, usize)>, SheetsError> {
+    if policy.max_rows_per_tab < 2 {
+        return Err(SheetsError::Invalid("max_rows_per_tab must leave room for the header and at least one row".to_string()));
+    }
+    let metadata = metadata::spreadsheet_metadata(access_token).await?;
+    let mut family = tab_family(&metadata, base_sheet);
+    if family.is_empty() {
+        return Err(SheetsError::NotFound(format!("no tab named '{}'", base_sheet)));
+    }
+
+    let header: Vec<String> = fetch_values(access_token, &format!("{}!1:1", quote_sheet(base_sheet)))
+        .await?
+        .into_iter()
+        .next()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|cell| cell.as_str().map(str::to_string).unwrap_or_else(|| cell.to_string()))
+        .collect();
+
+    let mut current = family.pop().unwrap_or_else(|| base_sheet.to_string());
+    let mut used = fetch_values(access_token, &format!("{}!A:A", quote_sheet(&current)))
+        .await?
+        .len();
+    let mut next_member = family_member(base_sheet, &current).unwrap_or(1) + 1;
+
+    let mut written = Vec::new();
+    let mut remaining = rows.into_iter().peekable();
+    while remaining.peek().is_some() {
+        if used >= policy.max_rows_per_tab {
+            current = family_tab_name(base_sheet, next_member);
+            next_member += 1;
+            add_tab_with_header(access_token, &current, &header).await?;
+            used = 1;
+        }
+        let room = policy.max_rows_per_tab - used;
+        let batch: Vec<Vec<String>> = remaining.by_ref().take(room).collect();
+        let count = append_rows_to_google_sheet(access_token, &quote_sheet(&current), batch).await?;
+        used += count;
+        written.push((current.clone(), count));
+    }
+    Ok(written)
+}
+
+// Create a tab and copy the family header into row 1
+pub(crate) async fn add_tab_with_header(
+    access_token: &SecretString,
+    title: &str,
+    header: &[String],
+) -> Result<(), SheetsError> {
+    let sheet_id = config::sheet_id()?;
+
+    api::v4::batch_update(access_token, vec![json!({ "addSheet": { "properties": { "title": title } } })]).await?;
+    metadata::invalidate_metadata();
+
+    if !header.is_empty() {
+        let url = format!(
+            "https://sheets.googleapis.com/v4/spreadsheets/{}/values/{}?valueInputOption=RAW",
+            sheet_id,
+            A1Range::sheet(title).cell(0, 1).encoded()
+        );
+        api::v4::send(access_token, Method::PUT, &url, Some(&json!({ "values": [header] }))).await?;
+    }
+    println!(" Created tab '{}'", title);
+    Ok(())
+}
+
+// Read every tab of the family as one table; the header is kept once
+pub async fn read_tab_family(
+    access_token: &SecretString,
+    base_sheet: &str,
+) -> Result<Vec<Vec<Value>>, SheetsError> {
+    let metadata = metadata::spreadsheet_metadata(access_token).await?;
+    let mut rows = Vec::new();
+    for (i, tab) in tab_family(&metadata, base_sheet).iter().enumerate() {
+        let values = fetch_values(access_token, &quote_sheet(tab)).await?;
+        let skip = if i == 0 { 0 } else { 1 };
+        rows.extend(values.into_iter().skip(skip));
+    }
+    Ok(rows)
+}
@@ -0,0 +1,70 @@
+use crate::metadata;
+use crate::rollover::{add_tab_with_header, quote_sheet};
+use crate::{append_rows_to_google_sheet, SecretString, SheetsError};
+use chrono::{Duration, NaiveDate};
+use std::collections::BTreeMap;
+
+// Send each appended row to a tab named after its date column,
+// e.g. tab_format "RETURNS %Y-%m" puts 2024-05-17 into "RETURNS 2024-05"
+#[derive(Debug, Clone)]
+pub struct MonthRouting {
+    pub tab_format: String, // chrono format string for the tab name
+    pub date_column: usize, // zero-based column holding the date
+    pub template_header: Vec<String>, // header written into newly created tabs
+}
+
+impl MonthRouting {
+    pub fn tab_for(&self, row: &[String]) -> Result<String, String> {
+        let cell = row
+            .get(self.date_column)
+            .map(|c| c.trim())
+            .filter(|c| !c.is_empty())
+            .ok_or_else(|| format!("row has no date in column {}", self.date_column + 1))?;
+        let date = parse_date(cell).ok_or_else(|| format!("cannot read '{}' as a date", cell))?;
+        Ok(date.format(&self.tab_format).to_string())
+    }
+}
+
+// ISO dates (optionally with time), UK-style dd/mm/yyyy, and Sheets serial day numbers
+pub fn parse_date(text: &str) -> Option<NaiveDate> {
+    let text = text.trim();
+    let date_part = text.split(['T', ' ']).next().unwrap_or(text);
+    if let Ok(date) = NaiveDate::parse_from_str(date_part, "%Y-%m-%d") {
+        return Some(date);
+    }
+    for format in ["%d/%m/%Y", "%d-%m-%Y", "%d.%m.%Y"] {
+        if let Ok(date) = NaiveDate::parse_from_str(date_part, format) {
+            return Some(date);
+        }
+    }
+    // Serial numbers count days from 1899-12-30
+    let serial: f64 = text.parse().ok()?;
+    if !(1.0..=2_958_465.0).contains(&serial) {
+        return None;
+    }
+    NaiveDate::from_ymd_opt(1899, 12, 30)?.checked_add_signed(Duration::days(serial.trunc() as i64))
+}
+
+// Group rows by destination tab, create missing tabs with the template header,
+// then append each group. Returns (tab, rows appended) in tab-name order.
+pub async fn append_routed(
+    access_token: &SecretString,
+    routing: &MonthRouting,
+    rows: Vec<Vec<String>>,
+) -> Result<Vec<(String, usize)>, SheetsError> {
+    let mut groups: BTreeMap<String, Vec<Vec<String>>> = BTreeMap::new();
+    for row in rows {
+        groups.entry(routing.tab_for(&row).map_err(SheetsError::Invalid)?).or_default().push(row);
+    }
+
+    let mut results = Vec::new();
+    for (tab, rows) in groups {
+        let metadata = metadata::spreadsheet_metadata(access_token).await?;
+        if metadata.sheet(&tab).is_none() {
+            add_tab_with_header(access_token, &tab, &routing.template_header).await?;
+        }
+        let count = append_rows_to_google_sheet(access_token, &quote_sheet(&tab), rows).await?;
+        results.push((tab, count));
+    }
+    Ok(results)
+}
@@ -0,0 +1,14 @@
+// Helpers for treating a sheet row as named fields
+
+// Header text -> identifier-friendly key ("CHANNEL VLOOKUP" -> channel_vlookup)
+pub fn column_key(name: &str) -> String {
+    let mut out = String::new();
+    for c in name.trim().chars() {
+        if c.is_alphanumeric() {
+            out.extend(c.to_lowercase());
+        } else if !out.ends_with('_') && !out.is_empty() {
+            out.push('_');
+        }
+    }
+    out.trim_end_matches('_').to_string()
+}
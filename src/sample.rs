@@ -0,0 +1,132 @@
+use crate::a1::{range_start, range_width, A1Range};
+use crate::metadata::spreadsheet_metadata;
+use crate::{batch_get_values, fetch_values, SecretString, SheetsError};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+
+// Ranges per values:batchGet while sampling; keeps the URL well under limits
+const RANGES_PER_REQUEST: usize = 100;
+// Sampled rows can be blank grid rows below the data; draw again this often
+const MAX_ROUNDS: usize = 4;
+
+// A quick look at a big tab without downloading it: the header (the first row
+// of the range) and some rows below it, with their 1-based row numbers
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RowSample {
+    pub header: Vec<Value>,
+    pub rows: Vec<(usize, Vec<Value>)>,
+}
+
+// A tab name or A1 range, as rows to read
+struct Bounds {
+    sheet: String,
+    column: usize,
+    header_row: usize,
+    last_row: Option<usize>,
+    width: Option<usize>, // None = whole rows
+}
+
+impl Bounds {
+    fn parse(range: &str) -> Result<Self, String> {
+        let parsed = A1Range::parse(range)?;
+        let sheet = parsed.sheet_name().ok_or_else(|| format!("'{}' needs a tab name", range))?.to_string();
+        if !range.contains('!') {
+            return Ok(Bounds { sheet, column: 0, header_row: 1, last_row: None, width: None });
+        }
+        let (column, header_row) = range_start(range);
+        // The digits of the end cell, if it has any ("A2:J" is open-ended)
+        let last_row = range.rsplit_once('!').and_then(|(_, cells)| cells.split_once(':')).and_then(|(_, end)| {
+            end.trim_start_matches(|c: char| c.is_ascii_alphabetic() || c == '$').parse().ok()
+        });
+        Ok(Bounds { sheet, column, header_row, last_row, width: range_width(range) })
+    }
+
+    fn rows(&self, first: usize, last: usize) -> String {
+        let range = A1Range::sheet(&self.sheet);
+        match self.width {
+            Some(width) => range.cell(self.column, first).to(self.column + width - 1, last),
+            None => range.rows(first, last),
+        }
+        .to_string()
+    }
+}
+
+// The header and the first `n` rows under it, in one bounded read
+pub async fn preview(access_token: &SecretString, range: &str, n: usize) -> Result<RowSample, SheetsError> {
+    let bounds = Bounds::parse(range).map_err(SheetsError::Invalid)?;
+    let last = bounds.last_row.map_or(bounds.header_row + n, |last| last.min(bounds.header_row + n));
+    let mut rows = fetch_values(access_token, &bounds.rows(bounds.header_row, last)).await?.into_iter();
+    let header = rows.next().unwrap_or_default();
+    Ok(RowSample { header, rows: rows.enumerate().map(|(i, row)| (bounds.header_row + 1 + i, row)).collect() })
+}
+
+// The header and `n` rows picked at random below it, read one row range each
+// (batched), in sheet order. The same seed picks the same rows while the tab
+// keeps its size. The tab's grid size bounds the draw, so with many blank
+// rows under the data fewer than `n` rows may come back.
+pub async fn sample(access_token: &SecretString, range: &str, n: usize, seed: u64) -> Result<RowSample, SheetsError> {
+    let bounds = Bounds::parse(range).map_err(SheetsError::Invalid)?;
+    let header = fetch_values(access_token, &bounds.rows(bounds.header_row, bounds.header_row)).await?.into_iter().next().unwrap_or_default();
+    let last = match bounds.last_row {
+        Some(last) => last,
+        None => {
+            let metadata = spreadsheet_metadata(access_token).await?;
+            let tab = metadata.sheet(&bounds.sheet).ok_or_else(|| SheetsError::NotFound(format!("no tab named '{}'", bounds.sheet)))?;
+            tab.grid_properties.as_ref().map_or(0, |grid| grid.row_count as usize)
+        }
+    };
+    let first = bounds.header_row + 1;
+    let total = (last + 1).saturating_sub(first);
+
+    let mut rng = SplitMix64(seed);
+    let mut tried = HashSet::new();
+    let mut found = BTreeMap::new();
+    for _ in 0..MAX_ROUNDS {
+        let needed = n.saturating_sub(found.len());
+        if needed == 0 || tried.len() == total {
+            break;
+        }
+        let mut picks = Vec::new();
+        if total - tried.len() <= needed {
+            picks.extend((first..=last).filter(|row| !tried.contains(row)));
+        } else {
+            while picks.len() < needed {
+                let row = first + rng.below(total as u64) as usize;
+                if tried.insert(row) {
+                    picks.push(row);
+                }
+            }
+        }
+        tried.extend(picks.iter().copied());
+        picks.sort_unstable();
+        for chunk in picks.chunks(RANGES_PER_REQUEST) {
+            let ranges: Vec<String> = chunk.iter().map(|&row| bounds.rows(row, row)).collect();
+            let ranges: Vec<&str> = ranges.iter().map(String::as_str).collect();
+            for (&row, values) in chunk.iter().zip(batch_get_values(access_token, &ranges).await?) {
+                if let Some(values) = values.into_iter().next().filter(|cells| !cells.is_empty()) {
+                    found.insert(row, values);
+                }
+            }
+        }
+    }
+    Ok(RowSample { header, rows: found.into_iter().take(n).collect() })
+}
+
+// Small seeded generator; the sample only has to be spread out and repeatable
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in 0..bound (bound > 0); the modulo bias is negligible at sheet sizes
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+}
@@ -0,0 +1,83 @@
+use crate::numbers::{parse_number, NumberLocale};
+use std::env;
+
+// How a column's cells should be interpreted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Text,
+    Number(NumberLocale),
+    Currency(NumberLocale), // Number that holds money
+    Date,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnSpec {
+    pub name: String, // Header text, compared case-insensitively
+    pub column_type: ColumnType,
+}
+
+// Per-column types, e.g. SHEET_SCHEMA="AMOUNT:currency(eu); QTY:number; RETURN DATE:date"
+// Reads (export_filtered, PagedReader::filtered, `sheets summarize`) use it for
+// filter comparisons, computed columns and totals
+#[derive(Debug, Clone, Default)]
+pub struct Schema {
+    pub columns: Vec<ColumnSpec>,
+}
+
+impl Schema {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut columns = Vec::new();
+        for part in spec.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+            let (name, kind) = part
+                .rsplit_once(':')
+                .ok_or_else(|| format!("schema entry '{}' must look like COLUMN:type", part))?;
+            columns.push(ColumnSpec {
+                name: name.trim().to_string(),
+                column_type: parse_type(kind.trim()).ok_or_else(|| format!("unknown column type '{}' for '{}'", kind.trim(), name.trim()))?,
+            });
+        }
+        Ok(Schema { columns })
+    }
+
+    // From SHEET_SCHEMA; unset means every column is text
+    pub fn from_env() -> Result<Self, String> {
+        match env::var("SHEET_SCHEMA") {
+            Ok(spec) => Schema::parse(&spec),
+            Err(_) => Ok(Schema::default()),
+        }
+    }
+
+    pub fn column_type(&self, name: &str) -> ColumnType {
+        self.columns
+            .iter()
+            .find(|c| c.name.eq_ignore_ascii_case(name.trim()))
+            .map_or(ColumnType::Text, |c| c.column_type)
+    }
+
+    // Parse a cell of `column` as a number using the column's locale (Auto if untyped)
+    pub fn parse_number(&self, column: &str, text: &str) -> Option<f64> {
+        parse_number(text, self.locale(column))
+    }
+
+    pub fn locale(&self, column: &str) -> NumberLocale {
+        match self.column_type(column) {
+            ColumnType::Number(locale) | ColumnType::Currency(locale) => locale,
+            _ => NumberLocale::Auto,
+        }
+    }
+}
+
+// "number", "currency", "number(eu)", "currency(dot)", "date", "text"
+fn parse_type(kind: &str) -> Option<ColumnType> {
+    let (base, arg) = match kind.split_once('(') {
+        Some((base, rest)) => (base.trim(), rest.strip_suffix(')')?.trim()),
+        None => (kind, ""),
+    };
+    match base.to_ascii_lowercase().as_str() {
+        "text" | "string" => Some(ColumnType::Text),
+        "number" => Some(ColumnType::Number(NumberLocale::parse(arg)?)),
+        "currency" | "money" => Some(ColumnType::Currency(NumberLocale::parse(arg)?)),
+        "date" => Some(ColumnType::Date),
+        _ => None,
+    }
+}
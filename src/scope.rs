@@ -0,0 +1,73 @@
+use crate::config::ConfigError;
+use crate::drive::{DRIVE_FILE_SCOPE, DRIVE_SCOPE};
+use crate::{SHEETS_READONLY_SCOPE, SHEETS_SCOPE};
+use std::env;
+use std::fmt;
+
+// OAuth scopes that reach the Sheets API, narrowest first. Tokens are minted
+// for whatever the credentials carry (Credentials::with_scopes, or
+// SHEETS_SCOPES), so a reporting job can run with a token that can't write.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Scope {
+    SpreadsheetsReadonly,
+    #[default]
+    Spreadsheets,
+    // Only files this app created or the user opened with it
+    DriveFile,
+    // Every file the account can see, including Drive calls (see drive)
+    Drive,
+}
+
+impl Scope {
+    pub fn url(self) -> &'static str {
+        match self {
+            Scope::SpreadsheetsReadonly => SHEETS_READONLY_SCOPE,
+            Scope::Spreadsheets => SHEETS_SCOPE,
+            Scope::DriveFile => DRIVE_FILE_SCOPE,
+            Scope::Drive => DRIVE_SCOPE,
+        }
+    }
+
+    pub fn allows_writes(self) -> bool {
+        self != Scope::SpreadsheetsReadonly
+    }
+
+    // "spreadsheets.readonly", "spreadsheets", "drive.file", "drive", or the full URL
+    pub fn parse(name: &str) -> Option<Self> {
+        let name = name.trim();
+        let name = name.strip_prefix("https://www.googleapis.com/auth/").unwrap_or(name);
+        match name.to_ascii_lowercase().as_str() {
+            "spreadsheets.readonly" | "readonly" => Some(Scope::SpreadsheetsReadonly),
+            "spreadsheets" => Some(Scope::Spreadsheets),
+            "drive.file" => Some(Scope::DriveFile),
+            "drive" => Some(Scope::Drive),
+            _ => None,
+        }
+    }
+
+    // SHEETS_SCOPES, comma or space separated; Spreadsheets when unset
+    pub fn from_env() -> Result<Vec<Self>, ConfigError> {
+        let Ok(list) = env::var("SHEETS_SCOPES") else { return Ok(vec![Scope::default()]) };
+        let scopes = list
+            .split([',', ' '])
+            .filter(|name| !name.trim().is_empty())
+            .map(|name| {
+                Scope::parse(name).ok_or_else(|| ConfigError::Invalid {
+                    var: "SHEETS_SCOPES".to_string(),
+                    reason: format!("unknown scope '{}' (expected spreadsheets.readonly, spreadsheets, drive.file or drive)", name.trim()),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(if scopes.is_empty() { vec![Scope::default()] } else { scopes })
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.url().trim_start_matches("https://www.googleapis.com/auth/"))
+    }
+}
+
+pub fn scope_urls(scopes: &[Scope]) -> Vec<&'static str> {
+    scopes.iter().map(|scope| scope.url()).collect()
+}
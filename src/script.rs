@@ -0,0 +1,71 @@
+use crate::row::column_key;
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+
+// Row filters and derived columns written as rhai expressions, e.g.
+//   row.amount > 100 && row.channel == "DEBENHAMS"
+//   row.gross - row.refund
+// Fields are the snake_case header names; numeric-looking cells are numbers
+// and TRUE/FALSE cells are booleans, everything else is a string.
+pub struct RowScript {
+    engine: Engine,
+    ast: AST,
+}
+
+impl RowScript {
+    pub fn compile(expression: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(100_000); // Stop runaway scripts
+        let ast = engine
+            .compile_expression(expression)
+            .map_err(|e| format!("invalid expression '{}': {}", expression, e))?;
+        Ok(RowScript { engine, ast })
+    }
+
+    pub fn eval(&self, header: &[String], row: &[String]) -> Result<Dynamic, Box<dyn std::error::Error>> {
+        let mut scope = Scope::new();
+        scope.push("row", row_map(header, row));
+        self.engine
+            .eval_ast_with_scope::<Dynamic>(&mut scope, &self.ast)
+            .map_err(|e| e.to_string().into())
+    }
+
+    // Use as a filter: the expression must produce a boolean
+    pub fn matches(&self, header: &[String], row: &[String]) -> Result<bool, Box<dyn std::error::Error>> {
+        let value = self.eval(header, row)?;
+        value
+            .as_bool()
+            .map_err(|found| format!("filter expression returned {} instead of a boolean", found).into())
+    }
+
+    // Use as a derived column: the result is written as text
+    pub fn eval_string(&self, header: &[String], row: &[String]) -> Result<String, Box<dyn std::error::Error>> {
+        let value = self.eval(header, row)?;
+        Ok(if value.is_unit() { String::new() } else { value.to_string() })
+    }
+}
+
+fn row_map(header: &[String], row: &[String]) -> Map {
+    header
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let cell = row.get(i).map(String::as_str).unwrap_or_default();
+            (column_key(name).into(), cell_dynamic(cell))
+        })
+        .collect()
+}
+
+fn cell_dynamic(cell: &str) -> Dynamic {
+    let trimmed = cell.trim();
+    if let Ok(n) = trimmed.parse::<i64>() {
+        return Dynamic::from(n);
+    }
+    if let Ok(f) = trimmed.parse::<f64>() {
+        return Dynamic::from(f);
+    }
+    match trimmed {
+        "TRUE" | "true" => Dynamic::from(true),
+        "FALSE" | "false" => Dynamic::from(false),
+        _ => Dynamic::from(cell.to_string()),
+    }
+}
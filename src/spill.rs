@@ -0,0 +1,152 @@
+use serde_json::Value;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Rough per-cell overhead of a serde_json::Value in a Vec, on top of its text
+const CELL_OVERHEAD: usize = 32;
+
+static SPILL_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+// Approximate heap size of a row
+pub fn row_size(row: &[Value]) -> usize {
+    row.iter()
+        .map(|cell| {
+            CELL_OVERHEAD
+                + match cell {
+                    Value::String(s) => s.len(),
+                    Value::Null | Value::Bool(_) | Value::Number(_) => 0,
+                    other => other.to_string().len(),
+                }
+        })
+        .sum()
+}
+
+// Collects rows in memory until `max_memory` bytes (approximate), then moves
+// them and everything after to a JSON-lines temp file. The file is removed
+// when the buffer is dropped.
+pub struct SpillBuffer {
+    max_memory: usize,
+    rows: Vec<Vec<Value>>,
+    bytes: usize,
+    spill: Option<(PathBuf, BufWriter<File>)>,
+    len: usize,
+}
+
+impl SpillBuffer {
+    pub fn new(max_memory: usize) -> Self {
+        SpillBuffer { max_memory, rows: Vec::new(), bytes: 0, spill: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn spilled(&self) -> bool {
+        self.spill.is_some()
+    }
+
+    pub fn push(&mut self, row: Vec<Value>) -> io::Result<()> {
+        self.len += 1;
+        if let Some((_, writer)) = &mut self.spill {
+            return write_row(writer, &row);
+        }
+        self.bytes += row_size(&row);
+        self.rows.push(row);
+        if self.bytes > self.max_memory {
+            self.spill_to_disk()?;
+        }
+        Ok(())
+    }
+
+    fn spill_to_disk(&mut self) -> io::Result<()> {
+        let n = SPILL_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("google-sheet-spill-{}-{}.jsonl", std::process::id(), n));
+        let mut writer = BufWriter::new(create_private(&path)?);
+        for row in self.rows.drain(..) {
+            write_row(&mut writer, &row)?;
+        }
+        self.rows.shrink_to_fit();
+        self.bytes = 0;
+        self.spill = Some((path, writer));
+        Ok(())
+    }
+
+    // Every row in push order, reading back from disk if the budget was exceeded
+    pub fn into_rows(mut self) -> io::Result<Box<dyn Iterator<Item = io::Result<Vec<Value>>>>> {
+        let Some((path, mut writer)) = self.spill.take() else {
+            return Ok(Box::new(std::mem::take(&mut self.rows).into_iter().map(Ok)));
+        };
+        writer.flush()?;
+        drop(writer);
+        let reader = BufReader::new(File::open(&path)?);
+        let guard = SpillFile(path);
+        Ok(Box::new(reader.lines().map(move |line| {
+            let _keep_until_done = &guard;
+            serde_json::from_str(&line?).map_err(io::Error::other)
+        })))
+    }
+}
+
+impl Drop for SpillBuffer {
+    fn drop(&mut self) {
+        if let Some((path, _)) = self.spill.take() {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+// Deletes the spill file once the reading iterator is dropped
+struct SpillFile(PathBuf);
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+// The temp dir is shared, so the rows are readable by this user only, and
+// create_new refuses a file (or symlink) someone else put there first
+fn create_private(path: &Path) -> io::Result<File> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+fn write_row(writer: &mut BufWriter<File>, row: &[Value]) -> io::Result<()> {
+    serde_json::to_writer(&mut *writer, row).map_err(io::Error::other)?;
+    writer.write_all(b"\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spilled_rows_come_back_in_order() {
+        let mut buffer = SpillBuffer::new(100);
+        for n in 0..10 {
+            buffer.push(vec![Value::from(format!("row {}", n))]).unwrap();
+        }
+        assert!(buffer.spilled());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let (path, _) = buffer.spill.as_ref().unwrap();
+            assert_eq!(fs::metadata(path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+        let rows: Vec<_> = buffer.into_rows().unwrap().map(Result::unwrap).collect();
+        assert_eq!(rows.len(), 10);
+        assert_eq!(rows[9], vec![Value::from("row 9")]);
+    }
+}
@@ -0,0 +1,130 @@
+use std::fmt;
+use std::str::FromStr;
+
+// A spreadsheet ID plus the tab (gid) when one was given in the URL
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SpreadsheetId {
+    id: String,
+    gid: Option<u64>,
+}
+
+impl SpreadsheetId {
+    pub fn new(id: &str) -> Result<Self, String> {
+        let id = id.trim();
+        if id.len() < 10 || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(format!("'{}' is not a valid spreadsheet ID", id));
+        }
+        Ok(SpreadsheetId { id: id.to_string(), gid: None })
+    }
+
+    // Accepts a bare ID or any Sheets/Drive URL form:
+    //   https://docs.google.com/spreadsheets/d/<id>/edit#gid=123
+    //   https://docs.google.com/spreadsheets/u/1/d/<id>/edit?gid=123
+    //   https://docs.google.com/spreadsheet/ccc?key=<id>        (legacy)
+    //   https://drive.google.com/open?id=<id>
+    pub fn from_url(input: &str) -> Result<Self, String> {
+        let input = input.trim();
+        if !input.contains('/') && !input.contains('?') {
+            return SpreadsheetId::new(input);
+        }
+
+        let (path_and_query, fragment) = input.split_once('#').unwrap_or((input, ""));
+        let (path, query) = path_and_query.split_once('?').unwrap_or((path_and_query, ""));
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+        if path.contains("/d/e/") {
+            return Err("published (/d/e/...) links don't expose the spreadsheet ID; copy the editor URL instead".to_string());
+        }
+
+        let from_path = segments
+            .windows(2)
+            .find(|pair| pair[0] == "d")
+            .map(|pair| pair[1].to_string());
+        let from_query = || param(query, "key").or_else(|| param(query, "id"));
+        let id = from_path
+            .or_else(from_query)
+            .ok_or_else(|| format!("no spreadsheet ID found in '{}'", input))?;
+
+        let mut parsed = SpreadsheetId::new(&id)?;
+        let gid = param(fragment, "gid").or_else(|| param(query, "gid"));
+        if let Some(gid) = gid {
+            parsed.gid = Some(gid.parse().map_err(|_| format!("gid '{}' is not a number", gid))?);
+        }
+        Ok(parsed)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.id
+    }
+
+    pub fn gid(&self) -> Option<u64> {
+        self.gid
+    }
+
+    pub fn with_gid(mut self, gid: u64) -> Self {
+        self.gid = Some(gid);
+        self
+    }
+}
+
+fn param(query: &str, name: &str) -> Option<String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.to_string())
+}
+
+impl fmt::Display for SpreadsheetId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.id)
+    }
+}
+
+impl FromStr for SpreadsheetId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SpreadsheetId::from_url(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "1BxiMVs0XRA5nFMdKvBdBZjgmUUqptlbs74OgvE2upms";
+
+    #[test]
+    fn bare_ids() {
+        let id = SpreadsheetId::from_url(&format!("  {}\n", ID)).unwrap();
+        assert_eq!((id.as_str(), id.gid()), (ID, None));
+        assert!(SpreadsheetId::from_url("short").is_err());
+        assert!(SpreadsheetId::from_url("has spaces in it here").is_err());
+    }
+
+    #[test]
+    fn url_forms() {
+        let parse = |url: String| SpreadsheetId::from_url(&url).map(|id| (id.as_str().to_string(), id.gid()));
+        let id = ID.to_string();
+        assert_eq!(parse(format!("https://docs.google.com/spreadsheets/d/{}/edit#gid=123", ID)), Ok((id.clone(), Some(123))));
+        assert_eq!(parse(format!("https://docs.google.com/spreadsheets/u/1/d/{}/edit?gid=7", ID)), Ok((id.clone(), Some(7))));
+        assert_eq!(parse(format!("https://docs.google.com/spreadsheets/d/{}", ID)), Ok((id.clone(), None)));
+        assert_eq!(parse(format!("https://docs.google.com/spreadsheet/ccc?key={}&usp=sharing", ID)), Ok((id.clone(), None)));
+        assert_eq!(parse(format!("https://drive.google.com/open?id={}", ID)), Ok((id, None)));
+    }
+
+    #[test]
+    fn unusable_urls() {
+        assert!(SpreadsheetId::from_url("https://docs.google.com/spreadsheets/d/e/2PACX-1vQabcdefghij/pubhtml").unwrap_err().contains("published"));
+        assert!(SpreadsheetId::from_url("https://docs.google.com/spreadsheets/").unwrap_err().contains("no spreadsheet ID"));
+        assert!(SpreadsheetId::from_url(&format!("https://docs.google.com/spreadsheets/d/{}/edit#gid=abc", ID)).unwrap_err().contains("not a number"));
+    }
+
+    #[test]
+    fn parses_with_from_str() {
+        let id: SpreadsheetId = format!("https://docs.google.com/spreadsheets/d/{}/edit#gid=5", ID).parse().unwrap();
+        assert_eq!(id, SpreadsheetId::new(ID).unwrap().with_gid(5));
+        assert_eq!(id.to_string(), ID);
+    }
+}
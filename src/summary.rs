@@ -0,0 +1,100 @@
+use serde::Serialize;
+use std::env;
+use std::fs;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Instant;
+
+// Process-wide counters behind the end-of-run summary
+static ROWS_READ: AtomicU64 = AtomicU64::new(0);
+static ROWS_MATCHED: AtomicU64 = AtomicU64::new(0);
+static ROWS_WRITTEN: AtomicU64 = AtomicU64::new(0);
+static ROWS_DELETED: AtomicU64 = AtomicU64::new(0);
+static API_CALLS: AtomicU64 = AtomicU64::new(0);
+static RETRIES: AtomicU64 = AtomicU64::new(0);
+static ERRORS: AtomicU64 = AtomicU64::new(0);
+
+fn started() -> &'static Instant {
+    static STARTED: OnceLock<Instant> = OnceLock::new();
+    STARTED.get_or_init(Instant::now)
+}
+
+// Call at startup so the duration covers the whole run
+pub fn start() {
+    started();
+}
+
+pub fn rows_read(n: usize) {
+    ROWS_READ.fetch_add(n as u64, Ordering::Relaxed);
+}
+
+pub fn rows_matched(n: usize) {
+    ROWS_MATCHED.fetch_add(n as u64, Ordering::Relaxed);
+}
+
+pub fn rows_written(n: usize) {
+    ROWS_WRITTEN.fetch_add(n as u64, Ordering::Relaxed);
+}
+
+pub fn rows_deleted(n: usize) {
+    ROWS_DELETED.fetch_add(n as u64, Ordering::Relaxed);
+}
+
+// One HTTP request sent, retries included
+pub fn api_call() {
+    API_CALLS.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn retry() {
+    RETRIES.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn error() {
+    ERRORS.fetch_add(1, Ordering::Relaxed);
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunSummary {
+    pub command: String,
+    pub ok: bool,
+    pub rows_read: u64,
+    pub rows_matched: u64,
+    pub rows_written: u64,
+    pub rows_deleted: u64,
+    pub api_calls: u64,
+    pub retries: u64,
+    pub errors: u64,
+    pub duration_ms: u64,
+}
+
+// Counters so far; `ok` is false once any error was recorded
+pub fn snapshot(command: &str) -> RunSummary {
+    let errors = ERRORS.load(Ordering::Relaxed);
+    RunSummary {
+        command: command.to_string(),
+        ok: errors == 0,
+        rows_read: ROWS_READ.load(Ordering::Relaxed),
+        rows_matched: ROWS_MATCHED.load(Ordering::Relaxed),
+        rows_written: ROWS_WRITTEN.load(Ordering::Relaxed),
+        rows_deleted: ROWS_DELETED.load(Ordering::Relaxed),
+        api_calls: API_CALLS.load(Ordering::Relaxed),
+        retries: RETRIES.load(Ordering::Relaxed),
+        errors,
+        duration_ms: started().elapsed().as_millis() as u64,
+    }
+}
+
+// One JSON line on stderr prefixed "run-summary ", and the same JSON in
+// RUN_SUMMARY_FILE when set. RUN_SUMMARY=0 turns both off.
+pub fn emit(command: &str) {
+    if env::var("RUN_SUMMARY").as_deref() == Ok("0") {
+        return;
+    }
+    let Ok(json) = serde_json::to_string(&snapshot(command)) else { return };
+    eprintln!("run-summary {}", json);
+    if let Some(path) = env::var_os("RUN_SUMMARY_FILE") {
+        if let Err(e) = fs::write(&path, format!("{}\n", json)) {
+            eprintln!("Error writing run summary: {}", e);
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use crate::archive::{move_rows, DEFAULT_BATCH_ROWS};
+use crate::config::{self, ConfigError};
+use crate::metadata;
+use crate::render::RenderOptions;
+use crate::rollover::quote_sheet;
+use crate::row::column_key;
+use crate::{SheetsClient, SheetsError};
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::env;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+// Retention for tabs that only ever grow: rows whose date column is older
+// than the retention period are deleted, or moved to an archive tab first.
+// Meant to run from cron (`sheets sweep`) or sweep_every in a service.
+// Dates are read as serial numbers, so any date format in the sheet works;
+// text cells count as dates only in ISO form (2024-01-31). Rows without a
+// date are kept.
+//
+// Rows are removed in batches through archive::move_rows, which re-reads each
+// batch first: rows inserted, deleted or sorted above them since the read
+// stop the pass instead of removing the wrong rows, and a failed batch is
+// taken out of the archive tab again. More than MAX_DELETE_WITHOUT_CONFIRM
+// expired rows need confirmation like any mass delete; unattended runs opt
+// out with Retention::force (RETENTION_FORCE=1) or `sheets sweep --yes`.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExpiryAction {
+    Delete,
+    // Append to this tab (values only, dates as serial numbers), then delete
+    Archive(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Retention {
+    pub sheet: String,
+    pub date_column: String, // Header text or its snake_case key
+    pub max_age_days: u64,
+    pub action: ExpiryAction,
+    // JSON lines describing every run and the rows it removed
+    pub audit_path: Option<PathBuf>,
+    // Remove any number of expired rows without confirmation
+    pub force: bool,
+}
+
+impl Retention {
+    pub fn new(sheet: &str, date_column: &str, max_age_days: u64) -> Self {
+        Retention {
+            sheet: sheet.to_string(),
+            date_column: date_column.to_string(),
+            max_age_days,
+            action: ExpiryAction::Delete,
+            audit_path: None,
+            force: false,
+        }
+    }
+
+    pub fn archive_to(mut self, tab: &str) -> Self {
+        self.action = ExpiryAction::Archive(tab.to_string());
+        self
+    }
+
+    pub fn audit_to(mut self, path: impl Into<PathBuf>) -> Self {
+        self.audit_path = Some(path.into());
+        self
+    }
+
+    // For cron and services: a backlog of expired rows is swept instead of
+    // failing every pass with ConfirmationRequired
+    pub fn force(mut self) -> Self {
+        self.force = true;
+        self
+    }
+
+    // RETENTION_DATE_COLUMN and RETENTION_DAYS, plus RETENTION_SHEET (default
+    // RETURNS MAIN), RETENTION_ARCHIVE_TAB, RETENTION_AUDIT_PATH and
+    // RETENTION_FORCE (1/true)
+    pub fn from_env() -> Result<Self, ConfigError> {
+        config::load_dotenv()?;
+        let days = config::require("RETENTION_DAYS")?;
+        let max_age_days = days.trim().parse().map_err(|_| ConfigError::Invalid {
+            var: "RETENTION_DAYS".to_string(),
+            reason: format!("'{}' is not a number of days", days),
+        })?;
+        let sheet = env::var("RETENTION_SHEET").unwrap_or_else(|_| "RETURNS MAIN".to_string());
+        let mut retention = Retention::new(&sheet, &config::require("RETENTION_DATE_COLUMN")?, max_age_days);
+        if let Ok(tab) = env::var("RETENTION_ARCHIVE_TAB") {
+            retention = retention.archive_to(tab.trim());
+        }
+        if let Ok(path) = env::var("RETENTION_AUDIT_PATH") {
+            retention = retention.audit_to(path);
+        }
+        if env::var("RETENTION_FORCE").is_ok_and(|flag| matches!(flag.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes")) {
+            retention = retention.force();
+        }
+        Ok(retention)
+    }
+
+    // Rows dated before this day have expired
+    pub fn cutoff(&self, now: DateTime<Utc>) -> NaiveDate {
+        now.date_naive().checked_sub_days(Days::new(self.max_age_days)).unwrap_or(NaiveDate::MIN)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SweptRow {
+    pub row_number: usize,
+    pub date: NaiveDate,
+    pub values: Vec<Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SweepOutcome {
+    pub cutoff: NaiveDate,
+    pub expired: Vec<SweptRow>,
+    pub undated: usize, // Rows kept because their date cell is blank or unreadable
+    pub dry_run: bool,
+}
+
+// One pass. With `dry_run` the expired rows are only reported (and audited).
+pub async fn sweep_expired(client: &SheetsClient, retention: &Retention, dry_run: bool) -> Result<SweepOutcome, SheetsError> {
+    let now = Utc::now();
+    let cutoff = retention.cutoff(now);
+    let rows = client.read_with(&quote_sheet(&retention.sheet), &RenderOptions::unformatted()).await?;
+    let Some((header, data)) = rows.split_first() else {
+        return Ok(SweepOutcome { cutoff, expired: Vec::new(), undated: 0, dry_run });
+    };
+    let wanted = retention.date_column.trim();
+    let column = header
+        .iter()
+        .position(|h| h.as_str().is_some_and(|h| h.trim().eq_ignore_ascii_case(wanted) || column_key(h) == wanted))
+        .ok_or_else(|| SheetsError::NotFound(format!("'{}' has no '{}' column", retention.sheet, wanted)))?;
+
+    let mut outcome = SweepOutcome { cutoff, expired: Vec::new(), undated: 0, dry_run };
+    for (i, row) in data.iter().enumerate() {
+        match row.get(column).and_then(cell_date) {
+            Some(date) if date < cutoff => outcome.expired.push(SweptRow { row_number: i + 2, date, values: row.clone() }),
+            Some(_) => {}
+            None => outcome.undated += 1,
+        }
+    }
+    if !dry_run && !outcome.expired.is_empty() {
+        let client = if retention.force { client.clone().force() } else { client.clone() };
+        let dest = match &retention.action {
+            ExpiryAction::Delete => None,
+            ExpiryAction::Archive(tab) => Some(tab.as_str()),
+        };
+        let rows: Vec<(usize, &Vec<Value>)> = outcome.expired.iter().map(|r| (r.row_number, &r.values)).collect();
+        let moved = move_rows(&client, "sweep", &retention.sheet, &rows, dest, DEFAULT_BATCH_ROWS).await;
+        metadata::invalidate_metadata();
+        if let Err(e) = moved {
+            // Batches before the failure are gone, so they're still audited
+            tracing::warn!("sweeping '{}': {}", retention.sheet, e);
+            outcome.expired.truncate(e.moved);
+            if let Some(path) = &retention.audit_path {
+                audit(path, retention, &outcome, now)?;
+            }
+            return Err(e.cause);
+        }
+    }
+    if let Some(path) = &retention.audit_path {
+        audit(path, retention, &outcome, now)?;
+    }
+    Ok(outcome)
+}
+
+// sweep_expired every `every`. A failed pass is logged and the next one
+// tried on schedule, except ConfirmationRequired: no later pass gets past it
+// without Retention::force, so it's returned instead of repeated forever.
+pub async fn sweep_every(client: &SheetsClient, retention: &Retention, every: Duration) -> SheetsError {
+    loop {
+        match sweep_expired(client, retention, false).await {
+            Ok(outcome) => tracing::info!("swept {} expired row(s) from '{}'", outcome.expired.len(), retention.sheet),
+            Err(e @ SheetsError::Confirmation(_)) => return e,
+            Err(e) => tracing::warn!("sweeping '{}' failed: {}", retention.sheet, e),
+        }
+        tokio::time::sleep(every).await;
+    }
+}
+
+// Serial numbers count days from 1899-12-30; the fraction is the time of day
+fn cell_date(cell: &Value) -> Option<NaiveDate> {
+    match cell {
+        Value::Number(n) => {
+            let days = n.as_f64()?.floor();
+            let epoch = NaiveDate::from_ymd_opt(1899, 12, 30)?;
+            (days >= 0.0).then(|| epoch.checked_add_days(Days::new(days as u64))).flatten()
+        }
+        Value::String(s) => NaiveDate::parse_from_str(s.trim().get(..10)?, "%Y-%m-%d").ok(),
+        _ => None,
+    }
+}
+
+fn audit(path: &PathBuf, retention: &Retention, outcome: &SweepOutcome, at: DateTime<Utc>) -> std::io::Result<()> {
+    let action = match &retention.action {
+        ExpiryAction::Delete => "delete".to_string(),
+        ExpiryAction::Archive(tab) => format!("archive to {}", tab),
+    };
+    let record = json!({
+        "at": at.to_rfc3339(),
+        "sheet": retention.sheet,
+        "action": action,
+        "max_age_days": retention.max_age_days,
+        "cutoff": outcome.cutoff,
+        "dry_run": outcome.dry_run,
+        "undated": outcome.undated,
+        "rows": outcome.expired,
+    });
+    // The removed rows are in there, so it's kept as private as the journal
+    let mut options = OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    writeln!(options.open(path)?, "{}", record)
+}
@@ -0,0 +1,191 @@
+use crate::a1::{column_letter, range_width};
+use crate::{fetch_values, SecretString, SheetsError};
+use serde_json::Value;
+use std::fmt;
+use std::sync::Mutex;
+
+// What a read produced. A brand-new tab or a cleared range is Empty, which
+// callers handle like any other case instead of indexing values[0].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReadResult {
+    Empty,
+    Table { header: Vec<Value>, rows: Vec<Vec<Value>> },
+}
+
+impl ReadResult {
+    pub fn is_empty(&self) -> bool {
+        matches!(self, ReadResult::Empty)
+    }
+
+    pub fn header(&self) -> &[Value] {
+        match self {
+            ReadResult::Empty => &[],
+            ReadResult::Table { header, .. } => header,
+        }
+    }
+
+    pub fn rows(&self) -> &[Vec<Value>] {
+        match self {
+            ReadResult::Empty => &[],
+            ReadResult::Table { rows, .. } => rows,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReadOptions {
+    // An empty range or blank first row is an error instead of Empty
+    pub require_header: bool,
+    // The range has no header: every row is data and the header is A, B, C, ...
+    pub treat_first_row_as_data: bool,
+    // The API drops trailing empty cells; pad every row with "" to the range's
+    // width (or the header's, for open-ended ranges) so row[9] always exists
+    pub pad_rows: bool,
+    // How many rows make up the header; 0 and 1 both mean one. See merge_header_rows.
+    pub header_rows: usize,
+}
+
+impl ReadOptions {
+    pub fn header_rows(mut self, rows: usize) -> Self {
+        self.header_rows = rows;
+        self
+    }
+}
+
+// HEADER_ROWS, for the reads that take their settings from the environment
+static HEADER_ROWS: Mutex<Option<usize>> = Mutex::new(None);
+
+// Use this many header rows instead of HEADER_ROWS, e.g. from --header-rows
+pub fn set_header_rows(rows: usize) {
+    *HEADER_ROWS.lock().unwrap_or_else(|e| e.into_inner()) = Some(rows);
+}
+
+// set_header_rows, else HEADER_ROWS, else 1
+pub fn header_rows_from_env() -> Result<usize, String> {
+    if let Some(rows) = *HEADER_ROWS.lock().unwrap_or_else(|e| e.into_inner()) {
+        return Ok(rows);
+    }
+    match std::env::var("HEADER_ROWS") {
+        Ok(rows) => rows.trim().parse().map_err(|_| format!("HEADER_ROWS must be a whole number, got '{}'", rows)),
+        Err(_) => Ok(1),
+    }
+}
+
+// Stacked header rows (a group row over a field row) as one composite name
+// per column: "RETURNS" over "DATE" becomes "RETURNS / DATE". Group labels
+// usually sit in merged cells, which read as the label followed by blanks, so
+// a label in an upper row carries right until the next label in its row or
+// in a row above it. Blank parts are left out, so a column with only a field
+// name keeps just that.
+pub fn merge_header_rows(rows: &[Vec<Value>]) -> Vec<Value> {
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let text = |row: &Vec<Value>, column: usize| match row.get(column) {
+        Some(Value::String(s)) => s.trim().to_string(),
+        Some(Value::Null) | None => String::new(),
+        Some(other) => other.to_string(),
+    };
+    let mut carried = vec![String::new(); rows.len().saturating_sub(1)];
+    (0..width)
+        .map(|column| {
+            let mut parts = Vec::new();
+            for (level, row) in rows.iter().enumerate() {
+                let label = text(row, column);
+                if level + 1 == rows.len() {
+                    parts.push(label);
+                    continue;
+                }
+                if !label.is_empty() {
+                    carried[level] = label;
+                    // A new group starts; labels below it no longer apply
+                    carried[level + 1..].iter_mut().for_each(String::clear);
+                }
+                parts.push(carried[level].clone());
+            }
+            parts.retain(|part| !part.is_empty());
+            Value::String(parts.join(" / "))
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableError {
+    MissingHeader { range: String },
+}
+
+impl fmt::Display for TableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableError::MissingHeader { range } => write!(f, "'{}' has no header row", range),
+        }
+    }
+}
+
+impl std::error::Error for TableError {}
+
+fn is_blank(row: &[Value]) -> bool {
+    row.iter().all(|cell| match cell {
+        Value::String(s) => s.trim().is_empty(),
+        Value::Null => true,
+        _ => false,
+    })
+}
+
+// Pad rows with "" to `width` cells; longer rows are left alone
+pub fn pad_rows(rows: &mut [Vec<Value>], width: usize) {
+    for row in rows {
+        if row.len() < width {
+            row.resize(width, Value::String(String::new()));
+        }
+    }
+}
+
+// Split raw values into header and rows according to `options`
+pub fn interpret(range: &str, values: Vec<Vec<Value>>, options: &ReadOptions) -> Result<ReadResult, TableError> {
+    let mut result = split_header(range, values, options)?;
+    if let (true, ReadResult::Table { header, rows }) = (options.pad_rows, &mut result) {
+        let width = range_width(range).unwrap_or(header.len()).max(header.len());
+        pad_rows(std::slice::from_mut(header), width);
+        pad_rows(rows, width);
+    }
+    Ok(result)
+}
+
+fn split_header(range: &str, mut values: Vec<Vec<Value>>, options: &ReadOptions) -> Result<ReadResult, TableError> {
+    if options.treat_first_row_as_data {
+        if values.is_empty() {
+            return Ok(ReadResult::Empty);
+        }
+        let width = values.iter().map(Vec::len).max().unwrap_or(0);
+        let header = (0..width).map(|i| Value::String(column_letter(i))).collect();
+        return Ok(ReadResult::Table { header, rows: values });
+    }
+    let header_rows = options.header_rows.min(values.len());
+    if header_rows > 1 {
+        let merged = merge_header_rows(&values[..header_rows]);
+        values.splice(..header_rows, [merged]);
+    }
+    if values.first().is_none_or(|header| is_blank(header)) {
+        if options.require_header {
+            return Err(TableError::MissingHeader { range: range.to_string() });
+        }
+        if values.iter().all(|row| is_blank(row)) {
+            return Ok(ReadResult::Empty);
+        }
+        // Blank header over real data: keep the rows, name columns by letter
+        values.remove(0);
+        let width = values.iter().map(Vec::len).max().unwrap_or(0);
+        let header = (0..width).map(|i| Value::String(column_letter(i))).collect();
+        return Ok(ReadResult::Table { header, rows: values });
+    }
+    let header = values.remove(0);
+    Ok(ReadResult::Table { header, rows: values })
+}
+
+pub async fn read_table(
+    access_token: &SecretString,
+    range: &str,
+    options: &ReadOptions,
+) -> Result<ReadResult, SheetsError> {
+    let values = fetch_values(access_token, range).await?;
+    Ok(interpret(range, values, options)?)
+}
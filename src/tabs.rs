@@ -0,0 +1,78 @@
+use crate::metadata::{self, SheetRef};
+use crate::{api, SecretString, SheetsError};
+use serde_json::{json, Value};
+
+// Worksheet tab management on top of batchUpdate. Each function drops the
+// cached metadata afterwards, since tab titles and ids just changed.
+
+pub fn add_sheet_request(title: &str) -> Value {
+    json!({ "addSheet": { "properties": { "title": title } } })
+}
+
+pub fn rename_sheet_request(gid: u64, title: &str) -> Value {
+    json!({ "updateSheetProperties": { "properties": { "sheetId": gid, "title": title }, "fields": "title" } })
+}
+
+// `index` places the copy among the tabs (0 = first); None puts it last
+pub fn duplicate_sheet_request(gid: u64, new_title: &str, index: Option<u32>) -> Value {
+    let mut request = json!({ "duplicateSheet": { "sourceSheetId": gid, "newSheetName": new_title } });
+    if let Some(index) = index {
+        request["duplicateSheet"]["insertSheetIndex"] = json!(index);
+    }
+    request
+}
+
+pub fn delete_sheet_request(gid: u64) -> Value {
+    json!({ "deleteSheet": { "sheetId": gid } })
+}
+
+// sheetId of the tab described by the first addSheet/duplicateSheet reply
+pub(crate) fn new_sheet_id(replies: &[Value], kind: &str) -> Result<u64, SheetsError> {
+    replies
+        .first()
+        .and_then(|reply| reply[kind]["properties"]["sheetId"].as_u64())
+        .ok_or_else(|| SheetsError::Parse(format!("{} reply has no sheetId", kind)))
+}
+
+async fn send(access_token: &SecretString, request: Value) -> Result<Vec<Value>, SheetsError> {
+    let result = api::v4::batch_update(access_token, vec![request]).await;
+    metadata::invalidate_metadata();
+    result
+}
+
+// Create an empty tab; returns its gid
+pub async fn add_sheet(access_token: &SecretString, title: &str) -> Result<u64, SheetsError> {
+    let replies = send(access_token, add_sheet_request(title)).await?;
+    println!(" Added tab '{}'", title);
+    new_sheet_id(&replies, "addSheet")
+}
+
+pub async fn rename_sheet(
+    access_token: &SecretString,
+    sheet: impl Into<SheetRef>,
+    new_title: &str,
+) -> Result<(), SheetsError> {
+    let gid = metadata::resolve_sheet(access_token, &sheet.into()).await?;
+    send(access_token, rename_sheet_request(gid, new_title)).await?;
+    println!(" Renamed tab {} to '{}'", gid, new_title);
+    Ok(())
+}
+
+// Copy a tab with its values, formats and charts; returns the copy's gid
+pub async fn duplicate_sheet(
+    access_token: &SecretString,
+    sheet: impl Into<SheetRef>,
+    new_title: &str,
+) -> Result<u64, SheetsError> {
+    let gid = metadata::resolve_sheet(access_token, &sheet.into()).await?;
+    let replies = send(access_token, duplicate_sheet_request(gid, new_title, None)).await?;
+    println!(" Duplicated tab {} as '{}'", gid, new_title);
+    new_sheet_id(&replies, "duplicateSheet")
+}
+
+pub async fn delete_sheet(access_token: &SecretString, sheet: impl Into<SheetRef>) -> Result<(), SheetsError> {
+    let gid = metadata::resolve_sheet(access_token, &sheet.into()).await?;
+    send(access_token, delete_sheet_request(gid)).await?;
+    println!(" Deleted tab {}", gid);
+    Ok(())
+}
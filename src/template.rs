@@ -0,0 +1,69 @@
+use crate::routing::parse_date;
+use crate::row::column_key;
+use handlebars::{handlebars_helper, Handlebars};
+use serde_json::{Map, Value};
+
+// Compute a cell from the other cells of its row, e.g.
+//   "{{first_name}} {{last_name}}"
+//   "{{format_date [RETURN DATE] "%d %b %Y"}}"
+// Columns are available under their header text and a snake_case alias
+// ("CHANNEL VLOOKUP" -> channel_vlookup).
+pub struct RowTemplate {
+    registry: Handlebars<'static>,
+}
+
+handlebars_helper!(format_date: |value: Value, format: str| {
+    let text = match &value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    parse_date(&text).map(|d| d.format(format).to_string()).unwrap_or(text)
+});
+
+handlebars_helper!(upper: |value: str| value.to_uppercase());
+handlebars_helper!(lower: |value: str| value.to_lowercase());
+handlebars_helper!(trim: |value: str| value.trim().to_string());
+
+impl RowTemplate {
+    pub fn new(template: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut registry = Handlebars::new();
+        registry.register_escape_fn(handlebars::no_escape); // Cells are plain text, not HTML
+        registry.register_helper("format_date", Box::new(format_date));
+        registry.register_helper("upper", Box::new(upper));
+        registry.register_helper("lower", Box::new(lower));
+        registry.register_helper("trim", Box::new(trim));
+        registry.register_template_string("row", template)?;
+        Ok(RowTemplate { registry })
+    }
+
+    pub fn render(&self, header: &[String], row: &[String]) -> Result<String, Box<dyn std::error::Error>> {
+        Ok(self.registry.render("row", &row_context(header, row))?)
+    }
+
+    // Fill `column` of every row with the rendered template (rows are padded if short)
+    pub fn apply_column(
+        &self,
+        header: &[String],
+        rows: &mut [Vec<String>],
+        column: usize,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        for row in rows.iter_mut() {
+            let value = self.render(header, row)?;
+            if row.len() <= column {
+                row.resize(column + 1, String::new());
+            }
+            row[column] = value;
+        }
+        Ok(())
+    }
+}
+
+pub fn row_context(header: &[String], row: &[String]) -> Value {
+    let mut context = Map::new();
+    for (i, name) in header.iter().enumerate() {
+        let value = Value::String(row.get(i).cloned().unwrap_or_default());
+        context.insert(column_key(name), value.clone());
+        context.insert(name.clone(), value);
+    }
+    Value::Object(context)
+}
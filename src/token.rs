@@ -0,0 +1,88 @@
+use crate::config::ConfigError;
+use crate::scope::scope_urls;
+use crate::{cache_file, mint_token, Credentials, SecretString, SheetsError};
+use chrono::{DateTime, Duration, Utc};
+use tokio::sync::Mutex;
+
+// Refresh this long before Google's expiry so a token never lapses mid-request
+pub const DEFAULT_REFRESH_SKEW_SECS: i64 = 120;
+
+#[derive(Debug)]
+struct CachedToken {
+    token: SecretString,
+    expires_at: DateTime<Utc>,
+}
+
+// Hands out access tokens for one set of credentials, minting a new one only
+// when the current token is within `skew` of expiring. Concurrent callers
+// share one refresh. Tokens still valid in the cache file are reused too.
+#[derive(Debug)]
+pub struct TokenProvider {
+    credentials: Credentials,
+    scopes: Vec<String>,
+    skew: Duration,
+    current: Mutex<Option<CachedToken>>,
+}
+
+impl TokenProvider {
+    pub fn new(credentials: Credentials, scopes: &[&str]) -> Self {
+        TokenProvider {
+            credentials,
+            scopes: scopes.iter().map(|s| s.to_string()).collect(),
+            skew: Duration::seconds(DEFAULT_REFRESH_SKEW_SECS),
+            current: Mutex::new(None),
+        }
+    }
+
+    // Tokens for the scopes `credentials` carry (see Credentials::with_scopes)
+    pub fn for_credentials(credentials: Credentials) -> Self {
+        let scopes = scope_urls(&credentials.scopes);
+        TokenProvider::new(credentials, &scopes)
+    }
+
+    // Credentials and scopes from the environment
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Ok(TokenProvider::for_credentials(Credentials::from_env()?))
+    }
+
+    pub fn with_skew(mut self, skew: Duration) -> Self {
+        self.skew = skew;
+        self
+    }
+
+    pub fn credentials(&self) -> &Credentials {
+        &self.credentials
+    }
+
+    pub async fn token(&self) -> Result<SecretString, SheetsError> {
+        let mut current = self.current.lock().await;
+        if let Some(cached) = current.as_ref() {
+            if cached.expires_at - self.skew > Utc::now() {
+                return Ok(cached.token.clone());
+            }
+        }
+
+        let scopes: Vec<&str> = self.scopes.iter().map(String::as_str).collect();
+        let from_file = cache_file::load_token_with_expiry(&self.credentials, &scopes.join(" "))
+            .and_then(|(token, expires_at)| Some((token, DateTime::from_timestamp(expires_at, 0)?)))
+            .filter(|(_, expires_at)| *expires_at - self.skew > Utc::now());
+        let (token, expires_at) = match from_file {
+            Some(cached) => cached,
+            None => {
+                let (token, expires_in) = mint_token(&self.credentials, &scopes).await?;
+                (token, Utc::now() + Duration::seconds(expires_in))
+            }
+        };
+        *current = Some(CachedToken { token: token.clone(), expires_at });
+        Ok(token)
+    }
+
+    // Forget the current token, e.g. after a 401
+    pub async fn invalidate(&self) {
+        *self.current.lock().await = None;
+    }
+
+    pub async fn expires_at(&self) -> Option<DateTime<Utc>> {
+        self.current.lock().await.as_ref().map(|c| c.expires_at)
+    }
+}
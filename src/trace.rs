@@ -0,0 +1,120 @@
+use reqwest::header::HeaderMap;
+use reqwest::RequestBuilder;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// W3C trace context (https://www.w3.org/TR/trace-context/) for outgoing
+// requests. A service embedding the crate runs its sheet calls inside
+// with_trace_context using the incoming request's traceparent; every Google
+// request then carries a child span ID, and the matching tracing span records
+// the trace/span IDs plus any request ID Google sends back.
+
+tokio::task_local! {
+    static CONTEXT: TraceContext;
+}
+
+// Response headers that identify a request on Google's side
+const REQUEST_ID_HEADERS: &[&str] = &["x-goog-request-id", "x-guploader-uploadid", "x-request-id"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceContext {
+    pub trace_id: String,  // 32 hex chars
+    pub parent_id: String, // 16 hex chars
+    pub sampled: bool,
+}
+
+impl TraceContext {
+    // Parse "00-<trace-id>-<parent-id>-<flags>"
+    pub fn parse(traceparent: &str) -> Option<Self> {
+        let parts: Vec<&str> = traceparent.trim().split('-').collect();
+        let [version, trace_id, parent_id, flags] = parts.as_slice() else { return None };
+        let hex = |s: &str, len: usize| s.len() == len && s.chars().all(|c| c.is_ascii_hexdigit());
+        if *version != "00" || !hex(trace_id, 32) || !hex(parent_id, 16) || !hex(flags, 2) {
+            return None;
+        }
+        if trace_id.chars().all(|c| c == '0') || parent_id.chars().all(|c| c == '0') {
+            return None;
+        }
+        let flags = u8::from_str_radix(flags, 16).ok()?;
+        Some(TraceContext {
+            trace_id: trace_id.to_ascii_lowercase(),
+            parent_id: parent_id.to_ascii_lowercase(),
+            sampled: flags & 1 == 1,
+        })
+    }
+
+    // A new trace, for calls made outside any incoming request
+    pub fn new_root() -> Self {
+        TraceContext { trace_id: random_hex(16), parent_id: random_hex(8), sampled: true }
+    }
+
+    fn child(&self) -> (String, String) {
+        let span_id = random_hex(8);
+        let header = format!("00-{}-{}-{}", self.trace_id, span_id, if self.sampled { "01" } else { "00" });
+        (span_id, header)
+    }
+}
+
+impl fmt::Display for TraceContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "00-{}-{}-{}", self.trace_id, self.parent_id, if self.sampled { "01" } else { "00" })
+    }
+}
+
+// Run `future` with `context` as the parent of every request it makes
+pub async fn with_trace_context<F: std::future::Future>(context: TraceContext, future: F) -> F::Output {
+    CONTEXT.scope(context, future).await
+}
+
+pub fn current_context() -> Option<TraceContext> {
+    CONTEXT.try_with(Clone::clone).ok()
+}
+
+// Not cryptographic, just unique enough for trace IDs without pulling in a RNG crate
+fn random_hex(bytes: usize) -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+    let mut hasher = Sha256::new();
+    hasher.update(nanos.to_le_bytes());
+    hasher.update(std::process::id().to_le_bytes());
+    hasher.update(COUNTER.fetch_add(1, Ordering::Relaxed).to_le_bytes());
+    hasher.finalize()[..bytes].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Span for one API call; record_response fills in the status and request ID
+pub fn request_span(method: &str, url: &str) -> (tracing::Span, Option<String>) {
+    let path = url.split('?').next().unwrap_or(url);
+    let span = tracing::info_span!(
+        "sheets.request",
+        http.method = method,
+        http.url = path,
+        http.status_code = tracing::field::Empty,
+        trace_id = tracing::field::Empty,
+        span_id = tracing::field::Empty,
+        google.request_id = tracing::field::Empty,
+    );
+    let traceparent = current_context().map(|context| {
+        let (span_id, header) = context.child();
+        span.record("trace_id", context.trace_id.as_str());
+        span.record("span_id", span_id.as_str());
+        header
+    });
+    (span, traceparent)
+}
+
+// Add the traceparent header when a trace context is active
+pub fn inject(request: RequestBuilder, traceparent: Option<&str>) -> RequestBuilder {
+    match traceparent {
+        Some(header) => request.header("traceparent", header),
+        None => request,
+    }
+}
+
+pub fn record_response(span: &tracing::Span, status: u16, headers: &HeaderMap) {
+    span.record("http.status_code", status);
+    if let Some(id) = REQUEST_ID_HEADERS.iter().find_map(|h| headers.get(*h)?.to_str().ok()) {
+        span.record("google.request_id", id);
+    }
+}
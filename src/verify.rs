@@ -0,0 +1,105 @@
+use crate::a1::{column_letter, range_start};
+use crate::cell_value::CellValue;
+use crate::limits::MAX_CELL_CHARS;
+use crate::{fetch_values, SecretString, SheetsError};
+use serde_json::Value;
+use std::env;
+use std::fmt;
+
+// VERIFY_WRITES=1 turns on readback after every append/update
+pub fn verify_writes_enabled() -> bool {
+    env::var("VERIFY_WRITES")
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "on"))
+        .unwrap_or(false)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellMismatch {
+    pub cell: String, // A1 address, e.g. "C5"
+    pub expected: String,
+    pub actual: String,
+}
+
+impl CellMismatch {
+    pub fn is_truncation(&self) -> bool {
+        self.expected.chars().count() > MAX_CELL_CHARS && self.expected.starts_with(&self.actual)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WriteVerificationError {
+    pub range: String,
+    pub mismatches: Vec<CellMismatch>,
+}
+
+impl fmt::Display for WriteVerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "readback of '{}' differs in {} cell(s)", self.range, self.mismatches.len())?;
+        for m in self.mismatches.iter().take(5) {
+            if m.is_truncation() {
+                write!(f, "; {} truncated to {} chars (limit {})", m.cell, m.actual.chars().count(), MAX_CELL_CHARS)?;
+            } else {
+                write!(f, "; {} expected {:?} got {:?}", m.cell, preview(&m.expected), preview(&m.actual))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for WriteVerificationError {}
+
+fn preview(text: &str) -> String {
+    let short: String = text.chars().take(30).collect();
+    if short.len() < text.len() {
+        format!("{}...", short)
+    } else {
+        short
+    }
+}
+
+// Re-read `range` (as reported by the API after the write) and compare it to what was sent
+pub async fn verify_range(
+    access_token: &SecretString,
+    range: &str,
+    expected: &[Vec<CellValue>],
+) -> Result<(), SheetsError> {
+    let actual = fetch_values(access_token, range).await?;
+    let mismatches = compare_values(range, expected, &actual);
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(WriteVerificationError {
+            range: range.to_string(),
+            mismatches,
+        }
+        .into())
+    }
+}
+
+// Missing trailing cells in the readback count as empty strings. Numbers and
+// formulas are skipped: how they read back depends on formatting.
+pub fn compare_values(range: &str, expected: &[Vec<CellValue>], actual: &[Vec<Value>]) -> Vec<CellMismatch> {
+    let (start_col, start_row) = range_start(range);
+    let mut mismatches = Vec::new();
+    for (r, row) in expected.iter().enumerate() {
+        for (c, want) in row.iter().enumerate() {
+            let Some(want) = want.expected_text() else { continue };
+            let got = actual
+                .get(r)
+                .and_then(|row| row.get(c))
+                .map(|cell| match cell {
+                    Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                })
+                .unwrap_or_default();
+            if got != want {
+                mismatches.push(CellMismatch {
+                    cell: format!("{}{}", column_letter(start_col + c), start_row + r),
+                    expected: want,
+                    actual: got,
+                });
+            }
+        }
+    }
+    mismatches
+}
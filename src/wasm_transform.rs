@@ -0,0 +1,108 @@
+use serde_json::{json, Value};
+use std::path::Path;
+use std::sync::Mutex;
+use wasmtime::{Engine, Instance, Linker, Memory, Module, Store, TypedFunc};
+
+// Row transform loaded from a WASM module, so transformation logic can be
+// written in any language that compiles to wasm32.
+//
+// The module must export:
+//   memory
+//   alloc(len: i32) -> i32                    buffer for the input
+//   transform(ptr: i32, len: i32) -> i64      (out_ptr << 32) | out_len
+// Input is JSON {"header": [...], "row": [...]}; output is a JSON array of
+// strings (the new row) or `null` to drop the row.
+pub struct WasmTransform {
+    inner: Mutex<Loaded>,
+}
+
+struct Loaded {
+    store: Store<()>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    transform: TypedFunc<(i32, i32), i64>,
+}
+
+impl WasmTransform {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let engine = Engine::default();
+        let module = Module::from_file(&engine, path.as_ref())
+            .map_err(|e| format!("loading {}: {}", path.as_ref().display(), e))?;
+        Self::from_module(&engine, &module)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        let engine = Engine::default();
+        let module = Module::new(&engine, bytes).map_err(|e| e.to_string())?;
+        Self::from_module(&engine, &module)
+    }
+
+    fn from_module(engine: &Engine, module: &Module) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut store = Store::new(engine, ());
+        // No host imports: plugins only see the row they are given
+        let instance: Instance = Linker::new(engine)
+            .instantiate(&mut store, module)
+            .map_err(|e| e.to_string())?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or("wasm module does not export `memory`")?;
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&mut store, "alloc")
+            .map_err(|e| format!("wasm module `alloc` export: {}", e))?;
+        let transform = instance
+            .get_typed_func::<(i32, i32), i64>(&mut store, "transform")
+            .map_err(|e| format!("wasm module `transform` export: {}", e))?;
+        Ok(WasmTransform {
+            inner: Mutex::new(Loaded { store, memory, alloc, transform }),
+        })
+    }
+
+    // None means the plugin dropped the row
+    pub fn transform(&self, header: &[String], row: &[String]) -> Result<Option<Vec<String>>, Box<dyn std::error::Error>> {
+        let input = json!({ "header": header, "row": row }).to_string();
+        let mut guard = self.inner.lock().map_err(|_| "wasm transform poisoned by an earlier panic")?;
+        let Loaded { store, memory, alloc, transform } = &mut *guard;
+
+        let len = i32::try_from(input.len()).map_err(|_| "row too large for wasm transform")?;
+        let ptr = alloc.call(&mut *store, len).map_err(|e| e.to_string())?;
+        memory
+            .write(&mut *store, ptr as u32 as usize, input.as_bytes())
+            .map_err(|e| e.to_string())?;
+
+        let packed = transform.call(&mut *store, (ptr, len)).map_err(|e| e.to_string())? as u64;
+        let (out_ptr, out_len) = ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize);
+        let mut output = vec![0u8; out_len];
+        memory
+            .read(&*store, out_ptr, &mut output)
+            .map_err(|e| format!("wasm transform returned an invalid buffer: {}", e))?;
+
+        match serde_json::from_slice::<Value>(&output)? {
+            Value::Null => Ok(None),
+            Value::Array(cells) => Ok(Some(
+                cells
+                    .into_iter()
+                    .map(|cell| match cell {
+                        Value::String(s) => s,
+                        Value::Null => String::new(),
+                        other => other.to_string(),
+                    })
+                    .collect(),
+            )),
+            other => Err(format!("wasm transform must return an array or null, got {}", other).into()),
+        }
+    }
+
+    pub fn transform_rows(
+        &self,
+        header: &[String],
+        rows: Vec<Vec<String>>,
+    ) -> Result<Vec<Vec<String>>, Box<dyn std::error::Error>> {
+        let mut out = Vec::with_capacity(rows.len());
+        for row in rows {
+            if let Some(row) = self.transform(header, &row)? {
+                out.push(row);
+            }
+        }
+        Ok(out)
+    }
+}
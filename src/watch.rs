@@ -0,0 +1,174 @@
+use crate::a1::{range_sheet, range_start};
+use crate::cell_value::CellValue;
+use crate::coerce::cell_text;
+use crate::rollover::quote_sheet;
+use crate::{api, SecretString, SheetsError};
+use futures::stream::{self, Stream};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::env;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::time::{interval, Interval, MissedTickBehavior};
+
+// Self-write events waiting for slow watchers before the oldest are dropped
+const CHANNEL_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Appended,
+    Updated,
+    Removed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventSource {
+    // Seen by polling the sheet
+    Poll,
+    // Written by this process and announced right away (source=self)
+    #[serde(rename = "self")]
+    SelfWrite,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangeEvent {
+    pub sheet: String,
+    pub row: usize, // 1-based sheet row
+    pub kind: ChangeKind,
+    pub values: Vec<Value>,
+    pub source: EventSource,
+}
+
+static SELF_EVENTS: AtomicBool = AtomicBool::new(false);
+
+fn channel() -> &'static broadcast::Sender<ChangeEvent> {
+    static CHANNEL: OnceLock<broadcast::Sender<ChangeEvent>> = OnceLock::new();
+    CHANNEL.get_or_init(|| broadcast::channel(CHANNEL_CAPACITY).0)
+}
+
+// Announce this process's own appends/updates to local watch() streams.
+// Also on with WATCH_SELF_EVENTS=1.
+pub fn set_self_events(on: bool) {
+    SELF_EVENTS.store(on, Ordering::Relaxed);
+}
+
+fn self_events_enabled() -> bool {
+    SELF_EVENTS.load(Ordering::Relaxed) || matches!(env::var("WATCH_SELF_EVENTS").as_deref(), Ok("1" | "true"))
+}
+
+// Called after a successful write with the range the API reported. No-op
+// unless self events are on and someone is watching.
+pub(crate) fn publish_write(updated_range: &str, kind: ChangeKind, rows: &[Vec<CellValue>]) {
+    if !self_events_enabled() || channel().receiver_count() == 0 {
+        return;
+    }
+    let Some(sheet) = range_sheet(updated_range) else { return };
+    let (_, first_row) = range_start(updated_range);
+    for (offset, row) in rows.iter().enumerate() {
+        let _ = channel().send(ChangeEvent {
+            sheet: sheet.clone(),
+            row: first_row + offset,
+            kind,
+            values: row.iter().map(|cell| cell.to_json(false)).collect(),
+            source: EventSource::SelfWrite,
+        });
+    }
+}
+
+struct WatchState<'a> {
+    access_token: &'a SecretString,
+    sheet: String,
+    snapshot: Option<Vec<Vec<Value>>>,
+    ticks: Interval,
+    local: broadcast::Receiver<ChangeEvent>,
+    pending: VecDeque<Result<ChangeEvent, SheetsError>>,
+}
+
+// Row-level changes to `sheet`, found by re-reading it every `every`. The
+// first read is the baseline and produces no events. Self-write events (see
+// set_self_events) arrive between polls in write order and are folded into
+// the baseline, so the next poll doesn't report them a second time.
+pub fn watch<'a>(access_token: &'a SecretString, sheet: &str, every: Duration) -> impl Stream<Item = Result<ChangeEvent, SheetsError>> + 'a {
+    let mut ticks = interval(every);
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let state = WatchState {
+        access_token,
+        sheet: sheet.to_string(),
+        snapshot: None,
+        ticks,
+        local: channel().subscribe(),
+        pending: VecDeque::new(),
+    };
+    stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(event) = state.pending.pop_front() {
+                return Some((event, state));
+            }
+            tokio::select! {
+                _ = state.ticks.tick() => state.poll().await,
+                // Lagged receivers just miss self events; the next poll still sees the rows
+                received = state.local.recv() => match received {
+                    Ok(event) if event.sheet.eq_ignore_ascii_case(&state.sheet) => state.fold_in(event),
+                    _ => {}
+                },
+            }
+        }
+    })
+}
+
+impl WatchState<'_> {
+    async fn poll(&mut self) {
+        let rows = match api::v4::get_values(self.access_token, &quote_sheet(&self.sheet)).await {
+            Ok(rows) => rows,
+            Err(e) => return self.pending.push_back(Err(e)),
+        };
+        if let Some(previous) = &self.snapshot {
+            for event in diff(&self.sheet, previous, &rows) {
+                self.pending.push_back(Ok(event));
+            }
+        }
+        self.snapshot = Some(rows);
+    }
+
+    fn fold_in(&mut self, event: ChangeEvent) {
+        if let (Some(snapshot), Some(index)) = (&mut self.snapshot, event.row.checked_sub(1)) {
+            if snapshot.len() <= index {
+                snapshot.resize(index + 1, Vec::new());
+            }
+            snapshot[index] = event.values.clone();
+        }
+        self.pending.push_back(Ok(event));
+    }
+}
+
+fn same_row(a: &[Value], b: &[Value]) -> bool {
+    let width = a.len().max(b.len());
+    (0..width).all(|i| a.get(i).map(cell_text).unwrap_or_default() == b.get(i).map(cell_text).unwrap_or_default())
+}
+
+fn diff(sheet: &str, previous: &[Vec<Value>], current: &[Vec<Value>]) -> Vec<ChangeEvent> {
+    let event = |index: usize, kind, values: &[Value]| ChangeEvent {
+        sheet: sheet.to_string(),
+        row: index + 1,
+        kind,
+        values: values.to_vec(),
+        source: EventSource::Poll,
+    };
+    let mut events = Vec::new();
+    for (index, row) in current.iter().enumerate() {
+        match previous.get(index) {
+            None => events.push(event(index, ChangeKind::Appended, row)),
+            Some(old) if !same_row(old, row) => events.push(event(index, ChangeKind::Updated, row)),
+            _ => {}
+        }
+    }
+    for (index, old) in previous.iter().enumerate().skip(current.len()) {
+        events.push(event(index, ChangeKind::Removed, old));
+    }
+    events
+}
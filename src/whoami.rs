@@ -0,0 +1,82 @@
+use crate::har::SendRecorded;
+use crate::{config, Scope, SecretString, SheetsError};
+use chrono::{DateTime, Duration, Utc};
+use reqwest::Client;
+use serde::Deserialize;
+use std::fmt;
+
+const TOKENINFO_URL: &str = "https://oauth2.googleapis.com/tokeninfo";
+
+#[derive(Deserialize)]
+struct TokenInfoResponse {
+    azp: Option<String>,
+    email: Option<String>,
+    scope: Option<String>,
+    expires_in: Option<String>,
+    error_description: Option<String>,
+}
+
+// What Google thinks a token is: who it belongs to, what it may do and when it expires
+#[derive(Debug, Clone)]
+pub struct TokenInfo {
+    pub principal: String,
+    pub client_id: Option<String>,
+    pub scopes: Vec<String>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl TokenInfo {
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+
+    // Drive and drive.file tokens can write spreadsheets too
+    pub fn can_write_sheets(&self) -> bool {
+        [Scope::Spreadsheets, Scope::DriveFile, Scope::Drive].iter().any(|scope| self.has_scope(scope.url()))
+    }
+}
+
+impl fmt::Display for TokenInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "principal: {}", self.principal)?;
+        if let Some(client_id) = &self.client_id {
+            writeln!(f, "client id: {}", client_id)?;
+        }
+        writeln!(f, "expires:   {} ({} min left)", self.expires_at.to_rfc3339(), (self.expires_at - Utc::now()).num_minutes())?;
+        writeln!(f, "scopes:")?;
+        for scope in &self.scopes {
+            writeln!(f, "  {}", scope)?;
+        }
+        Ok(())
+    }
+}
+
+// Ask Google's tokeninfo endpoint about `access_token`. Service-account
+// tokens without the email scope carry no email, so the principal falls back
+// to SERVICE_ACCOUNT_EMAIL.
+pub async fn whoami(access_token: &SecretString) -> Result<TokenInfo, SheetsError> {
+    // POST so the token doesn't end up in proxy logs as part of a URL
+    let response = Client::new()
+        .post(TOKENINFO_URL)
+        .form(&[("access_token", access_token.expose_secret())])
+        .send_recorded()
+        .await?
+        .json::<TokenInfoResponse>()
+        .await?;
+    if let Some(error) = response.error_description {
+        return Err(SheetsError::Auth(format!("token rejected by tokeninfo: {}", error)));
+    }
+    let principal = match response.email {
+        Some(email) => email,
+        None => config::Config::from_env()
+            .map(|c| c.service_account_email)
+            .unwrap_or_else(|_| "unknown".to_string()),
+    };
+    let expires_in: i64 = response.expires_in.as_deref().and_then(|s| s.parse().ok()).unwrap_or(0);
+    Ok(TokenInfo {
+        principal,
+        client_id: response.azp,
+        scopes: response.scope.unwrap_or_default().split_whitespace().map(str::to_string).collect(),
+        expires_at: Utc::now() + Duration::seconds(expires_in),
+    })
+}
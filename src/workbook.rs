@@ -0,0 +1,350 @@
+use crate::cell_value::CellValue;
+use crate::filter::Filter;
+use crate::formula::{Cells, Evaluator, FormulaValue};
+use crate::metadata::invalidate_metadata;
+use crate::policy::{self, Operation};
+use crate::{api, config, SecretString, SheetsError};
+use serde_json::{json, Map, Value};
+
+// The whole spreadsheet in memory, for "open, edit, save": load it in one
+// includeGridData read, query and change it locally, then save only what
+// changed. Values are what was typed (formulas as "=..."), not what they show.
+// Adding, removing or reordering tabs isn't modelled; use the tabs module.
+
+#[derive(Debug, Clone, Default)]
+pub struct LoadOptions {
+    // Also load each cell's userEnteredFormat, so edits to it are saved
+    pub formats: bool,
+    // Tabs to load, by title; empty loads every tab
+    pub sheets: Vec<String>,
+}
+
+impl LoadOptions {
+    pub fn with_formats(mut self) -> Self {
+        self.formats = true;
+        self
+    }
+
+    pub fn sheets(mut self, titles: &[&str]) -> Self {
+        self.sheets = titles.iter().map(|t| t.to_string()).collect();
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Cell {
+    pub value: CellValue,
+    pub format: Option<Value>, // userEnteredFormat as the API gives it; None when not loaded
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkbookSheet {
+    pub sheet_id: u64,
+    pub title: String,
+    pub row_count: usize,
+    pub column_count: usize,
+    pub frozen_rows: usize,
+    cells: Vec<Vec<Cell>>, // Row-major from A1; rows and cells past the last value are left out
+}
+
+impl WorkbookSheet {
+    // `column` is 0-based and `row` 1-based, as in A1Range::cell
+    pub fn cell(&self, column: usize, row: usize) -> Option<&Cell> {
+        self.cells.get(row.checked_sub(1)?)?.get(column)
+    }
+
+    pub fn value(&self, column: usize, row: usize) -> &CellValue {
+        static EMPTY: CellValue = CellValue::Empty;
+        self.cell(column, row).map_or(&EMPTY, |cell| &cell.value)
+    }
+
+    // The grid grows on save if this writes past the tab's current size
+    pub fn set(&mut self, column: usize, row: usize, value: impl Into<CellValue>) {
+        self.cell_mut(column, row).value = value.into();
+    }
+
+    // Saved only when the workbook was loaded with formats
+    pub fn set_format(&mut self, column: usize, row: usize, format: Value) {
+        self.cell_mut(column, row).format = Some(format);
+    }
+
+    fn cell_mut(&mut self, column: usize, row: usize) -> &mut Cell {
+        let row = row.max(1) - 1;
+        if self.cells.len() <= row {
+            self.cells.resize_with(row + 1, Vec::new);
+        }
+        let cells = &mut self.cells[row];
+        if cells.len() <= column {
+            cells.resize_with(column + 1, Cell::default);
+        }
+        &mut cells[column]
+    }
+
+    // Rows holding any value, counted from row 1
+    pub fn used_rows(&self) -> usize {
+        self.cells.iter().rposition(|row| row.iter().any(|cell| cell.value != CellValue::Empty)).map_or(0, |i| i + 1)
+    }
+
+    // Values as a values.get response would give them, for the table and filter helpers
+    pub fn values(&self) -> Vec<Vec<Value>> {
+        self.cells[..self.used_rows()]
+            .iter()
+            .map(|row| {
+                let width = row.iter().rposition(|cell| cell.value != CellValue::Empty).map_or(0, |i| i + 1);
+                row[..width].iter().map(|cell| cell.value.to_json(false)).collect()
+            })
+            .collect()
+    }
+
+    // 1-based numbers of the rows below the header that match `filter`
+    pub fn filter_rows(&self, filter: &Filter) -> Result<Vec<usize>, String> {
+        let values = self.values();
+        let Some((header, rows)) = values.split_first() else { return Ok(Vec::new()) };
+        let compiled = filter.compile(header)?;
+        Ok(rows.iter().enumerate().filter(|(_, row)| compiled.matches(row)).map(|(i, _)| i + 2).collect())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Workbook {
+    pub spreadsheet_id: String,
+    pub title: String,
+    formats: bool,
+    sheets: Vec<WorkbookSheet>,
+    saved: Vec<WorkbookSheet>, // As last loaded or saved, to diff against
+}
+
+impl Workbook {
+    pub fn sheets(&self) -> &[WorkbookSheet] {
+        &self.sheets
+    }
+
+    // Tab names are unique regardless of case
+    pub fn sheet(&self, title: &str) -> Option<&WorkbookSheet> {
+        self.sheets.iter().find(|s| s.title.eq_ignore_ascii_case(title))
+    }
+
+    pub fn sheet_mut(&mut self, title: &str) -> Option<&mut WorkbookSheet> {
+        self.sheets.iter_mut().find(|s| s.title.eq_ignore_ascii_case(title))
+    }
+
+    // A cell's value with its formula worked out locally (see the formula
+    // module for what's supported)
+    pub fn evaluate(&self, sheet: &str, column: usize, row: usize) -> FormulaValue {
+        Evaluator::new(self).cell(sheet, column, row)
+    }
+
+    // A formula that isn't in the sheet yet, as if typed into a cell of `sheet`
+    pub fn evaluate_formula(&self, sheet: &str, formula: &str) -> FormulaValue {
+        Evaluator::new(self).formula(sheet, formula)
+    }
+
+    // Like WorkbookSheet::values, with every formula replaced by its result
+    pub fn computed_values(&self, sheet: &str) -> Option<Vec<Vec<Value>>> {
+        let tab = self.sheet(sheet)?;
+        let evaluator = Evaluator::new(self);
+        Some(
+            tab.cells[..tab.used_rows()]
+                .iter()
+                .enumerate()
+                .map(|(row, cells)| {
+                    let width = cells.iter().rposition(|cell| cell.value != CellValue::Empty).map_or(0, |i| i + 1);
+                    (0..width).map(|column| evaluator.cell(&tab.title, column, row + 1).to_json()).collect()
+                })
+                .collect(),
+        )
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.sheets != self.saved
+    }
+
+    // The batchUpdate requests that turn the spreadsheet as loaded into this
+    // model: renames, grid growth, then one updateCells per run of changed
+    // cells in a row
+    pub fn diff(&self) -> Vec<Value> {
+        let mut requests = Vec::new();
+        let fields = if self.formats { "userEnteredValue,userEnteredFormat" } else { "userEnteredValue" };
+        for sheet in &self.sheets {
+            let Some(before) = self.saved.iter().find(|s| s.sheet_id == sheet.sheet_id) else { continue };
+            if sheet.title != before.title {
+                requests.push(json!({ "updateSheetProperties": {
+                    "properties": { "sheetId": sheet.sheet_id, "title": sheet.title },
+                    "fields": "title",
+                }}));
+            }
+            requests.extend(grow_requests(sheet, before));
+            let height = sheet.cells.len().max(before.cells.len());
+            for row in 0..height {
+                let width = [&sheet.cells, &before.cells].iter().filter_map(|cells| cells.get(row)).map(Vec::len).max().unwrap_or(0);
+                let mut column = 0;
+                while column < width {
+                    let changed = |c: usize| cell_at(&sheet.cells, row, c) != cell_at(&before.cells, row, c);
+                    if !changed(column) {
+                        column += 1;
+                        continue;
+                    }
+                    let start = column;
+                    while column < width && changed(column) {
+                        column += 1;
+                    }
+                    let values: Vec<Value> = (start..column).map(|c| cell_data(&cell_at(&sheet.cells, row, c))).collect();
+                    requests.push(json!({ "updateCells": {
+                        "start": { "sheetId": sheet.sheet_id, "rowIndex": row, "columnIndex": start },
+                        "rows": [{ "values": values }],
+                        "fields": fields,
+                    }}));
+                }
+            }
+        }
+        requests
+    }
+
+    // Send the diff as one batchUpdate (so it lands all-or-nothing) and treat
+    // the result as the new starting point. Returns the requests sent.
+    pub async fn save(&mut self, access_token: &SecretString) -> Result<usize, SheetsError> {
+        let requests = self.diff();
+        if requests.is_empty() {
+            return Ok(0);
+        }
+        let count = requests.len();
+        api::v4::batch_update(access_token, requests).await?;
+        for sheet in &mut self.sheets {
+            sheet.row_count = sheet.row_count.max(sheet.cells.len());
+            sheet.column_count = sheet.column_count.max(sheet.cells.iter().map(Vec::len).max().unwrap_or(0));
+        }
+        self.saved = self.sheets.clone();
+        invalidate_metadata();
+        Ok(count)
+    }
+}
+
+impl Cells for Workbook {
+    fn cell(&self, sheet: &str, column: usize, row: usize) -> Option<CellValue> {
+        Some(self.sheet(sheet)?.value(column, row).clone())
+    }
+
+    fn used_rows(&self, sheet: &str) -> Option<usize> {
+        Some(self.sheet(sheet)?.used_rows())
+    }
+}
+
+pub async fn load_workbook(access_token: &SecretString) -> Result<Workbook, SheetsError> {
+    load_workbook_with(access_token, &LoadOptions::default()).await
+}
+
+pub async fn load_workbook_with(access_token: &SecretString, options: &LoadOptions) -> Result<Workbook, SheetsError> {
+    let policy = policy::global()?;
+    if options.sheets.is_empty() {
+        // Every tab, which a tab restriction refuses; name the tabs instead
+        policy.check(Operation::Read, None)?;
+    }
+    for title in &options.sheets {
+        policy.check(Operation::Read, Some(title))?;
+    }
+    let cell_fields = if options.formats { "userEnteredValue,userEnteredFormat" } else { "userEnteredValue" };
+    let fields = format!(
+        "properties.title,sheets(properties(sheetId,title,gridProperties(rowCount,columnCount,frozenRowCount)),data(startRow,startColumn,rowData.values({})))",
+        cell_fields
+    );
+    let ranges: Vec<&str> = options.sheets.iter().map(String::as_str).collect();
+    let response = api::v4::get_with_grid_data(access_token, &ranges, &fields).await?;
+    let sheets: Vec<WorkbookSheet> = response["sheets"].as_array().into_iter().flatten().map(parse_sheet).collect();
+    Ok(Workbook {
+        spreadsheet_id: config::sheet_id()?,
+        title: response["properties"]["title"].as_str().unwrap_or_default().to_string(),
+        formats: options.formats,
+        saved: sheets.clone(),
+        sheets,
+    })
+}
+
+fn parse_sheet(sheet: &Value) -> WorkbookSheet {
+    let properties = &sheet["properties"];
+    let grid = &properties["gridProperties"];
+    let count = |value: &Value| value.as_u64().unwrap_or(0) as usize;
+    let mut cells: Vec<Vec<Cell>> = Vec::new();
+    for data in sheet["data"].as_array().into_iter().flatten() {
+        let (start_row, start_column) = (count(&data["startRow"]), count(&data["startColumn"]));
+        for (i, row) in data["rowData"].as_array().into_iter().flatten().enumerate() {
+            let values = row["values"].as_array().cloned().unwrap_or_default();
+            if values.is_empty() {
+                continue;
+            }
+            let row = start_row + i;
+            if cells.len() <= row {
+                cells.resize_with(row + 1, Vec::new);
+            }
+            let target = &mut cells[row];
+            if target.len() < start_column + values.len() {
+                target.resize_with(start_column + values.len(), Cell::default);
+            }
+            for (j, value) in values.iter().enumerate() {
+                target[start_column + j] = Cell {
+                    value: entered_value(&value["userEnteredValue"]),
+                    format: value.get("userEnteredFormat").cloned(),
+                };
+            }
+        }
+    }
+    WorkbookSheet {
+        sheet_id: properties["sheetId"].as_u64().unwrap_or(0),
+        title: properties["title"].as_str().unwrap_or_default().to_string(),
+        row_count: count(&grid["rowCount"]),
+        column_count: count(&grid["columnCount"]),
+        frozen_rows: count(&grid["frozenRowCount"]),
+        cells,
+    }
+}
+
+// ExtendedValue -> CellValue; errorValue only appears on formula results, so
+// there's nothing typed to keep
+fn entered_value(value: &Value) -> CellValue {
+    if let Some(formula) = value["formulaValue"].as_str() {
+        CellValue::Formula(formula.to_string())
+    } else if let Some(text) = value["stringValue"].as_str() {
+        CellValue::from(text)
+    } else if let Some(number) = value["numberValue"].as_f64() {
+        CellValue::Number(number)
+    } else if let Some(flag) = value["boolValue"].as_bool() {
+        CellValue::Bool(flag)
+    } else {
+        CellValue::Empty
+    }
+}
+
+// CellData for updateCells; an Empty value with no format clears the cell
+fn cell_data(cell: &Cell) -> Value {
+    let mut data = Map::new();
+    let entered = match &cell.value {
+        CellValue::String(s) => Some(json!({ "stringValue": s })),
+        CellValue::Number(n) => Some(json!({ "numberValue": n })),
+        CellValue::Bool(b) => Some(json!({ "boolValue": b })),
+        CellValue::Formula(f) => Some(json!({ "formulaValue": f })),
+        CellValue::Empty => None,
+    };
+    if let Some(entered) = entered {
+        data.insert("userEnteredValue".to_string(), entered);
+    }
+    if let Some(format) = &cell.format {
+        data.insert("userEnteredFormat".to_string(), format.clone());
+    }
+    Value::Object(data)
+}
+
+fn cell_at(cells: &[Vec<Cell>], row: usize, column: usize) -> Cell {
+    cells.get(row).and_then(|r| r.get(column)).cloned().unwrap_or_default()
+}
+
+// appendDimension for edits past the tab's grid
+fn grow_requests(sheet: &WorkbookSheet, before: &WorkbookSheet) -> Vec<Value> {
+    let rows = sheet.cells.len();
+    let columns = sheet.cells.iter().map(Vec::len).max().unwrap_or(0);
+    let mut requests = Vec::new();
+    for (dimension, needed, have) in [("ROWS", rows, before.row_count), ("COLUMNS", columns, before.column_count)] {
+        if needed > have {
+            requests.push(json!({ "appendDimension": { "sheetId": sheet.sheet_id, "dimension": dimension, "length": needed - have } }));
+        }
+    }
+    requests
+}